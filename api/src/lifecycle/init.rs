@@ -8,4 +8,9 @@ pub struct InitArgs {
     pub version: BuildVersion,
     pub commit_hash: String,
     pub authorized_principals: Vec<Principal>,
+    /// Previous versions kept per reuploaded file; 3 when `None`.
+    pub version_retention: Option<u32>,
+    /// Seconds a removed file stays restorable; 7 days when `None`, and 0
+    /// deletes right away.
+    pub trash_retention_secs: Option<u64>,
 }
@@ -6,4 +6,8 @@ use serde::{Deserialize, Serialize};
 pub struct UpgradeArgs {
     pub version: BuildVersion,
     pub commit_hash: String,
+    /// New number of previous versions kept per file; unchanged when `None`.
+    pub version_retention: Option<u32>,
+    /// New number of seconds a removed file stays restorable; unchanged when `None`.
+    pub trash_retention_secs: Option<u64>,
 }
@@ -0,0 +1,34 @@
+use crate::types::storage::{UploadState, Visibility};
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug)]
+pub struct Args {
+    pub file_path: String,
+}
+
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug)]
+pub struct FileMetadata {
+    pub file_path: String,
+    pub file_size: u64,
+    /// Hex-encoded SHA-256 of the file's bytes.
+    pub file_hash: String,
+    pub chunk_size: u64,
+    pub content_type: String,
+    pub state: UploadState,
+    pub init_timestamp: u64,
+    pub finalized_timestamp: Option<u64>,
+    /// Application metadata set at `init_upload` or by `set_file_metadata`.
+    pub metadata: BTreeMap<String, String>,
+    pub expires_at: Option<u64>,
+    pub visibility: Visibility,
+}
+
+pub type Response = Result<FileMetadata, GetFileMetadataError>;
+
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub enum GetFileMetadataError {
+    FileNotFound,
+    InvalidFilePath,
+}
@@ -0,0 +1,26 @@
+use crate::types::storage::UploadState;
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug)]
+pub struct Args {
+    pub file_path: String,
+}
+
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub struct FinalizationProgress {
+    pub state: UploadState,
+    pub file_size: u64,
+    /// Bytes covered by the running file hash.
+    pub hashed_size: u64,
+    /// Bytes moved into permanent storage.
+    pub stored_size: u64,
+}
+
+pub type Response = Result<FinalizationProgress, GetFinalizationProgressError>;
+
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub enum GetFinalizationProgressError {
+    FileNotFound,
+    InvalidFilePath,
+}
@@ -0,0 +1,14 @@
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+pub type Args = ();
+pub type Response = StoredFilesSize;
+
+#[derive(Serialize, Deserialize, CandidType, Debug, Clone, PartialEq)]
+pub struct StoredFilesSize {
+    /// Sum of the sizes of all files, counting each path separately. Same as
+    /// `get_stored_files_size_bytes`.
+    pub logical_size_bytes: u64,
+    /// Bytes actually held, counting content shared by several paths once.
+    pub physical_size_bytes: u64,
+}
@@ -0,0 +1,31 @@
+use crate::types::storage::UploadState;
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug)]
+pub struct Args {
+    pub file_path: String,
+}
+
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub struct UploadStatus {
+    pub state: UploadState,
+    pub file_size: u64,
+    pub received_size: u64,
+    pub chunk_size: u64,
+    pub num_chunks: u64,
+    /// Indices of the chunks the canister does not have yet, ascending.
+    pub missing_chunks: Vec<u64>,
+    pub init_timestamp: u64,
+    /// When the abandoned-upload GC may drop this upload. `None` once the
+    /// upload is finalizing or finalized.
+    pub gc_deadline: Option<u64>,
+}
+
+pub type Response = Result<UploadStatus, GetUploadStatusError>;
+
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub enum GetUploadStatusError {
+    UploadNotFound,
+    InvalidFilePath,
+}
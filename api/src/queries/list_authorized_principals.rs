@@ -0,0 +1,11 @@
+use candid::{CandidType, Principal};
+use serde::{Deserialize, Serialize};
+
+pub type Args = ();
+
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub struct ListAuthorizedPrincipalsResp {
+    pub principals: Vec<Principal>,
+}
+
+pub type Response = ListAuthorizedPrincipalsResp;
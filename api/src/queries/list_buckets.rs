@@ -0,0 +1,23 @@
+use crate::types::storage::BucketConfig;
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+pub type Args = ();
+
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug)]
+pub struct BucketInfo {
+    pub name: String,
+    pub config: BucketConfig,
+    /// Total size of the bucket's files, uploads in progress and trashed files
+    /// included.
+    pub used_bytes: u64,
+    pub file_count: u64,
+}
+
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub struct ListBucketsResp {
+    /// Buckets in name order.
+    pub buckets: Vec<BucketInfo>,
+}
+
+pub type Response = ListBucketsResp;
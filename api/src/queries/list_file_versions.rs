@@ -0,0 +1,33 @@
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug)]
+pub struct Args {
+    pub file_path: String,
+}
+
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug)]
+pub struct FileVersion {
+    pub version: u64,
+    pub file_size: u64,
+    pub file_hash: String,
+    pub content_type: String,
+    pub finalized_timestamp: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub struct ListFileVersionsResp {
+    /// Version number of the bytes currently served.
+    pub current_version: u64,
+    /// Retained previous versions, newest first. Each one can be fetched at
+    /// `<path>?version=<version>` or brought back with `restore_file_version`.
+    pub versions: Vec<FileVersion>,
+}
+
+pub type Response = Result<ListFileVersionsResp, ListFileVersionsError>;
+
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub enum ListFileVersionsError {
+    FileNotFound,
+    InvalidFilePath,
+}
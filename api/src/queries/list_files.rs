@@ -0,0 +1,38 @@
+use crate::types::storage::UploadState;
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug)]
+pub struct Args {
+    /// Only list paths starting with this prefix.
+    pub prefix: Option<String>,
+    /// `next_cursor` of the previous page; the listing resumes after this path.
+    pub cursor: Option<String>,
+    /// Page size; defaults to 100 and is capped at 1000.
+    pub limit: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug)]
+pub struct FileInfo {
+    pub file_path: String,
+    pub file_size: u64,
+    pub file_hash: String,
+    pub content_type: String,
+    pub state: UploadState,
+    pub init_timestamp: u64,
+    pub finalized_timestamp: Option<u64>,
+    /// Application metadata set at `init_upload` or by `set_file_metadata`.
+    pub metadata: BTreeMap<String, String>,
+    pub expires_at: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub struct ListFilesResp {
+    /// Files in ascending path order.
+    pub files: Vec<FileInfo>,
+    /// Pass back as `cursor` to fetch the next page; `None` on the last page.
+    pub next_cursor: Option<String>,
+}
+
+pub type Response = ListFilesResp;
@@ -0,0 +1,23 @@
+use crate::types::storage::PrincipalQuota;
+use candid::{CandidType, Principal};
+use serde::{Deserialize, Serialize};
+
+pub type Args = ();
+
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug)]
+pub struct PrincipalUsage {
+    pub principal: Principal,
+    /// Sizes of the files whose current bytes this principal uploaded,
+    /// including uploads still in progress and files in the trash.
+    pub used_bytes: u64,
+    pub file_count: u64,
+    pub quota: PrincipalQuota,
+}
+
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub struct ListPrincipalUsageResp {
+    /// Every principal that has uploaded files or has a quota.
+    pub principals: Vec<PrincipalUsage>,
+}
+
+pub type Response = ListPrincipalUsageResp;
@@ -0,0 +1,12 @@
+use crate::types::storage::RoleAssignment;
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+pub type Args = ();
+
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub struct ListRolesResp {
+    pub assignments: Vec<RoleAssignment>,
+}
+
+pub type Response = ListRolesResp;
@@ -0,0 +1,28 @@
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug)]
+pub struct Args {
+    /// Only list paths starting with this prefix.
+    pub prefix: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug)]
+pub struct TrashedFile {
+    pub file_path: String,
+    pub file_size: u64,
+    pub file_hash: String,
+    pub content_type: String,
+    /// When `remove_file` moved the file to the trash.
+    pub deleted_at: u64,
+    /// When the trash sweep deletes it for good unless restored first.
+    pub purge_at: u64,
+}
+
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub struct ListTrashResp {
+    /// Trashed files in ascending path order.
+    pub files: Vec<TrashedFile>,
+}
+
+pub type Response = ListTrashResp;
@@ -1,3 +1,14 @@
+pub mod get_file_metadata;
+pub mod get_finalization_progress;
 pub mod get_storage_size;
+pub mod get_stored_files_size;
 pub mod get_stored_files_size_bytes;
+pub mod get_upload_status;
 pub mod http_request;
+pub mod list_authorized_principals;
+pub mod list_buckets;
+pub mod list_file_versions;
+pub mod list_files;
+pub mod list_principal_usage;
+pub mod list_roles;
+pub mod list_trash;
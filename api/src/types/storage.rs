@@ -1,4 +1,4 @@
-use candid::CandidType;
+use candid::{CandidType, Principal};
 use serde::{Deserialize, Serialize};
 
 #[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq)]
@@ -6,8 +6,87 @@ pub enum UploadState {
     Init,
     ReuploadInit,
     InProgress,
+    Finalizing,
     Finalized,
     InitReupload,
     ChunkReupload,
     FinalizeReupload,
+    /// Finalization stopped with the given reason. Kept until the path is
+    /// uploaded again, the upload is cancelled, or the GC removes it.
+    Failed(String),
+}
+
+/// Compression of a pre-encoded variant of a file, stored at the file's path
+/// plus `.br` or `.gz`.
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum ContentEncoding {
+    Brotli,
+    Gzip,
+}
+
+/// Limits on what a single principal may upload. `None` means unlimited.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct PrincipalQuota {
+    pub max_bytes: Option<u64>,
+    pub max_files: Option<u64>,
+}
+
+/// Settings of a bucket, the namespace of paths under `/<name>/`.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct BucketConfig {
+    /// Principals allowed to upload and remove files in the bucket, on top of
+    /// the canister's authorized principals.
+    pub writers: Vec<Principal>,
+    /// Cap on the total size of the bucket's files. `None` means unlimited.
+    pub max_bytes: Option<u64>,
+    /// Response headers for files of the bucket that have none of their own.
+    /// Same names allowed as for `init_upload`.
+    pub headers: Vec<(String, String)>,
+    /// Host names, e.g. custom domains, whose requests are served from the
+    /// bucket: `https://<host>/<path>` is the file at `/<name>/<path>`. Those
+    /// responses come from stable memory, like on the raw domain.
+    pub hosts: Vec<String>,
+}
+
+/// Why a caller can't act on a bucket-scoped path.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum BucketAccessError {
+    BucketNotFound,
+    /// The caller is not one of the bucket's writers and holds no role
+    /// allowing the call on the path.
+    NotAuthorized,
+}
+
+/// What a role lets its holder do with files. Authorized principals hold
+/// every role on every path.
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Role {
+    /// Everything below, and, when not scoped to a path prefix, the management
+    /// endpoints (roles, quotas, buckets).
+    Admin,
+    /// Upload new files. Existing files can't be replaced.
+    Uploader,
+    /// Reupload, move, restore versions and change metadata, headers or expiry.
+    Editor,
+    /// Remove files, and restore or purge them from the trash.
+    Deleter,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RoleAssignment {
+    pub principal: Principal,
+    pub role: Role,
+    /// Restricts the role to the files under this directory, e.g. `/images`.
+    /// `None` grants it on every path.
+    pub path_prefix: Option<String>,
+}
+
+/// Who may read a file over HTTP.
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Visibility {
+    #[default]
+    Public,
+    /// Served only from the raw domain, to requests carrying a token from
+    /// `create_download_token`, and never certified.
+    Private,
 }
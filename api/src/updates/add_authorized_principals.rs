@@ -0,0 +1,13 @@
+use candid::{CandidType, Principal};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug)]
+pub struct Args {
+    /// Principals already authorized are left as they are.
+    pub principals: Vec<Principal>,
+}
+
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub struct AddAuthorizedPrincipalsResp {}
+
+pub type Response = AddAuthorizedPrincipalsResp;
@@ -0,0 +1,46 @@
+use crate::types::storage::BucketAccessError;
+use crate::updates::finalize_upload::{FinalizeUploadError, FinalizeUploadResp};
+use crate::updates::init_upload::InitUploadError;
+use crate::updates::store_chunk::StoreChunkError;
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug)]
+pub struct Args {
+    pub entries: Vec<BatchUploadEntry>,
+    /// Bucket every entry's path is relative to; `None` for the canister-wide
+    /// namespace.
+    pub bucket: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug)]
+pub struct BatchUploadEntry {
+    pub file_path: String,
+    pub content: Vec<u8>,
+    /// Hex-encoded SHA-256 of `content`.
+    pub expected_hash: String,
+    /// Served as `content-type`. Guessed from the file extension when `None`.
+    pub content_type: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub struct BatchUploadResp {
+    /// One result per entry, in request order.
+    pub results: Vec<Result<FinalizeUploadResp, BatchUploadEntryError>>,
+}
+
+pub type Response = Result<BatchUploadResp, BatchUploadError>;
+
+/// Why a single entry was rejected, by the upload step that failed.
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub enum BatchUploadEntryError {
+    InitUpload(InitUploadError),
+    StoreChunk(StoreChunkError),
+    FinalizeUpload(FinalizeUploadError),
+}
+
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub enum BatchUploadError {
+    TooManyEntries,
+    BucketAccess(BucketAccessError),
+}
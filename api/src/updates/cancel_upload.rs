@@ -1,9 +1,12 @@
+use crate::types::storage::BucketAccessError;
 use candid::CandidType;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, CandidType, Clone, Debug)]
 pub struct Args {
     pub file_path: String,
+    /// Bucket the path is relative to; `None` for the canister-wide namespace.
+    pub bucket: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, CandidType, Debug)]
@@ -16,4 +19,5 @@ pub enum CancelUploadError {
     UploadNotInitialized,
     UploadAlreadyFinalized,
     InvalidFilePath,
+    BucketAccess(BucketAccessError),
 }
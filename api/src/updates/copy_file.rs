@@ -0,0 +1,34 @@
+use crate::types::storage::BucketAccessError;
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug)]
+pub struct Args {
+    pub from: String,
+    pub to: String,
+    /// Replace a finalized file already at `to`. Without it the copy fails with
+    /// `DestinationExists`.
+    pub overwrite: bool,
+    /// Bucket both paths are relative to; `None` for the canister-wide
+    /// namespace.
+    pub bucket: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub struct CopyFileResp {}
+
+pub type Response = Result<CopyFileResp, CopyFileError>;
+
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub enum CopyFileError {
+    FileNotFound,
+    DestinationExists,
+    UploadInProgress,
+    NotEnoughStorage,
+    TooManyFiles,
+    /// The caller's byte or file-count quota or the bucket's size limit would
+    /// be exceeded.
+    QuotaExceeded,
+    InvalidFilePath,
+    BucketAccess(BucketAccessError),
+}
@@ -0,0 +1,28 @@
+use crate::types::storage::BucketConfig;
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug)]
+pub struct Args {
+    /// 1 to 63 lowercase letters, digits and inner dashes. No file, upload or
+    /// trashed file may be at a path under `/<name>/` yet.
+    pub name: String,
+    pub config: BucketConfig,
+}
+
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub struct CreateBucketResp {}
+
+pub type Response = Result<CreateBucketResp, CreateBucketError>;
+
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub enum CreateBucketError {
+    BucketAlreadyExists,
+    InvalidBucketName,
+    InvalidHeaders,
+    InvalidHost,
+    /// Another bucket already serves one of the hosts.
+    HostInUse,
+    /// Files are already stored under the bucket's prefix.
+    PrefixInUse,
+}
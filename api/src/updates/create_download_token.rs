@@ -0,0 +1,29 @@
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug)]
+pub struct Args {
+    pub file_path: String,
+    /// Time (ns since the epoch) until which the token is accepted.
+    pub expires_at: u64,
+}
+
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub struct CreateDownloadTokenResp {
+    /// Query string granting access to the file on the raw domain:
+    /// `https://<canister>.raw.icp0.io/<path>?<query>`. Valid for this path
+    /// only, and for public files too, which don't need it.
+    pub query: String,
+}
+
+pub type Response = Result<CreateDownloadTokenResp, CreateDownloadTokenError>;
+
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub enum CreateDownloadTokenError {
+    FileNotFound,
+    InvalidFilePath,
+    InvalidExpiresAt,
+    NotAuthorized,
+    /// The signing key could not be generated. Retrying may succeed.
+    KeyUnavailable(String),
+}
@@ -0,0 +1,34 @@
+use candid::{CandidType, Principal};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug)]
+pub struct Args {
+    /// Principal allowed to call `init_upload`, `store_chunk`,
+    /// `finalize_upload` and `cancel_upload` with the ticket, without a bucket.
+    pub holder: Principal,
+    /// Path of the file the holder may upload, or the directory under which
+    /// it may upload files when `is_prefix` is set.
+    pub file_path: String,
+    pub is_prefix: bool,
+    /// Total size of the files the holder may start uploading, cancelled
+    /// uploads included.
+    pub max_bytes: u64,
+    /// Time (ns since the epoch) after which the ticket is no longer accepted,
+    /// uploads in progress included.
+    pub expires_at: u64,
+}
+
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub struct CreateUploadTicketResp {
+    pub ticket_id: u64,
+}
+
+pub type Response = Result<CreateUploadTicketResp, CreateUploadTicketError>;
+
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub enum CreateUploadTicketError {
+    InvalidFilePath,
+    InvalidExpiresAt,
+    /// The caller may not upload to the path itself.
+    NotAuthorized,
+}
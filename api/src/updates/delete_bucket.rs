@@ -0,0 +1,19 @@
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug)]
+pub struct Args {
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub struct DeleteBucketResp {}
+
+pub type Response = Result<DeleteBucketResp, DeleteBucketError>;
+
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub enum DeleteBucketError {
+    BucketNotFound,
+    /// Files, uploads or trashed files remain under the bucket's paths.
+    BucketNotEmpty,
+}
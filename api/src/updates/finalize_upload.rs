@@ -1,14 +1,22 @@
+use crate::types::storage::{BucketAccessError, UploadState};
 use candid::CandidType;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, CandidType, Clone, Debug)]
 pub struct Args {
     pub file_path: String,
+    /// Bucket the path is relative to; `None` for the canister-wide namespace.
+    pub bucket: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, CandidType, Debug)]
 pub struct FinalizeUploadResp {
     pub url: String,
+    /// `Finalizing` when the file is large enough that finalization continues
+    /// in the background; poll `get_finalization_progress` until `Finalized`.
+    /// `None` from canisters that predate background finalization, which are
+    /// always done when they reply.
+    pub state: Option<UploadState>,
 }
 
 pub type Response = Result<FinalizeUploadResp, FinalizeUploadError>;
@@ -22,4 +30,5 @@ pub enum FinalizeUploadError {
     FileSizeMismatch,
     FileHashMismatch,
     InvalidFilePath,
+    BucketAccess(BucketAccessError),
 }
@@ -0,0 +1,16 @@
+use crate::types::storage::RoleAssignment;
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+pub type Args = RoleAssignment;
+
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub struct GrantRoleResp {}
+
+pub type Response = Result<GrantRoleResp, GrantRoleError>;
+
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub enum GrantRoleError {
+    AlreadyGranted,
+    InvalidPathPrefix,
+}
@@ -1,3 +1,4 @@
+use crate::types::storage::BucketAccessError;
 use candid::CandidType;
 use serde::{Deserialize, Serialize};
 
@@ -7,6 +8,17 @@ pub struct Args {
     pub file_hash: String,
     pub file_size: u64,
     pub chunk_size: Option<u64>,
+    /// Indices of the chunks that will be sent. Every other chunk is taken from
+    /// the file's current bytes, and `file_size` may then differ from the
+    /// current size. `None` means the whole file is re-sent at the same size.
+    pub changed_chunks: Option<Vec<u64>>,
+    /// MIME type to serve the new bytes with. `None` keeps the current one.
+    pub content_type: Option<String>,
+    /// Response headers for the new bytes, as in `init_upload`. `None` keeps
+    /// the current ones.
+    pub headers: Option<Vec<(String, String)>>,
+    /// Bucket the path is relative to; `None` for the canister-wide namespace.
+    pub bucket: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, CandidType, Debug)]
@@ -19,8 +31,14 @@ pub enum InitReuploadError {
     FileNotFound,
     FileSizeMismatch,
     NotEnoughStorage,
+    /// The caller's byte quota would be exceeded.
+    QuotaExceeded,
     InvalidChunkSize,
     InvalidFilePath,
     TooManyChunks,
     TooManyFiles,
+    InvalidChangedChunks,
+    InvalidContentType,
+    InvalidHeaders,
+    BucketAccess(BucketAccessError),
 }
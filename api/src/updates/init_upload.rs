@@ -1,5 +1,7 @@
+use crate::types::storage::{BucketAccessError, ContentEncoding, Visibility};
 use candid::CandidType;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 #[derive(Serialize, Deserialize, CandidType, Clone, Debug)]
 pub struct Args {
@@ -7,10 +9,43 @@ pub struct Args {
     pub file_hash: String,
     pub file_size: u64,
     pub chunk_size: Option<u64>,
+    /// Hex-encoded SHA-256 of every chunk, in order. When set, each
+    /// `store_chunk` is checked against it.
+    pub chunk_hashes: Option<Vec<String>>,
+    /// Application key/value metadata kept with the file. At most 16 entries,
+    /// non-empty keys of up to 64 bytes, and 1 KiB for all keys and values
+    /// together.
+    pub metadata: Option<BTreeMap<String, String>>,
+    /// MIME type to serve the file with. Guessed from the path extension
+    /// when `None`.
+    pub content_type: Option<String>,
+    /// Response headers for this file, overriding the defaults. Allowed names
+    /// are `cache-control`, `content-disposition`, `content-language`,
+    /// `x-robots-tag` and `x-content-version`, each at most once.
+    pub headers: Option<Vec<(String, String)>>,
+    /// Upload a pre-compressed variant of an existing finalized file instead
+    /// of a file of its own. `file_path` must be that file's path plus `.br`
+    /// or `.gz`, and `file_hash` is the hash of the compressed bytes. The
+    /// variant is served for the file to clients that accept the encoding.
+    pub encoding: Option<ContentEncoding>,
+    /// Time (ns since the epoch) after which the file is no longer served and
+    /// gets deleted. Must be in the future; kept across reuploads and changed
+    /// with `set_file_expiry`.
+    pub expires_at: Option<u64>,
+    /// `None` makes the file public. Kept across reuploads and changed with
+    /// `set_file_visibility`; variants follow the file they encode.
+    pub visibility: Option<Visibility>,
+    /// Bucket the path is relative to; `None` for the canister-wide namespace.
+    pub bucket: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, CandidType, Debug)]
-pub struct InitUploadResp {}
+pub struct InitUploadResp {
+    /// The canister already stores content with this `file_hash`. The file is
+    /// finalized right away and no chunks must be sent. `None` from canisters
+    /// that predate deduplication, which never skip the chunks.
+    pub deduplicated: Option<bool>,
+}
 
 pub type Response = Result<InitUploadResp, InitUploadError>;
 
@@ -19,8 +54,19 @@ pub enum InitUploadError {
     ConcurrentManagementCall,
     FileAlreadyExists,
     NotEnoughStorage,
+    /// The caller's byte or file-count quota, the bucket's size limit or what
+    /// is left on the caller's upload ticket would be exceeded.
+    QuotaExceeded,
     InvalidChunkSize,
     InvalidFilePath,
     TooManyChunks,
     TooManyFiles,
+    InvalidChunkHashes,
+    InvalidMetadata,
+    InvalidContentType,
+    InvalidHeaders,
+    InvalidEncoding,
+    BaseFileNotFound,
+    InvalidExpiresAt,
+    BucketAccess(BucketAccessError),
 }
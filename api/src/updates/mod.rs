@@ -1,6 +1,27 @@
+pub mod add_authorized_principals;
+pub mod batch_upload;
 pub mod cancel_upload;
+pub mod copy_file;
+pub mod create_bucket;
+pub mod create_download_token;
+pub mod create_upload_ticket;
+pub mod delete_bucket;
 pub mod finalize_upload;
+pub mod grant_role;
 pub mod init_reupload;
 pub mod init_upload;
+pub mod move_file;
+pub mod purge_file;
+pub mod remove_authorized_principals;
 pub mod remove_file;
+pub mod restore_file;
+pub mod restore_file_version;
+pub mod revoke_role;
+pub mod revoke_upload_ticket;
+pub mod set_file_expiry;
+pub mod set_file_headers;
+pub mod set_file_metadata;
+pub mod set_file_visibility;
+pub mod set_principal_quota;
 pub mod store_chunk;
+pub mod update_bucket;
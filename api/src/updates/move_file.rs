@@ -0,0 +1,31 @@
+use crate::types::storage::BucketAccessError;
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug)]
+pub struct Args {
+    pub from: String,
+    pub to: String,
+    /// Replace a finalized file already at `to`. Without it the move fails with
+    /// `DestinationExists`.
+    pub overwrite: bool,
+    /// Bucket both paths are relative to; `None` for the canister-wide
+    /// namespace.
+    pub bucket: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub struct MoveFileResp {}
+
+pub type Response = Result<MoveFileResp, MoveFileError>;
+
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub enum MoveFileError {
+    FileNotFound,
+    DestinationExists,
+    UploadInProgress,
+    /// The size limit of the destination's bucket would be exceeded.
+    QuotaExceeded,
+    InvalidFilePath,
+    BucketAccess(BucketAccessError),
+}
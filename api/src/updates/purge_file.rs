@@ -0,0 +1,19 @@
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug)]
+pub struct Args {
+    pub file_path: String,
+}
+
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub struct PurgeFileResp {}
+
+pub type Response = Result<PurgeFileResp, PurgeFileError>;
+
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub enum PurgeFileError {
+    NotInTrash,
+    InvalidFilePath,
+    NotAuthorized,
+}
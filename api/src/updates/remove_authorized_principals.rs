@@ -0,0 +1,20 @@
+use candid::{CandidType, Principal};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug)]
+pub struct Args {
+    pub principals: Vec<Principal>,
+}
+
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub struct RemoveAuthorizedPrincipalsResp {}
+
+pub type Response = Result<RemoveAuthorizedPrincipalsResp, RemoveAuthorizedPrincipalsError>;
+
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub enum RemoveAuthorizedPrincipalsError {
+    PrincipalNotFound(Principal),
+    /// No admin would be left: neither an authorized principal nor a holder
+    /// of an Admin role not scoped to a path.
+    LastAdmin,
+}
@@ -0,0 +1,24 @@
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug)]
+pub struct Args {
+    pub file_path: String,
+}
+
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub struct RestoreFileResp {}
+
+pub type Response = Result<RestoreFileResp, RestoreFileError>;
+
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub enum RestoreFileError {
+    NotInTrash,
+    /// A file or an upload now occupies the path.
+    DestinationExists,
+    TooManyFiles,
+    /// The caller's byte or file-count quota would be exceeded.
+    QuotaExceeded,
+    InvalidFilePath,
+    NotAuthorized,
+}
@@ -0,0 +1,29 @@
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug)]
+pub struct Args {
+    pub file_path: String,
+    /// A version listed by `list_file_versions`.
+    pub version: u64,
+}
+
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub struct RestoreFileVersionResp {
+    /// The restored bytes are served as a new version, so the one they replace
+    /// stays in the history.
+    pub version: u64,
+}
+
+pub type Response = Result<RestoreFileVersionResp, RestoreFileVersionError>;
+
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub enum RestoreFileVersionError {
+    FileNotFound,
+    VersionNotFound,
+    UploadInProgress,
+    /// The caller's byte or file-count quota would be exceeded.
+    QuotaExceeded,
+    InvalidFilePath,
+    NotAuthorized,
+}
@@ -0,0 +1,16 @@
+use crate::types::storage::RoleAssignment;
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+/// Must match the granted assignment, path prefix included.
+pub type Args = RoleAssignment;
+
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub struct RevokeRoleResp {}
+
+pub type Response = Result<RevokeRoleResp, RevokeRoleError>;
+
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub enum RevokeRoleError {
+    RoleNotFound,
+}
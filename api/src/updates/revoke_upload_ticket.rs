@@ -0,0 +1,19 @@
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug)]
+pub struct Args {
+    pub ticket_id: u64,
+}
+
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub struct RevokeUploadTicketResp {}
+
+pub type Response = Result<RevokeUploadTicketResp, RevokeUploadTicketError>;
+
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub enum RevokeUploadTicketError {
+    TicketNotFound,
+    /// Only the ticket's issuer and admins may revoke it.
+    NotAuthorized,
+}
@@ -0,0 +1,23 @@
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug)]
+pub struct Args {
+    pub file_path: String,
+    /// New expiry time (ns since the epoch), which must be in the future.
+    /// `None` keeps the file until removed.
+    pub expires_at: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub struct SetFileExpiryResp {}
+
+pub type Response = Result<SetFileExpiryResp, SetFileExpiryError>;
+
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub enum SetFileExpiryError {
+    FileNotFound,
+    InvalidFilePath,
+    InvalidExpiresAt,
+    NotAuthorized,
+}
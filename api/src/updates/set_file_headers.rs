@@ -0,0 +1,23 @@
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug)]
+pub struct Args {
+    pub file_path: String,
+    /// Replaces the file's response headers; an empty list restores the
+    /// defaults. Same rules as `init_upload::Args::headers`.
+    pub headers: Vec<(String, String)>,
+}
+
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub struct SetFileHeadersResp {}
+
+pub type Response = Result<SetFileHeadersResp, SetFileHeadersError>;
+
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub enum SetFileHeadersError {
+    FileNotFound,
+    InvalidFilePath,
+    InvalidHeaders,
+    NotAuthorized,
+}
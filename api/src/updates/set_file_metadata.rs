@@ -0,0 +1,24 @@
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug)]
+pub struct Args {
+    pub file_path: String,
+    /// Replaces the file's whole metadata map; an empty map clears it. Same
+    /// limits as `init_upload::Args::metadata`.
+    pub metadata: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub struct SetFileMetadataResp {}
+
+pub type Response = Result<SetFileMetadataResp, SetFileMetadataError>;
+
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub enum SetFileMetadataError {
+    FileNotFound,
+    InvalidFilePath,
+    InvalidMetadata,
+    NotAuthorized,
+}
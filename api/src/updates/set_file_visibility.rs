@@ -0,0 +1,23 @@
+use crate::types::storage::Visibility;
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug)]
+pub struct Args {
+    pub file_path: String,
+    pub visibility: Visibility,
+}
+
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub struct SetFileVisibilityResp {}
+
+pub type Response = Result<SetFileVisibilityResp, SetFileVisibilityError>;
+
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub enum SetFileVisibilityError {
+    FileNotFound,
+    InvalidFilePath,
+    /// Variants follow the visibility of the file they encode.
+    EncodedVariant,
+    NotAuthorized,
+}
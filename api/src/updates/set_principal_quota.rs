@@ -0,0 +1,16 @@
+use crate::types::storage::PrincipalQuota;
+use candid::{CandidType, Principal};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug)]
+pub struct Args {
+    pub principal: Principal,
+    /// Replaces the principal's quota; both limits `None` removes it. Files
+    /// already uploaded are kept even if over the new limits.
+    pub quota: PrincipalQuota,
+}
+
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub struct SetPrincipalQuotaResp {}
+
+pub type Response = SetPrincipalQuotaResp;
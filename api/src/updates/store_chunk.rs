@@ -1,3 +1,4 @@
+use crate::types::storage::BucketAccessError;
 use candid::{CandidType, Nat};
 use serde::{Deserialize, Serialize};
 
@@ -6,6 +7,11 @@ pub struct Args {
     pub file_path: String,
     pub chunk_id: Nat,
     pub chunk_data: Vec<u8>,
+    /// Hex-encoded SHA-256 of `chunk_data`. When set, a chunk that does not
+    /// match is rejected with `ChunkHashMismatch` and can be resent on its own.
+    pub chunk_hash: Option<String>,
+    /// Bucket the path is relative to; `None` for the canister-wide namespace.
+    pub bucket: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, CandidType, Debug)]
@@ -19,8 +25,13 @@ pub enum StoreChunkError {
     UploadAlreadyFinalized,
     InvalidChunkId,
     InvalidChunkData,
+    /// On a partial reupload, the chunk is not `chunk_size` bytes long or, for
+    /// the last chunk, not the rest of the file.
+    InvalidChunkSize,
     InvalidFilePath,
     InvalidFileSize,
     InvalidFileHash,
     InvalidFileFormat,
+    ChunkHashMismatch,
+    BucketAccess(BucketAccessError),
 }
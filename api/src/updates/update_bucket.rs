@@ -0,0 +1,25 @@
+use crate::types::storage::BucketConfig;
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug)]
+pub struct Args {
+    pub name: String,
+    /// Replaces the bucket's whole configuration. A lower `max_bytes` only
+    /// applies to later uploads.
+    pub config: BucketConfig,
+}
+
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub struct UpdateBucketResp {}
+
+pub type Response = Result<UpdateBucketResp, UpdateBucketError>;
+
+#[derive(Serialize, Deserialize, CandidType, Debug)]
+pub enum UpdateBucketError {
+    BucketNotFound,
+    InvalidHeaders,
+    InvalidHost,
+    /// Another bucket already serves one of the hosts.
+    HostInUse,
+}
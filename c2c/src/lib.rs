@@ -1,11 +1,43 @@
+use bity_ic_storage_canister_api::add_authorized_principals;
+use bity_ic_storage_canister_api::batch_upload;
 use bity_ic_storage_canister_api::cancel_upload;
+use bity_ic_storage_canister_api::copy_file;
+use bity_ic_storage_canister_api::create_bucket;
+use bity_ic_storage_canister_api::create_download_token;
+use bity_ic_storage_canister_api::create_upload_ticket;
+use bity_ic_storage_canister_api::delete_bucket;
 use bity_ic_storage_canister_api::finalize_upload;
+use bity_ic_storage_canister_api::get_file_metadata;
+use bity_ic_storage_canister_api::get_finalization_progress;
 use bity_ic_storage_canister_api::get_storage_size;
+use bity_ic_storage_canister_api::get_stored_files_size;
 use bity_ic_storage_canister_api::get_stored_files_size_bytes;
+use bity_ic_storage_canister_api::get_upload_status;
+use bity_ic_storage_canister_api::grant_role;
 use bity_ic_storage_canister_api::init_reupload;
 use bity_ic_storage_canister_api::init_upload;
+use bity_ic_storage_canister_api::list_authorized_principals;
+use bity_ic_storage_canister_api::list_buckets;
+use bity_ic_storage_canister_api::list_file_versions;
+use bity_ic_storage_canister_api::list_files;
+use bity_ic_storage_canister_api::list_principal_usage;
+use bity_ic_storage_canister_api::list_roles;
+use bity_ic_storage_canister_api::list_trash;
+use bity_ic_storage_canister_api::move_file;
+use bity_ic_storage_canister_api::purge_file;
+use bity_ic_storage_canister_api::remove_authorized_principals;
 use bity_ic_storage_canister_api::remove_file;
+use bity_ic_storage_canister_api::restore_file;
+use bity_ic_storage_canister_api::restore_file_version;
+use bity_ic_storage_canister_api::revoke_role;
+use bity_ic_storage_canister_api::revoke_upload_ticket;
+use bity_ic_storage_canister_api::set_file_expiry;
+use bity_ic_storage_canister_api::set_file_headers;
+use bity_ic_storage_canister_api::set_file_metadata;
+use bity_ic_storage_canister_api::set_file_visibility;
+use bity_ic_storage_canister_api::set_principal_quota;
 use bity_ic_storage_canister_api::store_chunk;
+use bity_ic_storage_canister_api::update_bucket;
 
 pub async fn get_storage_size(
     canister_id: candid::Principal,
@@ -35,6 +67,34 @@ pub async fn get_stored_files_size_bytes(
         .map_err(|e| format!("Failed to decode response: {:?}", e))
 }
 
+pub async fn get_finalization_progress(
+    canister_id: candid::Principal,
+    args: get_finalization_progress::Args,
+) -> Result<get_finalization_progress::Response, String> {
+    let response = ic_cdk::call::Call::unbounded_wait(canister_id, "get_finalization_progress")
+        .with_arg(args)
+        .await
+        .map_err(|e| format!("Call failed: {:?}", e))?;
+
+    response
+        .candid::<get_finalization_progress::Response>()
+        .map_err(|e| format!("Failed to decode response: {:?}", e))
+}
+
+pub async fn get_upload_status(
+    canister_id: candid::Principal,
+    args: get_upload_status::Args,
+) -> Result<get_upload_status::Response, String> {
+    let response = ic_cdk::call::Call::unbounded_wait(canister_id, "get_upload_status")
+        .with_arg(args)
+        .await
+        .map_err(|e| format!("Call failed: {:?}", e))?;
+
+    response
+        .candid::<get_upload_status::Response>()
+        .map_err(|e| format!("Failed to decode response: {:?}", e))
+}
+
 pub async fn init_upload(
     canister_id: candid::Principal,
     args: init_upload::Args,
@@ -118,3 +178,423 @@ pub async fn remove_file(
         .candid::<remove_file::Response>()
         .map_err(|e| format!("Failed to decode response: {:?}", e))
 }
+
+pub async fn move_file(
+    canister_id: candid::Principal,
+    args: move_file::Args,
+) -> Result<move_file::Response, String> {
+    let response = ic_cdk::call::Call::unbounded_wait(canister_id, "move_file")
+        .with_arg(args)
+        .await
+        .map_err(|e| format!("Call failed: {:?}", e))?;
+
+    response
+        .candid::<move_file::Response>()
+        .map_err(|e| format!("Failed to decode response: {:?}", e))
+}
+
+pub async fn copy_file(
+    canister_id: candid::Principal,
+    args: copy_file::Args,
+) -> Result<copy_file::Response, String> {
+    let response = ic_cdk::call::Call::unbounded_wait(canister_id, "copy_file")
+        .with_arg(args)
+        .await
+        .map_err(|e| format!("Call failed: {:?}", e))?;
+
+    response
+        .candid::<copy_file::Response>()
+        .map_err(|e| format!("Failed to decode response: {:?}", e))
+}
+
+pub async fn batch_upload(
+    canister_id: candid::Principal,
+    args: batch_upload::Args,
+) -> Result<batch_upload::Response, String> {
+    let response = ic_cdk::call::Call::unbounded_wait(canister_id, "batch_upload")
+        .with_arg(args)
+        .await
+        .map_err(|e| format!("Call failed: {:?}", e))?;
+
+    response
+        .candid::<batch_upload::Response>()
+        .map_err(|e| format!("Failed to decode response: {:?}", e))
+}
+
+pub async fn list_files(
+    canister_id: candid::Principal,
+    args: list_files::Args,
+) -> Result<list_files::Response, String> {
+    let response = ic_cdk::call::Call::unbounded_wait(canister_id, "list_files")
+        .with_arg(args)
+        .await
+        .map_err(|e| format!("Call failed: {:?}", e))?;
+
+    response
+        .candid::<list_files::Response>()
+        .map_err(|e| format!("Failed to decode response: {:?}", e))
+}
+
+pub async fn get_file_metadata(
+    canister_id: candid::Principal,
+    args: get_file_metadata::Args,
+) -> Result<get_file_metadata::Response, String> {
+    let response = ic_cdk::call::Call::unbounded_wait(canister_id, "get_file_metadata")
+        .with_arg(args)
+        .await
+        .map_err(|e| format!("Call failed: {:?}", e))?;
+
+    response
+        .candid::<get_file_metadata::Response>()
+        .map_err(|e| format!("Failed to decode response: {:?}", e))
+}
+
+pub async fn set_file_metadata(
+    canister_id: candid::Principal,
+    args: set_file_metadata::Args,
+) -> Result<set_file_metadata::Response, String> {
+    let response = ic_cdk::call::Call::unbounded_wait(canister_id, "set_file_metadata")
+        .with_arg(args)
+        .await
+        .map_err(|e| format!("Call failed: {:?}", e))?;
+
+    response
+        .candid::<set_file_metadata::Response>()
+        .map_err(|e| format!("Failed to decode response: {:?}", e))
+}
+
+pub async fn set_file_headers(
+    canister_id: candid::Principal,
+    args: set_file_headers::Args,
+) -> Result<set_file_headers::Response, String> {
+    let response = ic_cdk::call::Call::unbounded_wait(canister_id, "set_file_headers")
+        .with_arg(args)
+        .await
+        .map_err(|e| format!("Call failed: {:?}", e))?;
+
+    response
+        .candid::<set_file_headers::Response>()
+        .map_err(|e| format!("Failed to decode response: {:?}", e))
+}
+
+pub async fn list_file_versions(
+    canister_id: candid::Principal,
+    args: list_file_versions::Args,
+) -> Result<list_file_versions::Response, String> {
+    let response = ic_cdk::call::Call::unbounded_wait(canister_id, "list_file_versions")
+        .with_arg(args)
+        .await
+        .map_err(|e| format!("Call failed: {:?}", e))?;
+
+    response
+        .candid::<list_file_versions::Response>()
+        .map_err(|e| format!("Failed to decode response: {:?}", e))
+}
+
+pub async fn restore_file_version(
+    canister_id: candid::Principal,
+    args: restore_file_version::Args,
+) -> Result<restore_file_version::Response, String> {
+    let response = ic_cdk::call::Call::unbounded_wait(canister_id, "restore_file_version")
+        .with_arg(args)
+        .await
+        .map_err(|e| format!("Call failed: {:?}", e))?;
+
+    response
+        .candid::<restore_file_version::Response>()
+        .map_err(|e| format!("Failed to decode response: {:?}", e))
+}
+
+pub async fn list_trash(
+    canister_id: candid::Principal,
+    args: list_trash::Args,
+) -> Result<list_trash::Response, String> {
+    let response = ic_cdk::call::Call::unbounded_wait(canister_id, "list_trash")
+        .with_arg(args)
+        .await
+        .map_err(|e| format!("Call failed: {:?}", e))?;
+
+    response
+        .candid::<list_trash::Response>()
+        .map_err(|e| format!("Failed to decode response: {:?}", e))
+}
+
+pub async fn restore_file(
+    canister_id: candid::Principal,
+    args: restore_file::Args,
+) -> Result<restore_file::Response, String> {
+    let response = ic_cdk::call::Call::unbounded_wait(canister_id, "restore_file")
+        .with_arg(args)
+        .await
+        .map_err(|e| format!("Call failed: {:?}", e))?;
+
+    response
+        .candid::<restore_file::Response>()
+        .map_err(|e| format!("Failed to decode response: {:?}", e))
+}
+
+pub async fn purge_file(
+    canister_id: candid::Principal,
+    args: purge_file::Args,
+) -> Result<purge_file::Response, String> {
+    let response = ic_cdk::call::Call::unbounded_wait(canister_id, "purge_file")
+        .with_arg(args)
+        .await
+        .map_err(|e| format!("Call failed: {:?}", e))?;
+
+    response
+        .candid::<purge_file::Response>()
+        .map_err(|e| format!("Failed to decode response: {:?}", e))
+}
+
+pub async fn set_file_expiry(
+    canister_id: candid::Principal,
+    args: set_file_expiry::Args,
+) -> Result<set_file_expiry::Response, String> {
+    let response = ic_cdk::call::Call::unbounded_wait(canister_id, "set_file_expiry")
+        .with_arg(args)
+        .await
+        .map_err(|e| format!("Call failed: {:?}", e))?;
+
+    response
+        .candid::<set_file_expiry::Response>()
+        .map_err(|e| format!("Failed to decode response: {:?}", e))
+}
+
+pub async fn list_principal_usage(
+    canister_id: candid::Principal,
+    args: list_principal_usage::Args,
+) -> Result<list_principal_usage::Response, String> {
+    let response = ic_cdk::call::Call::unbounded_wait(canister_id, "list_principal_usage")
+        .with_arg(args)
+        .await
+        .map_err(|e| format!("Call failed: {:?}", e))?;
+
+    response
+        .candid::<list_principal_usage::Response>()
+        .map_err(|e| format!("Failed to decode response: {:?}", e))
+}
+
+pub async fn set_principal_quota(
+    canister_id: candid::Principal,
+    args: set_principal_quota::Args,
+) -> Result<set_principal_quota::Response, String> {
+    let response = ic_cdk::call::Call::unbounded_wait(canister_id, "set_principal_quota")
+        .with_arg(args)
+        .await
+        .map_err(|e| format!("Call failed: {:?}", e))?;
+
+    response
+        .candid::<set_principal_quota::Response>()
+        .map_err(|e| format!("Failed to decode response: {:?}", e))
+}
+
+pub async fn create_bucket(
+    canister_id: candid::Principal,
+    args: create_bucket::Args,
+) -> Result<create_bucket::Response, String> {
+    let response = ic_cdk::call::Call::unbounded_wait(canister_id, "create_bucket")
+        .with_arg(args)
+        .await
+        .map_err(|e| format!("Call failed: {:?}", e))?;
+
+    response
+        .candid::<create_bucket::Response>()
+        .map_err(|e| format!("Failed to decode response: {:?}", e))
+}
+
+pub async fn update_bucket(
+    canister_id: candid::Principal,
+    args: update_bucket::Args,
+) -> Result<update_bucket::Response, String> {
+    let response = ic_cdk::call::Call::unbounded_wait(canister_id, "update_bucket")
+        .with_arg(args)
+        .await
+        .map_err(|e| format!("Call failed: {:?}", e))?;
+
+    response
+        .candid::<update_bucket::Response>()
+        .map_err(|e| format!("Failed to decode response: {:?}", e))
+}
+
+pub async fn delete_bucket(
+    canister_id: candid::Principal,
+    args: delete_bucket::Args,
+) -> Result<delete_bucket::Response, String> {
+    let response = ic_cdk::call::Call::unbounded_wait(canister_id, "delete_bucket")
+        .with_arg(args)
+        .await
+        .map_err(|e| format!("Call failed: {:?}", e))?;
+
+    response
+        .candid::<delete_bucket::Response>()
+        .map_err(|e| format!("Failed to decode response: {:?}", e))
+}
+
+pub async fn list_buckets(
+    canister_id: candid::Principal,
+    args: list_buckets::Args,
+) -> Result<list_buckets::Response, String> {
+    let response = ic_cdk::call::Call::unbounded_wait(canister_id, "list_buckets")
+        .with_arg(args)
+        .await
+        .map_err(|e| format!("Call failed: {:?}", e))?;
+
+    response
+        .candid::<list_buckets::Response>()
+        .map_err(|e| format!("Failed to decode response: {:?}", e))
+}
+
+pub async fn grant_role(
+    canister_id: candid::Principal,
+    args: grant_role::Args,
+) -> Result<grant_role::Response, String> {
+    let response = ic_cdk::call::Call::unbounded_wait(canister_id, "grant_role")
+        .with_arg(args)
+        .await
+        .map_err(|e| format!("Call failed: {:?}", e))?;
+
+    response
+        .candid::<grant_role::Response>()
+        .map_err(|e| format!("Failed to decode response: {:?}", e))
+}
+
+pub async fn revoke_role(
+    canister_id: candid::Principal,
+    args: revoke_role::Args,
+) -> Result<revoke_role::Response, String> {
+    let response = ic_cdk::call::Call::unbounded_wait(canister_id, "revoke_role")
+        .with_arg(args)
+        .await
+        .map_err(|e| format!("Call failed: {:?}", e))?;
+
+    response
+        .candid::<revoke_role::Response>()
+        .map_err(|e| format!("Failed to decode response: {:?}", e))
+}
+
+pub async fn list_roles(
+    canister_id: candid::Principal,
+    args: list_roles::Args,
+) -> Result<list_roles::Response, String> {
+    let response = ic_cdk::call::Call::unbounded_wait(canister_id, "list_roles")
+        .with_arg(args)
+        .await
+        .map_err(|e| format!("Call failed: {:?}", e))?;
+
+    response
+        .candid::<list_roles::Response>()
+        .map_err(|e| format!("Failed to decode response: {:?}", e))
+}
+
+pub async fn add_authorized_principals(
+    canister_id: candid::Principal,
+    args: add_authorized_principals::Args,
+) -> Result<add_authorized_principals::Response, String> {
+    let response = ic_cdk::call::Call::unbounded_wait(canister_id, "add_authorized_principals")
+        .with_arg(args)
+        .await
+        .map_err(|e| format!("Call failed: {:?}", e))?;
+
+    response
+        .candid::<add_authorized_principals::Response>()
+        .map_err(|e| format!("Failed to decode response: {:?}", e))
+}
+
+pub async fn remove_authorized_principals(
+    canister_id: candid::Principal,
+    args: remove_authorized_principals::Args,
+) -> Result<remove_authorized_principals::Response, String> {
+    let response = ic_cdk::call::Call::unbounded_wait(canister_id, "remove_authorized_principals")
+        .with_arg(args)
+        .await
+        .map_err(|e| format!("Call failed: {:?}", e))?;
+
+    response
+        .candid::<remove_authorized_principals::Response>()
+        .map_err(|e| format!("Failed to decode response: {:?}", e))
+}
+
+pub async fn list_authorized_principals(
+    canister_id: candid::Principal,
+    args: list_authorized_principals::Args,
+) -> Result<list_authorized_principals::Response, String> {
+    let response = ic_cdk::call::Call::unbounded_wait(canister_id, "list_authorized_principals")
+        .with_arg(args)
+        .await
+        .map_err(|e| format!("Call failed: {:?}", e))?;
+
+    response
+        .candid::<list_authorized_principals::Response>()
+        .map_err(|e| format!("Failed to decode response: {:?}", e))
+}
+
+pub async fn set_file_visibility(
+    canister_id: candid::Principal,
+    args: set_file_visibility::Args,
+) -> Result<set_file_visibility::Response, String> {
+    let response = ic_cdk::call::Call::unbounded_wait(canister_id, "set_file_visibility")
+        .with_arg(args)
+        .await
+        .map_err(|e| format!("Call failed: {:?}", e))?;
+
+    response
+        .candid::<set_file_visibility::Response>()
+        .map_err(|e| format!("Failed to decode response: {:?}", e))
+}
+
+pub async fn create_download_token(
+    canister_id: candid::Principal,
+    args: create_download_token::Args,
+) -> Result<create_download_token::Response, String> {
+    let response = ic_cdk::call::Call::unbounded_wait(canister_id, "create_download_token")
+        .with_arg(args)
+        .await
+        .map_err(|e| format!("Call failed: {:?}", e))?;
+
+    response
+        .candid::<create_download_token::Response>()
+        .map_err(|e| format!("Failed to decode response: {:?}", e))
+}
+
+pub async fn create_upload_ticket(
+    canister_id: candid::Principal,
+    args: create_upload_ticket::Args,
+) -> Result<create_upload_ticket::Response, String> {
+    let response = ic_cdk::call::Call::unbounded_wait(canister_id, "create_upload_ticket")
+        .with_arg(args)
+        .await
+        .map_err(|e| format!("Call failed: {:?}", e))?;
+
+    response
+        .candid::<create_upload_ticket::Response>()
+        .map_err(|e| format!("Failed to decode response: {:?}", e))
+}
+
+pub async fn revoke_upload_ticket(
+    canister_id: candid::Principal,
+    args: revoke_upload_ticket::Args,
+) -> Result<revoke_upload_ticket::Response, String> {
+    let response = ic_cdk::call::Call::unbounded_wait(canister_id, "revoke_upload_ticket")
+        .with_arg(args)
+        .await
+        .map_err(|e| format!("Call failed: {:?}", e))?;
+
+    response
+        .candid::<revoke_upload_ticket::Response>()
+        .map_err(|e| format!("Failed to decode response: {:?}", e))
+}
+
+pub async fn get_stored_files_size(
+    canister_id: candid::Principal,
+    args: get_stored_files_size::Args,
+) -> Result<get_stored_files_size::Response, String> {
+    let response = ic_cdk::call::Call::unbounded_wait(canister_id, "get_stored_files_size")
+        .with_arg(args)
+        .await
+        .map_err(|e| format!("Call failed: {:?}", e))?;
+
+    response
+        .candid::<get_stored_files_size::Response>()
+        .map_err(|e| format!("Failed to decode response: {:?}", e))
+}
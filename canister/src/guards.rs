@@ -1,9 +1,35 @@
 use crate::state::read_state;
+use bity_ic_utils::env::Environment;
 
-pub fn caller_is_governance_principal() -> Result<(), String> {
-    if read_state(|state| state.is_caller_governance_principal()) {
+/// Authorized principals, and holders of an Admin role not scoped to a path.
+pub fn caller_is_admin() -> Result<(), String> {
+    if read_state(|state| state.is_caller_admin()) {
         Ok(())
     } else {
-        Err("Caller is not a governance principal".to_string())
+        Err("Caller is not an admin".to_string())
+    }
+}
+
+/// Callers with any access to files: authorized principals, role holders,
+/// bucket writers and upload ticket holders. Endpoints with this guard check the caller's roles against
+/// the paths they act on.
+pub fn caller_has_file_access() -> Result<(), String> {
+    if read_state(|state| {
+        state.is_caller_governance_principal()
+            || state.caller_holds_any_role()
+            || state.is_caller_bucket_writer()
+            || state.caller_holds_upload_ticket()
+    }) {
+        Ok(())
+    } else {
+        Err("Caller has no access to files".to_string())
+    }
+}
+
+pub fn caller_is_controller() -> Result<(), String> {
+    if read_state(|state| ic_cdk::api::is_controller(&state.env.caller())) {
+        Ok(())
+    } else {
+        Err("Caller is not a controller".to_string())
     }
 }
@@ -1,12 +1,15 @@
-use crate::state::mutate_state;
+use crate::state::{mutate_state, read_state};
+use crate::types::storage::finalize_step_bytes;
 use crate::utils::trace;
-use ic_cdk_timers::set_timer_interval;
+use bity_ic_storage_canister_api::types::storage::UploadState;
+use ic_cdk_timers::{set_timer, set_timer_interval};
 use std::time::Duration;
 
 /// Run the abandoned-upload GC every hour. Removes init/in-progress entries
-/// older than 24h.
+/// older than 24h. The same timer purges trashed files past their retention
+/// and files past their expiry.
 const GC_INTERVAL: Duration = Duration::from_secs(60 * 60);
-const GC_TTL_NANOS: u64 = 24 * 60 * 60 * 1_000_000_000;
+pub const GC_TTL_NANOS: u64 = 24 * 60 * 60 * 1_000_000_000;
 
 pub fn start_jobs() {
     let _ = set_timer_interval(GC_INTERVAL, || async move {
@@ -18,5 +21,54 @@ pub fn start_jobs() {
                 "abandoned-upload GC: removed {removed} stale entries"
             ));
         }
+        let purged = mutate_state(|state| state.data.storage.purge_expired_trash(now));
+        if purged > 0 {
+            trace(&format!("trash sweep: purged {purged} expired files"));
+        }
+        let expired = mutate_state(|state| state.data.storage.purge_expired_files(now));
+        if expired > 0 {
+            trace(&format!("expiry sweep: deleted {expired} files"));
+        }
+    });
+
+    // Timers do not survive an upgrade; pick up finalizations that were in flight.
+    for file_path in read_state(|state| state.data.storage.finalizing_uploads()) {
+        schedule_finalization(file_path);
+    }
+    for file_path in read_state(|state| state.data.storage.uploads_behind_on_hashing()) {
+        if mutate_state(|state| state.data.storage.start_hash_catch_up(&file_path)) {
+            schedule_hash_catch_up(file_path);
+        }
+    }
+}
+
+/// Hash the chunks an out-of-order upload staged ahead of its running hash,
+/// one bounded step per message, so finalize finds the digest ready.
+pub fn schedule_hash_catch_up(file_path: String) {
+    let _ = set_timer(Duration::ZERO, async move {
+        if mutate_state(|state| state.data.storage.catch_up_hash(&file_path)) {
+            schedule_hash_catch_up(file_path);
+        }
+    });
+}
+
+/// Drive a `Finalizing` upload to completion, one bounded step per message.
+pub fn schedule_finalization(file_path: String) {
+    let _ = set_timer(Duration::ZERO, async move {
+        let result = mutate_state(|state| {
+            let step_bytes = finalize_step_bytes(&state.env);
+            state
+                .data
+                .storage
+                .continue_finalization(&file_path, step_bytes)
+        });
+        match result {
+            Ok(resp) if resp.state == Some(UploadState::Finalizing) => {
+                schedule_finalization(file_path)
+            }
+            Ok(_) => trace(&format!("finalization complete: {file_path}")),
+            // The upload is left `Failed` with the reason for the client to query.
+            Err(e) => trace(&format!("finalization of {file_path} failed: {e:?}")),
+        }
     });
 }
@@ -26,6 +26,13 @@ fn init(args: Args) {
 
             let mut data = Data::new(init_args.authorized_principals, max_storage_size_wasm32);
 
+            if let Some(retention) = init_args.version_retention {
+                data.storage.set_version_retention(retention);
+            }
+            if let Some(retention_secs) = init_args.trash_retention_secs {
+                data.storage.set_trash_retention_secs(retention_secs);
+            }
+
             if env.is_test_mode() {
                 data.authorized_principals.push(env.caller());
             }
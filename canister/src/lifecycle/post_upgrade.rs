@@ -37,6 +37,13 @@ fn post_upgrade(args: Args) {
 
             state.env.set_version(upgrade_args.version);
             state.env.set_commit_hash(upgrade_args.commit_hash);
+            state.data.storage.migrate_heap_chunks();
+            if let Some(retention) = upgrade_args.version_retention {
+                state.data.storage.set_version_retention(retention);
+            }
+            if let Some(retention_secs) = upgrade_args.trash_retention_secs {
+                state.data.storage.set_trash_retention_secs(retention_secs);
+            }
 
             bity_ic_canister_logger::init_with_logs(state.env.is_test_mode(), logs, traces);
             init_canister(state);
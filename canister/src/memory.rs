@@ -5,6 +5,8 @@ use ic_stable_structures::{
 
 const UPGRADES: MemoryId = MemoryId::new(0);
 const DATA_STORAGE: MemoryId = MemoryId::new(1);
+const UPLOAD_STAGING: MemoryId = MemoryId::new(2);
+const DATA_CHUNKS: MemoryId = MemoryId::new(3);
 
 pub type VM = VirtualMemory<DefaultMemoryImpl>;
 
@@ -25,3 +27,13 @@ fn get_memory(id: MemoryId) -> VM {
 pub fn get_data_storage_memory() -> VM {
     get_memory(DATA_STORAGE)
 }
+
+/// Chunks of uploads that are still in flight, keyed by upload key and chunk index.
+pub fn get_upload_staging_memory() -> VM {
+    get_memory(UPLOAD_STAGING)
+}
+
+/// Chunks of finalized files, keyed by file path and chunk index.
+pub fn get_data_chunks_memory() -> VM {
+    get_memory(DATA_CHUNKS)
+}
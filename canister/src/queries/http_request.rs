@@ -1,9 +1,10 @@
 use crate::{
     state::mutate_state,
     types::http::{
-        get_asset_headers, ASSET_ROUTER, HTTP_TREE, IMMUTABLE_ASSET_CACHE_CONTROL,
+        file_response_headers, get_asset_headers, ASSET_ROUTER, HTTP_TREE,
         NO_CACHE_ASSET_CACHE_CONTROL,
     },
+    types::storage::FileData,
     utils::trace,
 };
 use bity_ic_canister_logger::LogEntry;
@@ -21,13 +22,31 @@ use crate::state::read_state;
 #[query(hidden = true)]
 async fn http_request(req: HttpRequest<'static>) -> HttpResponse<'static> {
     let path = req.get_path().expect("Failed to parse request path");
+    // Hosts mapped to a bucket are served from its files, never certified.
+    let (path, is_bucket_host) = match bucket_host_path(&req, &path) {
+        Some(bucket_path) => (bucket_path, true),
+        None => (path, false),
+    };
 
     match path.as_str() {
         "/logs" => serve_logs(bity_ic_canister_logger::export_logs()),
         "/traces" => serve_logs(bity_ic_canister_logger::export_traces()),
         "/metrics" => serve_metrics(),
+        // Certified responses outlive expiry until the next purge.
+        _ if read_state(|state| state.data.storage.is_file_expired(&path)) => {
+            HttpResponse::builder()
+                .with_status_code(StatusCode::NOT_FOUND)
+                .build()
+        }
         _ => {
-            let asset_resp = serve_asset(&req);
+            // Previous versions are never certified, so they are served raw only.
+            let version = requested_version(req.get_query().ok().flatten());
+            let is_private = read_state(|state| state.data.storage.is_file_private(&path));
+            let asset_resp = match version {
+                Some(_) => None,
+                None if is_bucket_host || is_private => None,
+                None => serve_asset(&req),
+            };
             trace(&format!("asset_resp: {:?}", asset_resp));
 
             match asset_resp {
@@ -38,8 +57,8 @@ async fn http_request(req: HttpRequest<'static>) -> HttpResponse<'static> {
                         .iter()
                         .any(|(k, v)| k.eq_ignore_ascii_case("host") && v.contains(".raw."));
 
-                    if is_raw {
-                        serve_from_stable_memory(&req, &path)
+                    if is_raw || is_bucket_host {
+                        serve_from_stable_memory(&req, &path, version)
                     } else if req.headers().to_vec().iter().any(|(k, v)| {
                         k == "referer"
                             && v.contains(ic_cdk::api::canister_self().to_string().as_str())
@@ -56,6 +75,44 @@ async fn http_request(req: HttpRequest<'static>) -> HttpResponse<'static> {
     }
 }
 
+/// Path of the requested file when the host is mapped to a bucket.
+fn bucket_host_path(req: &HttpRequest, path: &str) -> Option<String> {
+    let host = req
+        .headers()
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case("host"))
+        .map(|(_, v)| v.as_str())?;
+    read_state(|state| {
+        state
+            .data
+            .storage
+            .bucket_for_host(host)
+            .map(|bucket| format!("/{}{}", bucket, path))
+    })
+}
+
+/// Download token and its expiry, from `?token=<hex>&expires=<ns>`.
+fn requested_token(query: Option<&str>) -> Option<(&str, u64)> {
+    let mut token = None;
+    let mut expires_at = None;
+    for pair in query?.split('&') {
+        if let Some(value) = pair.strip_prefix("token=") {
+            token = Some(value);
+        } else if let Some(value) = pair.strip_prefix("expires=") {
+            expires_at = value.parse().ok();
+        }
+    }
+    Some((token?, expires_at?))
+}
+
+/// Version number asked for with `?version=<n>`, if any.
+fn requested_version(query: Option<String>) -> Option<u64> {
+    query?
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("version="))
+        .and_then(|version| version.parse().ok())
+}
+
 fn parse_range_header(range: &str, total: usize) -> Option<(usize, usize)> {
     if total == 0 {
         return None;
@@ -73,14 +130,45 @@ fn parse_range_header(range: &str, total: usize) -> Option<(usize, usize)> {
     Some((start, end))
 }
 
-fn serve_from_stable_memory(req: &HttpRequest, path: &str) -> HttpResponse<'static> {
-    let result = read_state(|state| state.data.storage.get_file_data(path));
+fn serve_from_stable_memory(
+    req: &HttpRequest,
+    path: &str,
+    version: Option<u64>,
+) -> HttpResponse<'static> {
+    let query = req.get_query().ok().flatten();
+    let token = requested_token(query.as_deref());
+    if !read_state(|state| {
+        state
+            .data
+            .storage
+            .is_download_allowed(path, version, token, ic_cdk::api::time())
+    }) {
+        return HttpResponse::builder()
+            .with_status_code(StatusCode::FORBIDDEN)
+            .build();
+    }
+
+    let accept_encoding = req
+        .headers()
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case("accept-encoding"))
+        .map(|(_, v)| v.as_str());
+    let result = read_state(|state| match version {
+        Some(version) => state.data.storage.get_file_version_data(path, version),
+        None => state.data.storage.get_file_data(path, accept_encoding),
+    });
 
     match result {
         None => HttpResponse::builder()
             .with_status_code(StatusCode::NOT_FOUND)
             .build(),
-        Some((data, content_type)) => {
+        Some(FileData {
+            data,
+            content_type,
+            headers: file_headers,
+            content_encoding,
+            has_variants,
+        }) => {
             let range_header = req
                 .headers()
                 .iter()
@@ -88,14 +176,15 @@ fn serve_from_stable_memory(req: &HttpRequest, path: &str) -> HttpResponse<'stat
                 .map(|(_, v)| v.clone());
 
             let total = data.len();
-            let headers = get_asset_headers(vec![
-                ("content-type".to_string(), content_type.to_string()),
-                (
-                    "cache-control".to_string(),
-                    IMMUTABLE_ASSET_CACHE_CONTROL.to_string(),
-                ),
-                ("accept-ranges".to_string(), "bytes".to_string()),
-            ]);
+            let mut headers = file_response_headers(file_headers);
+            headers.push(("content-type".to_string(), content_type));
+            headers.push(("accept-ranges".to_string(), "bytes".to_string()));
+            if let Some(content_encoding) = content_encoding {
+                headers.push(("content-encoding".to_string(), content_encoding.to_string()));
+            }
+            if has_variants {
+                headers.push(("vary".to_string(), "accept-encoding".to_string()));
+            }
 
             if let Some(range) = range_header {
                 if let Some((start, end)) = parse_range_header(&range, total) {
@@ -133,14 +222,27 @@ async fn http_request_update(req: HttpUpdateRequest<'static>) -> HttpUpdateRespo
     match path.as_str() {
         _ => {
             trace("Cache miss");
-            let cache_miss_ret =
-                mutate_state(|state| state.data.storage.cache_miss(&state.env, path.clone()));
+            let query = req.get_query().ok().flatten();
+            let version = requested_version(query.clone());
+            let is_private = read_state(|state| state.data.storage.is_file_private(&path));
+            let cache_miss_ret = match version {
+                // Nothing to certify: the raw domain serves previous versions
+                // and private files.
+                Some(_) => Ok(()),
+                None if is_private => Ok(()),
+                None => {
+                    mutate_state(|state| state.data.storage.cache_miss(&state.env, path.clone()))
+                }
+            };
             match cache_miss_ret {
                 Ok(_) => {
+                    // Keep the version or download token for the raw domain.
+                    let query = query.map(|query| format!("?{}", query)).unwrap_or_default();
                     let redirection_url = format!(
-                        "https://{}.raw.icp0.io{}",
+                        "https://{}.raw.icp0.io{}{}",
                         ic_cdk::api::canister_self().to_string(),
-                        path.clone()
+                        path.clone(),
+                        query
                     );
 
                     let response = HttpResponse::temporary_redirect(
@@ -1,11 +1,27 @@
+use crate::guards::{caller_is_admin, caller_is_controller};
 use crate::state::read_state;
 
 pub use bity_ic_storage_canister_api::queries::get_storage_size::{
     Args as GetStorageSizeArgs, Response as GetStorageSizeResponse,
 };
+pub use bity_ic_storage_canister_api::queries::get_stored_files_size::{
+    Args as GetStoredFilesSizeArgs, Response as GetStoredFilesSizeResponse,
+};
 pub use bity_ic_storage_canister_api::queries::get_stored_files_size_bytes::{
     Args as GetStoredFilesSizeBytesArgs, Response as GetStoredFilesSizeBytesResponse,
 };
+pub use bity_ic_storage_canister_api::queries::list_authorized_principals::{
+    Args as ListAuthorizedPrincipalsArgs, Response as ListAuthorizedPrincipalsResponse,
+};
+pub use bity_ic_storage_canister_api::queries::list_buckets::{
+    Args as ListBucketsArgs, Response as ListBucketsResponse,
+};
+pub use bity_ic_storage_canister_api::queries::list_principal_usage::{
+    Args as ListPrincipalUsageArgs, Response as ListPrincipalUsageResponse,
+};
+pub use bity_ic_storage_canister_api::queries::list_roles::{
+    Args as ListRolesArgs, Response as ListRolesResponse,
+};
 
 use ic_cdk::query;
 
@@ -20,3 +36,34 @@ async fn get_stored_files_size_bytes(
 ) -> GetStoredFilesSizeBytesResponse {
     read_state(|s| s.data.storage.get_stored_files_size_bytes())
 }
+
+#[query]
+async fn get_stored_files_size(_: GetStoredFilesSizeArgs) -> GetStoredFilesSizeResponse {
+    read_state(|s| s.data.storage.get_stored_files_size())
+}
+
+#[query(guard = "caller_is_admin")]
+async fn list_principal_usage(_: ListPrincipalUsageArgs) -> ListPrincipalUsageResponse {
+    read_state(|s| s.data.storage.list_principal_usage())
+}
+
+#[query(guard = "caller_is_admin")]
+async fn list_buckets(_: ListBucketsArgs) -> ListBucketsResponse {
+    read_state(|s| s.data.storage.list_buckets())
+}
+
+#[query(guard = "caller_is_admin")]
+async fn list_roles(_: ListRolesArgs) -> ListRolesResponse {
+    read_state(|s| ListRolesResponse {
+        assignments: s.data.role_assignments.clone(),
+    })
+}
+
+#[query(guard = "caller_is_controller")]
+async fn list_authorized_principals(
+    _: ListAuthorizedPrincipalsArgs,
+) -> ListAuthorizedPrincipalsResponse {
+    read_state(|s| ListAuthorizedPrincipalsResponse {
+        principals: s.data.authorized_principals.clone(),
+    })
+}
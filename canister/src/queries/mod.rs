@@ -4,4 +4,4 @@ pub mod management;
 pub mod storage;
 
 pub use management::*;
-// pub use storage::*;
+pub use storage::*;
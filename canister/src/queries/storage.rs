@@ -1 +1,79 @@
+use crate::jobs::GC_TTL_NANOS;
+use crate::state::read_state;
 
+pub use bity_ic_storage_canister_api::queries::get_file_metadata::{
+    Args as GetFileMetadataArgs, Response as GetFileMetadataResponse,
+};
+pub use bity_ic_storage_canister_api::queries::get_finalization_progress::{
+    Args as GetFinalizationProgressArgs, Response as GetFinalizationProgressResponse,
+};
+pub use bity_ic_storage_canister_api::queries::get_upload_status::{
+    Args as GetUploadStatusArgs, Response as GetUploadStatusResponse,
+};
+pub use bity_ic_storage_canister_api::queries::list_file_versions::{
+    Args as ListFileVersionsArgs, Response as ListFileVersionsResponse,
+};
+pub use bity_ic_storage_canister_api::queries::list_files::{
+    Args as ListFilesArgs, Response as ListFilesResponse,
+};
+pub use bity_ic_storage_canister_api::queries::list_trash::{
+    Args as ListTrashArgs, Response as ListTrashResponse,
+};
+
+use ic_cdk::query;
+
+#[query]
+fn get_file_metadata(args: GetFileMetadataArgs) -> GetFileMetadataResponse {
+    read_state(|s| {
+        s.data
+            .storage
+            .get_file_metadata(&args.file_path, |path| s.caller_may_read(path))
+    })
+}
+
+#[query]
+fn get_finalization_progress(args: GetFinalizationProgressArgs) -> GetFinalizationProgressResponse {
+    read_state(|s| {
+        s.data
+            .storage
+            .get_finalization_progress(&args.file_path, |path| s.caller_may_read(path))
+    })
+}
+
+#[query]
+fn get_upload_status(args: GetUploadStatusArgs) -> GetUploadStatusResponse {
+    read_state(|s| {
+        s.data
+            .storage
+            .get_upload_status(&args.file_path, GC_TTL_NANOS, |path| {
+                s.caller_may_read(path)
+            })
+    })
+}
+
+#[query]
+fn list_files(args: ListFilesArgs) -> ListFilesResponse {
+    read_state(|s| {
+        s.data
+            .storage
+            .list_files(args, |path| s.caller_may_read(path))
+    })
+}
+
+#[query]
+fn list_file_versions(args: ListFileVersionsArgs) -> ListFileVersionsResponse {
+    read_state(|s| {
+        s.data
+            .storage
+            .list_file_versions(&args.file_path, |path| s.caller_may_read(path))
+    })
+}
+
+#[query]
+fn list_trash(args: ListTrashArgs) -> ListTrashResponse {
+    read_state(|s| {
+        s.data
+            .storage
+            .list_trash(args, |path| s.caller_may_read(path))
+    })
+}
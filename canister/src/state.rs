@@ -1,7 +1,13 @@
 use crate::types::storage;
+use crate::utils::{is_under_prefix, validate_file_path};
 use bity_ic_canister_state_macros::canister_state;
+use bity_ic_storage_canister_api::types::storage::{BucketAccessError, Role, RoleAssignment};
 use bity_ic_storage_canister_api::{
-    cancel_upload, finalize_upload, init_reupload, init_upload, remove_file, store_chunk,
+    add_authorized_principals, batch_upload, cancel_upload, copy_file, create_upload_ticket,
+    finalize_upload, grant_role, init_reupload, init_upload, move_file, purge_file,
+    remove_authorized_principals, remove_file, restore_file, restore_file_version, revoke_role,
+    revoke_upload_ticket, set_file_expiry, set_file_headers, set_file_metadata,
+    set_file_visibility, set_principal_quota, store_chunk,
 };
 use bity_ic_types::BuildVersion;
 use bity_ic_types::{Cycles, TimestampMillis};
@@ -9,6 +15,7 @@ use bity_ic_utils::env::{CanisterEnv, Environment};
 use bity_ic_utils::memory::MemorySize;
 use candid::{CandidType, Nat, Principal};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 canister_state!(RuntimeState);
 
@@ -27,6 +34,142 @@ impl RuntimeState {
         self.data.authorized_principals.contains(&self.env.caller())
     }
 
+    pub fn is_caller_admin(&self) -> bool {
+        let caller = self.env.caller();
+        self.is_caller_governance_principal()
+            || self.data.role_assignments.iter().any(|assignment| {
+                assignment.principal == caller
+                    && assignment.role == Role::Admin
+                    && assignment.path_prefix.is_none()
+            })
+    }
+
+    pub fn caller_holds_any_role(&self) -> bool {
+        let caller = self.env.caller();
+        self.data
+            .role_assignments
+            .iter()
+            .any(|assignment| assignment.principal == caller)
+    }
+
+    /// Whether the caller may act on `path` as one of `roles`. Admins may act
+    /// on the paths in their scope as any role.
+    pub fn caller_has_role(&self, roles: &[Role], path: &str) -> bool {
+        let caller = self.env.caller();
+        self.is_caller_governance_principal()
+            || self.data.role_assignments.iter().any(|assignment| {
+                assignment.principal == caller
+                    && (assignment.role == Role::Admin || roles.contains(&assignment.role))
+                    && assignment
+                        .path_prefix
+                        .as_deref()
+                        .is_none_or(|prefix| is_under_prefix(path, prefix))
+            })
+    }
+
+    /// Whether the caller may read the file at `path` even when it is private,
+    /// which any role on the path allows.
+    pub fn caller_may_read(&self, path: &str) -> bool {
+        self.caller_has_role(&[Role::Uploader, Role::Editor, Role::Deleter], path)
+    }
+
+    /// Id of the unexpired upload ticket of the caller covering `path` with the
+    /// most bytes left, so an exhausted one is only picked if all of them are.
+    pub fn caller_upload_ticket(&self, path: &str) -> Option<u64> {
+        let caller = self.env.caller();
+        let now = ic_cdk::api::time();
+        self.data
+            .upload_tickets
+            .iter()
+            .filter(|(_, ticket)| {
+                ticket.holder == caller && ticket.expires_at > now && ticket.covers(path)
+            })
+            .max_by_key(|(_, ticket)| ticket.remaining_bytes())
+            .map(|(id, _)| *id)
+    }
+
+    pub fn caller_holds_upload_ticket(&self) -> bool {
+        let caller = self.env.caller();
+        let now = ic_cdk::api::time();
+        self.data
+            .upload_tickets
+            .values()
+            .any(|ticket| ticket.holder == caller && ticket.expires_at > now)
+    }
+
+    pub fn is_caller_bucket_writer(&self) -> bool {
+        self.data.storage.is_bucket_writer(self.env.caller())
+    }
+
+    /// Storage path of `file_path` in `bucket`, if the caller may act on it as
+    /// one of `roles`. A bucket's writers may act on all of its files.
+    pub fn scoped_file_path(
+        &self,
+        bucket: Option<&str>,
+        file_path: &str,
+        roles: &[Role],
+    ) -> Result<String, BucketAccessError> {
+        let is_writer = match bucket {
+            None => false,
+            Some(name) => self
+                .data
+                .storage
+                .bucket(name)
+                .ok_or(BucketAccessError::BucketNotFound)?
+                .writers
+                .contains(&self.env.caller()),
+        };
+        let path = bucket_file_path(bucket, file_path);
+        if is_writer || self.caller_has_role(roles, &path) {
+            Ok(path)
+        } else {
+            Err(BucketAccessError::NotAuthorized)
+        }
+    }
+
+    /// `scoped_file_path` for the upload endpoints, which also accept an upload
+    /// ticket of the caller for a path outside buckets, as long as the file and
+    /// any upload or reupload there are the caller's own. Returns the ticket used, if the caller has
+    /// no other access.
+    pub fn upload_file_path(
+        &self,
+        bucket: Option<&str>,
+        file_path: &str,
+        roles: &[Role],
+    ) -> Result<(String, Option<u64>), BucketAccessError> {
+        match self.scoped_file_path(bucket, file_path, roles) {
+            Err(BucketAccessError::NotAuthorized) if bucket.is_none() => {
+                let path = file_path.trim_start_matches('/');
+                let is_others = [path.to_string(), format!("?reupload:{path}")]
+                    .iter()
+                    .filter_map(|key| self.data.storage.file_owner(key))
+                    .any(|owner| owner != self.env.caller());
+                self.caller_upload_ticket(file_path)
+                    .filter(|_| !is_others)
+                    .map(|ticket_id| (file_path.to_string(), Some(ticket_id)))
+                    .ok_or(BucketAccessError::NotAuthorized)
+            }
+            result => result.map(|path| (path, None)),
+        }
+    }
+
+    /// `upload_file_path` for the endpoints carrying on an upload in flight.
+    /// A pending reupload replaces a file, so like starting one it takes an
+    /// Editor; any other upload an Uploader or an Editor.
+    pub fn upload_in_flight_path(
+        &self,
+        bucket: Option<&str>,
+        file_path: &str,
+    ) -> Result<(String, Option<u64>), BucketAccessError> {
+        let path = bucket_file_path(bucket, file_path);
+        let roles: &[Role] = if self.data.storage.is_reupload_pending(&path) {
+            &[Role::Editor]
+        } else {
+            &[Role::Uploader, Role::Editor]
+        };
+        self.upload_file_path(bucket, file_path, roles)
+    }
+
     pub fn metrics(&self) -> Metrics {
         Metrics {
             canister_info: CanisterInfo {
@@ -45,6 +188,13 @@ impl RuntimeState {
 #[derive(Serialize, Deserialize)]
 pub struct Data {
     pub authorized_principals: Vec<Principal>,
+    #[serde(default)]
+    pub role_assignments: Vec<RoleAssignment>,
+    /// Upload tickets by id. Expired ones are dropped when a ticket is created.
+    #[serde(default)]
+    pub upload_tickets: BTreeMap<u64, UploadTicket>,
+    #[serde(default)]
+    pub next_upload_ticket_id: u64,
     pub storage: storage::StorageData,
     pub http_cache: HttpCache,
 }
@@ -54,6 +204,9 @@ impl Data {
     pub fn new(authorized_principals: Vec<Principal>, max_storage_size_wasm32: u128) -> Self {
         Self {
             authorized_principals: authorized_principals.into_iter().collect(),
+            role_assignments: Vec::new(),
+            upload_tickets: BTreeMap::new(),
+            next_upload_ticket_id: 0,
             storage: storage::StorageData::new(max_storage_size_wasm32),
             http_cache: HttpCache::default(),
         }
@@ -61,18 +214,136 @@ impl Data {
 }
 
 impl Data {
+    pub fn add_authorized_principals(
+        &mut self,
+        data: add_authorized_principals::Args,
+    ) -> add_authorized_principals::Response {
+        for principal in data.principals {
+            if !self.authorized_principals.contains(&principal) {
+                self.authorized_principals.push(principal);
+            }
+        }
+        add_authorized_principals::AddAuthorizedPrincipalsResp {}
+    }
+
+    pub fn remove_authorized_principals(
+        &mut self,
+        data: remove_authorized_principals::Args,
+    ) -> remove_authorized_principals::Response {
+        if let Some(principal) = data
+            .principals
+            .iter()
+            .find(|principal| !self.authorized_principals.contains(principal))
+        {
+            return Err(
+                remove_authorized_principals::RemoveAuthorizedPrincipalsError::PrincipalNotFound(
+                    *principal,
+                ),
+            );
+        }
+        let has_admin_role = self
+            .role_assignments
+            .iter()
+            .any(|assignment| assignment.role == Role::Admin && assignment.path_prefix.is_none());
+        let remaining = self
+            .authorized_principals
+            .iter()
+            .filter(|principal| !data.principals.contains(principal))
+            .count();
+        if remaining == 0 && !has_admin_role {
+            return Err(remove_authorized_principals::RemoveAuthorizedPrincipalsError::LastAdmin);
+        }
+        self.authorized_principals
+            .retain(|principal| !data.principals.contains(principal));
+        Ok(remove_authorized_principals::RemoveAuthorizedPrincipalsResp {})
+    }
+
+    pub fn grant_role(&mut self, mut data: grant_role::Args) -> grant_role::Response {
+        data.path_prefix = normalize_path_prefix(data.path_prefix)
+            .ok_or(grant_role::GrantRoleError::InvalidPathPrefix)?;
+        if self.role_assignments.contains(&data) {
+            return Err(grant_role::GrantRoleError::AlreadyGranted);
+        }
+        self.role_assignments.push(data);
+        Ok(grant_role::GrantRoleResp {})
+    }
+
+    pub fn revoke_role(&mut self, mut data: revoke_role::Args) -> revoke_role::Response {
+        data.path_prefix = normalize_path_prefix(data.path_prefix)
+            .ok_or(revoke_role::RevokeRoleError::RoleNotFound)?;
+        let len = self.role_assignments.len();
+        self.role_assignments
+            .retain(|assignment| *assignment != data);
+        if self.role_assignments.len() == len {
+            return Err(revoke_role::RevokeRoleError::RoleNotFound);
+        }
+        Ok(revoke_role::RevokeRoleResp {})
+    }
+
+    pub fn create_upload_ticket(
+        &mut self,
+        data: create_upload_ticket::Args,
+        issuer: Principal,
+        now: u64,
+    ) -> create_upload_ticket::Response {
+        let path = data.file_path.trim_matches('/');
+        if path.is_empty() || validate_file_path(path).is_err() {
+            return Err(create_upload_ticket::CreateUploadTicketError::InvalidFilePath);
+        }
+        if data.expires_at <= now {
+            return Err(create_upload_ticket::CreateUploadTicketError::InvalidExpiresAt);
+        }
+
+        self.upload_tickets
+            .retain(|_, ticket| ticket.expires_at > now);
+        let ticket_id = self.next_upload_ticket_id;
+        self.next_upload_ticket_id += 1;
+        self.upload_tickets.insert(
+            ticket_id,
+            UploadTicket {
+                holder: data.holder,
+                issuer,
+                path: path.to_string(),
+                is_prefix: data.is_prefix,
+                max_bytes: data.max_bytes,
+                used_bytes: 0,
+                expires_at: data.expires_at,
+            },
+        );
+        Ok(create_upload_ticket::CreateUploadTicketResp { ticket_id })
+    }
+
+    pub fn revoke_upload_ticket(
+        &mut self,
+        data: revoke_upload_ticket::Args,
+        caller: Principal,
+        is_admin: bool,
+    ) -> revoke_upload_ticket::Response {
+        let ticket = self
+            .upload_tickets
+            .get(&data.ticket_id)
+            .ok_or(revoke_upload_ticket::RevokeUploadTicketError::TicketNotFound)?;
+        if !is_admin && ticket.issuer != caller {
+            return Err(revoke_upload_ticket::RevokeUploadTicketError::NotAuthorized);
+        }
+        self.upload_tickets.remove(&data.ticket_id);
+        Ok(revoke_upload_ticket::RevokeUploadTicketResp {})
+    }
+
     pub fn init_upload(
         &mut self,
         data: init_upload::Args,
+        caller: Principal,
     ) -> Result<init_upload::InitUploadResp, init_upload::InitUploadError> {
-        self.storage.init_upload(data)
+        self.storage.init_upload(data, caller)
     }
 
     pub fn init_reupload(
         &mut self,
         data: init_reupload::Args,
+        caller: Principal,
     ) -> Result<init_reupload::InitReuploadResp, init_reupload::InitReuploadError> {
-        self.storage.init_reupload(data)
+        self.storage.init_reupload(data, caller)
     }
 
     pub fn store_chunk(
@@ -85,8 +356,9 @@ impl Data {
     pub fn finalize_upload(
         &mut self,
         data: finalize_upload::Args,
+        step_bytes: u64,
     ) -> Result<finalize_upload::FinalizeUploadResp, finalize_upload::FinalizeUploadError> {
-        self.storage.finalize_upload(data)
+        self.storage.finalize_upload(data, step_bytes)
     }
 
     pub fn cancel_upload(
@@ -102,6 +374,145 @@ impl Data {
     ) -> Result<remove_file::RemoveFileResp, remove_file::RemoveFileError> {
         self.storage.remove_file(media_hash_id)
     }
+
+    pub fn batch_upload(
+        &mut self,
+        data: batch_upload::Args,
+        caller: Principal,
+    ) -> Result<batch_upload::BatchUploadResp, batch_upload::BatchUploadError> {
+        self.storage.batch_upload(data, caller)
+    }
+
+    pub fn copy_file(
+        &mut self,
+        data: copy_file::Args,
+        caller: Principal,
+    ) -> Result<copy_file::CopyFileResp, copy_file::CopyFileError> {
+        self.storage.copy_file(data, caller)
+    }
+
+    pub fn move_file(
+        &mut self,
+        data: move_file::Args,
+    ) -> Result<move_file::MoveFileResp, move_file::MoveFileError> {
+        self.storage.move_file(data)
+    }
+
+    pub fn restore_file(
+        &mut self,
+        data: restore_file::Args,
+        caller: Principal,
+    ) -> Result<restore_file::RestoreFileResp, restore_file::RestoreFileError> {
+        self.storage.restore_file(data, caller)
+    }
+
+    pub fn purge_file(
+        &mut self,
+        data: purge_file::Args,
+    ) -> Result<purge_file::PurgeFileResp, purge_file::PurgeFileError> {
+        self.storage.purge_file(data)
+    }
+
+    pub fn restore_file_version(
+        &mut self,
+        data: restore_file_version::Args,
+        caller: Principal,
+    ) -> Result<
+        restore_file_version::RestoreFileVersionResp,
+        restore_file_version::RestoreFileVersionError,
+    > {
+        self.storage.restore_file_version(data, caller)
+    }
+
+    pub fn set_file_expiry(
+        &mut self,
+        data: set_file_expiry::Args,
+    ) -> Result<set_file_expiry::SetFileExpiryResp, set_file_expiry::SetFileExpiryError> {
+        self.storage.set_file_expiry(data)
+    }
+
+    pub fn set_principal_quota(
+        &mut self,
+        data: set_principal_quota::Args,
+    ) -> set_principal_quota::SetPrincipalQuotaResp {
+        self.storage.set_principal_quota(data)
+    }
+
+    pub fn set_file_headers(
+        &mut self,
+        data: set_file_headers::Args,
+    ) -> Result<set_file_headers::SetFileHeadersResp, set_file_headers::SetFileHeadersError> {
+        self.storage.set_file_headers(data)
+    }
+
+    pub fn set_file_visibility(
+        &mut self,
+        data: set_file_visibility::Args,
+    ) -> Result<
+        set_file_visibility::SetFileVisibilityResp,
+        set_file_visibility::SetFileVisibilityError,
+    > {
+        self.storage.set_file_visibility(data)
+    }
+
+    pub fn set_file_metadata(
+        &mut self,
+        data: set_file_metadata::Args,
+    ) -> Result<set_file_metadata::SetFileMetadataResp, set_file_metadata::SetFileMetadataError>
+    {
+        self.storage.set_file_metadata(data)
+    }
+}
+
+/// Lets its holder upload to a path, or under a directory, without a role.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct UploadTicket {
+    pub holder: Principal,
+    pub issuer: Principal,
+    /// Canonical path, without leading or trailing '/'.
+    pub path: String,
+    pub is_prefix: bool,
+    pub max_bytes: u64,
+    /// Sizes of the uploads started with the ticket.
+    pub used_bytes: u64,
+    pub expires_at: u64,
+}
+
+impl UploadTicket {
+    pub fn covers(&self, path: &str) -> bool {
+        if self.is_prefix {
+            is_under_prefix(path, &self.path)
+        } else {
+            path.trim_start_matches('/') == self.path
+        }
+    }
+
+    pub fn remaining_bytes(&self) -> u64 {
+        self.max_bytes.saturating_sub(self.used_bytes)
+    }
+}
+
+/// Storage path of `file_path` in `bucket`, or `file_path` itself outside buckets.
+fn bucket_file_path(bucket: Option<&str>, file_path: &str) -> String {
+    match bucket {
+        None => file_path.to_string(),
+        Some(name) => format!("{}/{}", name, file_path.trim_start_matches('/')),
+    }
+}
+
+/// Canonical form of a role's path prefix, without leading or trailing '/'.
+/// An empty prefix covers every path, like `None`. Returns `None` for an
+/// invalid prefix.
+fn normalize_path_prefix(prefix: Option<String>) -> Option<Option<String>> {
+    let Some(prefix) = prefix else {
+        return Some(None);
+    };
+    let prefix = prefix.trim_matches('/');
+    if prefix.is_empty() {
+        return Some(None);
+    }
+    validate_file_path(prefix).ok()?;
+    Some(Some(prefix.to_string()))
 }
 
 #[derive(CandidType, Serialize)]
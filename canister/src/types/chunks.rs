@@ -0,0 +1,153 @@
+use ic_stable_structures::storable::Bound;
+use ic_stable_structures::Storable;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+/// Key of a single chunk in a stable-memory chunk map.
+///
+/// Encoded as `owner bytes || 0x00 || index (big-endian u64)` so that all chunks
+/// of one owner are contiguous and sorted by index. `validate_file_path` rejects
+/// `\0`, so the separator can never appear inside an owner key.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ChunkKey {
+    pub owner: String,
+    pub index: u64,
+}
+
+impl ChunkKey {
+    pub fn new(owner: &str, index: u64) -> Self {
+        Self {
+            owner: owner.to_string(),
+            index,
+        }
+    }
+
+    /// Inclusive key range covering every chunk of `owner`.
+    pub fn range(owner: &str) -> std::ops::RangeInclusive<ChunkKey> {
+        ChunkKey::new(owner, 0)..=ChunkKey::new(owner, u64::MAX)
+    }
+}
+
+impl Storable for ChunkKey {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(self.clone().into_bytes())
+    }
+
+    fn into_bytes(self) -> Vec<u8> {
+        let mut bytes = self.owner.into_bytes();
+        bytes.push(0);
+        bytes.extend_from_slice(&self.index.to_be_bytes());
+        bytes
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        let split = bytes.len() - 9;
+        let owner = String::from_utf8(bytes[..split].to_vec()).expect("invalid chunk key owner");
+        let mut index = [0u8; 8];
+        index.copy_from_slice(&bytes[split + 1..]);
+        Self {
+            owner,
+            index: u64::from_be_bytes(index),
+        }
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+/// Compact record of which chunks of an upload have been received.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ChunkBitmap {
+    #[serde(with = "serde_bytes")]
+    bits: Vec<u8>,
+    len: u64,
+}
+
+impl ChunkBitmap {
+    pub fn new(len: u64) -> Self {
+        Self {
+            bits: vec![0; len.div_ceil(8) as usize],
+            len,
+        }
+    }
+
+    pub fn num_chunks(&self) -> u64 {
+        self.len
+    }
+
+    pub fn get(&self, index: u64) -> bool {
+        index < self.len && self.bits[(index / 8) as usize] & (1 << (index % 8)) != 0
+    }
+
+    pub fn set(&mut self, index: u64) {
+        if index < self.len {
+            self.bits[(index / 8) as usize] |= 1 << (index % 8);
+        }
+    }
+
+    pub fn count(&self) -> u64 {
+        self.bits.iter().map(|b| b.count_ones() as u64).sum()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.count() == self.len
+    }
+
+    /// Indices of the chunks not received yet, in ascending order.
+    pub fn missing(&self) -> impl Iterator<Item = u64> + '_ {
+        (0..self.len).filter(|&index| !self.get(index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunk_key_roundtrips() {
+        let key = ChunkKey::new("dir/file.mp4", 42);
+        let bytes = key.to_bytes().into_owned();
+        assert_eq!(ChunkKey::from_bytes(Cow::Owned(bytes)), key);
+    }
+
+    #[test]
+    fn chunk_key_orders_by_owner_then_index() {
+        let encoded = |owner: &str, index: u64| ChunkKey::new(owner, index).into_bytes();
+        assert!(encoded("a", 1) < encoded("a", 2));
+        assert!(encoded("a", 255) < encoded("a", 256));
+        assert!(encoded("a", u64::MAX) < encoded("a/b", 0));
+        assert!(encoded("a", u64::MAX) < encoded("ab", 0));
+    }
+
+    #[test]
+    fn chunk_bitmap_tracks_received_chunks() {
+        let mut bitmap = ChunkBitmap::new(10);
+        assert_eq!(bitmap.count(), 0);
+        assert!(!bitmap.get(9));
+
+        bitmap.set(0);
+        bitmap.set(9);
+        bitmap.set(9);
+        bitmap.set(10); // out of range, ignored
+        assert!(bitmap.get(0));
+        assert!(bitmap.get(9));
+        assert!(!bitmap.get(10));
+        assert_eq!(bitmap.count(), 2);
+        assert!(!bitmap.is_complete());
+
+        assert_eq!(
+            bitmap.missing().collect::<Vec<_>>(),
+            (1..9).collect::<Vec<_>>()
+        );
+
+        for i in 0..10 {
+            bitmap.set(i);
+        }
+        assert!(bitmap.is_complete());
+        assert_eq!(bitmap.missing().count(), 0);
+    }
+
+    #[test]
+    fn empty_chunk_bitmap_is_complete() {
+        assert!(ChunkBitmap::new(0).is_complete());
+    }
+}
@@ -220,8 +220,45 @@ fn get_asset_config() -> Vec<AssetConfig> {
     asset_configs
 }
 
-pub fn certify_asset(assets: Vec<Asset<'static, '_>>) {
-    let asset_configs = get_asset_config();
+/// Response headers of a stored file, other than `content-type`: the defaults,
+/// an immutable `cache-control` unless the file sets its own, then the file's
+/// headers.
+pub fn file_response_headers(file_headers: Vec<HeaderField>) -> Vec<HeaderField> {
+    let mut headers = Vec::with_capacity(file_headers.len() + 1);
+    if !file_headers.iter().any(|(name, _)| name == "cache-control") {
+        headers.push((
+            "cache-control".to_string(),
+            IMMUTABLE_ASSET_CACHE_CONTROL.to_string(),
+        ));
+    }
+    headers.extend(file_headers);
+    get_asset_headers(headers)
+}
+
+/// Config for a single stored file whose explicit `content_type` or own
+/// headers differ from what its extension pattern would give.
+pub fn file_asset_config(
+    path: &str,
+    content_type: String,
+    file_headers: Vec<HeaderField>,
+) -> AssetConfig {
+    AssetConfig::File {
+        path: path.to_string(),
+        content_type: Some(content_type),
+        headers: file_response_headers(file_headers),
+        fallback_for: vec![],
+        aliased_by: vec![],
+        encodings: vec![
+            AssetEncoding::Brotli.default_config(),
+            AssetEncoding::Gzip.default_config(),
+        ],
+    }
+}
+
+/// `file_configs` are matched before the extension patterns.
+pub fn certify_asset(assets: Vec<Asset<'static, '_>>, file_configs: Vec<AssetConfig>) {
+    let mut asset_configs = file_configs;
+    asset_configs.extend(get_asset_config());
 
     ASSET_ROUTER.with_borrow_mut(|asset_router| {
         // 4. Certify the assets using the `certify_assets` function from the `ic-asset-certification` crate.
@@ -234,8 +271,10 @@ pub fn certify_asset(assets: Vec<Asset<'static, '_>>) {
     });
 }
 
-pub fn uncertify_asset(assets: Vec<Asset<'static, '_>>) {
-    let asset_configs = get_asset_config();
+/// Must be given the same `file_configs` the assets were certified with.
+pub fn uncertify_asset(assets: Vec<Asset<'static, '_>>, file_configs: Vec<AssetConfig>) {
+    let mut asset_configs = file_configs;
+    asset_configs.extend(get_asset_config());
 
     ASSET_ROUTER.with_borrow_mut(|asset_router| {
         // 4. Certify the assets using the `certify_assets` function from the `ic-asset-certification` crate.
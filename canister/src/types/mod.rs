@@ -1,3 +1,5 @@
+pub mod chunks;
 pub mod http;
 pub mod management;
+pub mod sha256;
 pub mod storage;
@@ -0,0 +1,146 @@
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::compress256;
+use sha2::digest::generic_array::GenericArray;
+use sha2::Sha256;
+
+const INITIAL_STATE: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+const BLOCK_SIZE: usize = 64;
+
+/// SHA-256 whose intermediate state can be persisted in canister state.
+///
+/// `sha2::Sha256` cannot be serialized, so a running hash kept in upload
+/// metadata would not survive `pre_upgrade`. This keeps the raw compression
+/// state plus the unprocessed tail of the input instead.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct IncrementalSha256 {
+    state: [u32; 8],
+    #[serde(with = "serde_bytes")]
+    pending: Vec<u8>,
+    len: u64,
+}
+
+impl Default for IncrementalSha256 {
+    fn default() -> Self {
+        Self {
+            state: INITIAL_STATE,
+            pending: Vec::with_capacity(BLOCK_SIZE),
+            len: 0,
+        }
+    }
+}
+
+impl IncrementalSha256 {
+    pub fn update(&mut self, mut data: &[u8]) {
+        self.len += data.len() as u64;
+
+        if !self.pending.is_empty() {
+            let take = (BLOCK_SIZE - self.pending.len()).min(data.len());
+            self.pending.extend_from_slice(&data[..take]);
+            data = &data[take..];
+            if self.pending.len() < BLOCK_SIZE {
+                return;
+            }
+            let block = std::mem::take(&mut self.pending);
+            self.compress(&block);
+        }
+
+        let mut blocks = data.chunks_exact(BLOCK_SIZE);
+        for block in &mut blocks {
+            self.compress(block);
+        }
+        self.pending.extend_from_slice(blocks.remainder());
+    }
+
+    /// Number of input bytes fed to the hash so far.
+    pub fn bytes_hashed(&self) -> u64 {
+        self.len
+    }
+
+    pub fn finalize(mut self) -> [u8; 32] {
+        let bit_len = self.len * 8;
+        let mut tail = std::mem::take(&mut self.pending);
+        tail.push(0x80);
+        while tail.len() % BLOCK_SIZE != BLOCK_SIZE - 8 {
+            tail.push(0);
+        }
+        tail.extend_from_slice(&bit_len.to_be_bytes());
+        for block in tail.chunks_exact(BLOCK_SIZE) {
+            self.compress(block);
+        }
+
+        let mut digest = [0u8; 32];
+        for (out, word) in digest.chunks_exact_mut(4).zip(self.state) {
+            out.copy_from_slice(&word.to_be_bytes());
+        }
+        digest
+    }
+
+    fn compress(&mut self, block: &[u8]) {
+        compress256(&mut self.state, &[GenericArray::clone_from_slice(block)]);
+    }
+}
+
+/// HMAC-SHA256 of `message` under `key`, to be finalized or verified.
+pub fn hmac_sha256(key: &[u8], message: &[u8]) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes keys of any length");
+    mac.update(message);
+    mac
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha2::Digest;
+
+    fn reference(data: &[u8]) -> [u8; 32] {
+        Sha256::digest(data).into()
+    }
+
+    #[test]
+    fn matches_sha256_for_single_update() {
+        for len in [0, 1, 55, 56, 63, 64, 65, 119, 120, 1000] {
+            let data: Vec<u8> = (0..len).map(|i| (i % 251) as u8).collect();
+            let mut hasher = IncrementalSha256::default();
+            hasher.update(&data);
+            assert_eq!(hasher.finalize(), reference(&data), "len {len}");
+        }
+    }
+
+    #[test]
+    fn matches_sha256_across_uneven_updates() {
+        let data: Vec<u8> = (0..5000).map(|i| (i % 241) as u8).collect();
+        let mut hasher = IncrementalSha256::default();
+        for piece in data.chunks(37) {
+            hasher.update(piece);
+        }
+        assert_eq!(hasher.finalize(), reference(&data));
+    }
+
+    #[test]
+    fn hmac_matches_rfc_4231() {
+        assert_eq!(
+            hex::encode(
+                hmac_sha256(b"Jefe", b"what do ya want for nothing?")
+                    .finalize()
+                    .into_bytes()
+            ),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        // Keys longer than a block are hashed first.
+        assert_eq!(
+            hex::encode(
+                hmac_sha256(
+                    &[0xaa; 131],
+                    b"Test Using Larger Than Block-Size Key - Hash Key First"
+                )
+                .finalize()
+                .into_bytes()
+            ),
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+        );
+    }
+}
@@ -1,20 +1,39 @@
-use bity_ic_storage_canister_api::types::storage::UploadState;
-use bity_ic_storage_canister_api::{cancel_upload, finalize_upload, init_upload, store_chunk};
-use ic_asset_certification::Asset;
+use bity_ic_storage_canister_api::types::storage::{
+    BucketConfig, ContentEncoding, PrincipalQuota, UploadState, Visibility,
+};
+use bity_ic_storage_canister_api::{
+    cancel_upload, create_bucket, create_download_token, delete_bucket, finalize_upload,
+    get_file_metadata, get_finalization_progress, get_stored_files_size, get_upload_status,
+    init_upload, list_buckets, list_file_versions, list_files, list_principal_usage, list_trash,
+    purge_file, restore_file, restore_file_version, set_file_expiry, set_file_headers,
+    set_file_metadata, set_file_visibility, set_principal_quota, store_chunk, update_bucket,
+};
+use ic_asset_certification::{Asset, AssetConfig};
 // use icrc_ledger_types::icrc::generic_value::ICRC3Value as Value;
-use super::http::{certify_asset, uncertify_asset};
-use crate::memory::get_data_storage_memory;
+use super::chunks::{ChunkBitmap, ChunkKey};
+use super::http::{certify_asset, file_asset_config, uncertify_asset};
+use super::sha256::{hmac_sha256, IncrementalSha256};
 use crate::memory::VM;
-use crate::utils::{get_content_type_for_path, trace, validate_file_path};
+use crate::memory::{get_data_chunks_memory, get_data_storage_memory, get_upload_staging_memory};
+use crate::utils::{
+    accepts_encoding, get_content_type_for_path, is_sha256_hex, is_valid_header_value,
+    normalize_file_headers, trace, validate_file_path,
+};
+use bity_ic_storage_canister_api::batch_upload;
+use bity_ic_storage_canister_api::copy_file;
 use bity_ic_storage_canister_api::init_reupload;
+use bity_ic_storage_canister_api::move_file;
 use bity_ic_storage_canister_api::remove_file;
 use bity_ic_utils::env::CanisterEnv;
+use candid::Principal;
 use hex;
+use hmac::{Hmac, Mac};
 use ic_cdk::stable::{stable_size, WASM_PAGE_SIZE_IN_BYTES};
 use ic_stable_structures::StableBTreeMap;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
 const DEFAULT_CHUNK_SIZE: u64 = 1 * 1024 * 1024;
 
@@ -24,10 +43,62 @@ const DEFAULT_CHUNK_SIZE: u64 = 1 * 1024 * 1024;
 /// a single file can be up to ~10 GiB without forcing a huge chunk size.
 pub const MAX_CHUNKS_PER_FILE: u64 = 10_000;
 
-/// Hard cap on number of files (finalized + in-flight) tracked by one canister.
+/// Hard cap on number of files (finalized, in-flight and trashed) tracked by one canister.
 /// Bounds heap usage from metadata flooding: cap * sizeof(InternalRawStorageMetadata).
 pub const MAX_FILES_PER_CANISTER: usize = 100_000;
 
+/// Upper bound on entries in one `batch_upload` call. The ingress limit already
+/// caps the bytes; this caps the per-entry bookkeeping done in one message.
+pub const MAX_BATCH_UPLOAD_ENTRIES: usize = 1_000;
+
+/// Limits on the application metadata of one file, so that its heap footprint
+/// stays bounded like the rest of the per-file metadata.
+pub const MAX_METADATA_ENTRIES: usize = 16;
+pub const MAX_METADATA_KEY_BYTES: usize = 64;
+pub const MAX_METADATA_BYTES: usize = 1024;
+
+/// Page size of `list_files` when the caller gives none, and the largest allowed.
+const LIST_FILES_DEFAULT_LIMIT: usize = 100;
+const LIST_FILES_MAX_LIMIT: usize = 1_000;
+
+/// Previous versions kept per file unless configured otherwise at init/upgrade.
+pub const DEFAULT_VERSION_RETENTION: u32 = 3;
+
+/// How long a removed file stays restorable unless configured otherwise.
+pub const DEFAULT_TRASH_RETENTION_NANOS: u64 = 7 * 24 * 60 * 60 * 1_000_000_000;
+
+/// How many bytes of already-staged chunks a single `store_chunk` call may feed
+/// into the running hash when a gap in an out-of-order upload gets filled.
+/// Keeps each call well under the instruction limit; whatever is left over is
+/// hashed by a catch-up job, the same number of bytes per message, so finalize
+/// only has to compare digests.
+const HASH_CATCH_UP_BYTES_PER_CALL: u64 = 8 * DEFAULT_CHUNK_SIZE;
+
+/// How many bytes one finalization step may hash or move into the chunk store.
+/// Files above this finalize over several timer-driven messages.
+const FINALIZE_STEP_BYTES: u64 = 256 * DEFAULT_CHUNK_SIZE;
+
+/// Per-step finalization budget. Test mode uses a small one so integration
+/// tests exercise multi-step finalization without uploading huge files.
+pub fn finalize_step_bytes(env: &CanisterEnv) -> u64 {
+    if env.is_test_mode() {
+        16 * DEFAULT_CHUNK_SIZE
+    } else {
+        FINALIZE_STEP_BYTES
+    }
+}
+
+fn finalize_upload_resp(path: &str, state: UploadState) -> finalize_upload::FinalizeUploadResp {
+    finalize_upload::FinalizeUploadResp {
+        url: format!(
+            "https://{}.raw.icp0.io/{}",
+            ic_cdk::api::canister_self(),
+            path
+        ),
+        state: Some(state),
+    }
+}
+
 /// Time (ns) when this upload was initiated. Used by the abandoned-upload GC.
 /// On deserialization of old state (pre-S8), defaults to canister `now` so
 /// in-flight uploads survive an upgrade with a fresh TTL.
@@ -35,6 +106,19 @@ fn default_init_timestamp() -> u64 {
     ic_cdk::api::time()
 }
 
+/// Files finalized before version history existed are their path's first version.
+fn default_version() -> u64 {
+    1
+}
+
+fn default_version_retention() -> u32 {
+    DEFAULT_VERSION_RETENTION
+}
+
+fn default_trash_retention_nanos() -> u64 {
+    DEFAULT_TRASH_RETENTION_NANOS
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct InternalRawStorageMetadata {
     pub file_path: String,
@@ -42,17 +126,200 @@ pub struct InternalRawStorageMetadata {
     pub file_size: u64,
     pub received_size: u64,
     pub chunks_size: u64,
-    pub chunks: Vec<Vec<u8>>,
+    /// Chunks already written to the upload staging region.
+    #[serde(default)]
+    pub received_chunks: ChunkBitmap,
+    /// Chunks that canisters predating the staging region buffered on the heap.
+    /// Only ever read back from old state; see `migrate_heap_chunks`.
+    #[serde(default, rename = "chunks", skip_serializing)]
+    pub heap_chunks: Vec<Vec<u8>>,
+    /// Running SHA-256 over the leading chunks received so far.
+    #[serde(default)]
+    pub hash_state: IncrementalSha256,
+    /// Number of leading chunks already fed into `hash_state`.
+    #[serde(default)]
+    pub hashed_chunks: u64,
+    /// Owner of this file's chunks in `storage_chunks`. `None` for files
+    /// finalized before the chunk store existed, whose bytes are in `storage_raw`.
+    /// Set when finalization starts, so it also marks where promoted chunks go.
+    /// Once finalized, every path with the same content shares this key.
+    #[serde(default)]
+    pub content_key: Option<String>,
+    /// Bytes already moved from the staging region into the chunk store.
+    #[serde(default)]
+    pub promoted_size: u64,
+    /// Lowercase hex SHA-256 of every chunk, when a manifest was given at init.
+    #[serde(default)]
+    pub chunk_hashes: Vec<String>,
+    /// Explicit `content-type`; guessed from the path extension when `None`.
+    #[serde(default)]
+    pub content_type: Option<String>,
+    /// Normalized per-file response headers. On a pending reupload, `None`
+    /// means keeping those of the file being replaced.
+    #[serde(default)]
+    pub http_headers: Option<Vec<(String, String)>>,
+    /// Set on a pre-compressed variant of the file at this path minus the
+    /// encoding's suffix.
+    #[serde(default)]
+    pub encoding: Option<ContentEncoding>,
+    /// Chunks of a partial reupload taken from the file's current bytes
+    /// instead of being sent. Empty for every other upload.
+    #[serde(default)]
+    pub reused_chunks: ChunkBitmap,
+    /// Chunks before this index have had their reused bytes staged.
+    #[serde(default)]
+    pub reuse_cursor: u64,
     pub state: UploadState,
     #[serde(default = "default_init_timestamp")]
     pub init_timestamp: u64,
+    /// When the current bytes went live; `None` until finalized.
+    #[serde(default)]
+    pub finalized_timestamp: Option<u64>,
+    /// Application key/value metadata. Kept across reuploads of the path.
+    #[serde(default)]
+    pub user_metadata: BTreeMap<String, String>,
+    /// Version number at this path; each finalized reupload or restore adds one.
+    #[serde(default = "default_version")]
+    pub version: u64,
+    /// Time (ns) after which the file is no longer served and gets deleted.
+    /// Kept across reuploads of the path.
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+    /// Principal that uploaded these bytes, charged for them against its quota.
+    /// `None` for files uploaded before quotas existed.
+    #[serde(default)]
+    pub owner: Option<Principal>,
+    /// Kept across reuploads of the path. Ignored on variants, which follow
+    /// the file they encode.
+    #[serde(default)]
+    pub visibility: Visibility,
+}
+
+impl InternalRawStorageMetadata {
+    fn is_expired(&self, now: u64) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+
+    /// The next chunk to hash has arrived, so the running hash can move on.
+    fn has_unhashed_chunks(&self) -> bool {
+        self.received_chunks.get(self.hashed_chunks)
+    }
+
+    /// Feed staged chunks into the running hash in order, starting at the first
+    /// chunk not hashed yet. Stops at the first chunk that hasn't arrived, or once
+    /// `max_bytes` have been hashed.
+    fn advance_hash(
+        &mut self,
+        upload_key: &str,
+        staging: &StableBTreeMap<ChunkKey, Vec<u8>, VM>,
+        max_bytes: u64,
+    ) {
+        let mut hashed_bytes = 0;
+        while hashed_bytes < max_bytes && self.received_chunks.get(self.hashed_chunks) {
+            let Some(chunk) = staging.get(&ChunkKey::new(upload_key, self.hashed_chunks)) else {
+                break;
+            };
+            self.hash_state.update(&chunk);
+            hashed_bytes += chunk.len() as u64;
+            self.hashed_chunks += 1;
+        }
+    }
+}
+
+/// A finalized file as served on the raw path.
+pub struct FileData {
+    pub data: Vec<u8>,
+    pub content_type: String,
+    /// The file's own response headers, on top of the defaults.
+    pub headers: Vec<(String, String)>,
+    /// `content-encoding` of `data` when a pre-compressed variant was picked.
+    pub content_encoding: Option<&'static str>,
+    /// The file has pre-compressed variants, so the response varies with
+    /// `accept-encoding`.
+    pub has_variants: bool,
+}
+
+/// Variant encodings, most preferred first.
+const VARIANT_ENCODINGS: [ContentEncoding; 2] = [ContentEncoding::Brotli, ContentEncoding::Gzip];
+
+/// Path suffix of a variant, matching the asset router's default postfixes.
+fn encoding_suffix(encoding: ContentEncoding) -> &'static str {
+    match encoding {
+        ContentEncoding::Brotli => ".br",
+        ContentEncoding::Gzip => ".gz",
+    }
+}
+
+/// `content-encoding` token of a variant.
+fn encoding_token(encoding: ContentEncoding) -> &'static str {
+    match encoding {
+        ContentEncoding::Brotli => "br",
+        ContentEncoding::Gzip => "gzip",
+    }
+}
+
+/// Finalized bytes in the chunk store, shared by every path whose file hash
+/// matches. The bytes are freed when the last referencing path goes away.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct StoredContent {
+    pub content_key: String,
+    pub size: u64,
+    pub refs: u64,
+}
+
+/// A removed file waiting in the trash, with the variants and version history
+/// it had at its path. All their bytes are in the chunk store.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TrashedFile {
+    pub metadata: InternalRawStorageMetadata,
+    pub variants: Vec<InternalRawStorageMetadata>,
+    pub versions: Vec<InternalRawStorageMetadata>,
+    pub deleted_at: u64,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct StorageData {
     #[serde(skip, default = "init_storage_raw")]
     storage_raw: StableBTreeMap<String, Vec<u8>, VM>,
+    #[serde(skip, default = "init_storage_chunks")]
+    storage_chunks: StableBTreeMap<ChunkKey, Vec<u8>, VM>,
+    #[serde(skip, default = "init_upload_staging")]
+    upload_staging: StableBTreeMap<ChunkKey, Vec<u8>, VM>,
     storage_raw_internal_metadata: HashMap<String, InternalRawStorageMetadata>,
+    /// Finalized content by file hash. Files still in `storage_raw` are not
+    /// listed here and are never shared.
+    #[serde(default)]
+    contents: HashMap<String, StoredContent>,
+    /// Source of fresh `content_key`s. `#` cannot appear in a file path, so
+    /// `#<id>` keys never collide with upload keys.
+    #[serde(default)]
+    next_content_id: u64,
+    /// Previous versions of each reuploaded path, oldest first. Their bytes are
+    /// always in the chunk store and hold a reference on `contents`.
+    #[serde(default)]
+    versions: HashMap<String, Vec<InternalRawStorageMetadata>>,
+    /// How many previous versions to keep per path.
+    #[serde(default = "default_version_retention")]
+    version_retention: u32,
+    /// Removed files by path. A path is trashed at most once at a time.
+    #[serde(default)]
+    trash: HashMap<String, TrashedFile>,
+    #[serde(default = "default_trash_retention_nanos")]
+    trash_retention_nanos: u64,
+    /// Upload limits by principal. Principals without one are only bound by
+    /// the canister's storage size.
+    #[serde(default)]
+    quotas: HashMap<Principal, PrincipalQuota>,
+    /// Buckets by name. Each owns the paths under `<name>/`.
+    #[serde(default)]
+    buckets: BTreeMap<String, BucketConfig>,
+    /// Secret signing download tokens. Empty until the first token is created.
+    #[serde(default)]
+    download_token_key: Vec<u8>,
+    /// Paths whose hash catch-up job is scheduled. Timers don't survive an
+    /// upgrade, so neither does this; `start_jobs` schedules them again.
+    #[serde(skip)]
+    hash_catch_ups: BTreeSet<String>,
     certified_assets: Vec<String>,
     max_storage_size_wasm32: u128,
 }
@@ -62,11 +329,80 @@ fn init_storage_raw() -> StableBTreeMap<String, Vec<u8>, VM> {
     StableBTreeMap::init(memory)
 }
 
+fn init_storage_chunks() -> StableBTreeMap<ChunkKey, Vec<u8>, VM> {
+    let memory = get_data_chunks_memory();
+    StableBTreeMap::init(memory)
+}
+
+fn init_upload_staging() -> StableBTreeMap<ChunkKey, Vec<u8>, VM> {
+    let memory = get_upload_staging_memory();
+    StableBTreeMap::init(memory)
+}
+
+/// Remove every chunk belonging to `owner` from a chunk map.
+fn remove_chunks(chunks: &mut StableBTreeMap<ChunkKey, Vec<u8>, VM>, owner: &str) {
+    let keys: Vec<ChunkKey> = chunks.keys_range(ChunkKey::range(owner)).collect();
+    for key in keys {
+        chunks.remove(&key);
+    }
+}
+
+/// Lowercase letters, digits and dashes, not at either end, so a bucket name
+/// is a single path segment and a valid DNS label.
+fn is_valid_bucket_name(name: &str) -> bool {
+    (1..=63).contains(&name.len())
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-')
+        && !name.starts_with('-')
+        && !name.ends_with('-')
+}
+
+/// A lowercase DNS name, without scheme or port.
+fn is_valid_host(host: &str) -> bool {
+    (1..=253).contains(&host.len())
+        && host.split('.').all(|label| {
+            !label.is_empty()
+                && label
+                    .bytes()
+                    .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-')
+        })
+}
+
+/// Why a bucket configuration is rejected, shared by create and update.
+enum BucketConfigError {
+    InvalidHeaders,
+    InvalidHost,
+    HostInUse,
+}
+
+/// Whether `metadata` fits the `MAX_METADATA_*` limits.
+fn is_valid_user_metadata(metadata: &BTreeMap<String, String>) -> bool {
+    let total_bytes: usize = metadata.iter().map(|(k, v)| k.len() + v.len()).sum();
+    metadata.len() <= MAX_METADATA_ENTRIES
+        && total_bytes <= MAX_METADATA_BYTES
+        && metadata
+            .keys()
+            .all(|key| !key.is_empty() && key.len() <= MAX_METADATA_KEY_BYTES)
+}
+
 impl StorageData {
     pub fn new(max_storage_size_wasm32: u128) -> Self {
         Self {
             storage_raw: init_storage_raw(),
+            storage_chunks: init_storage_chunks(),
+            upload_staging: init_upload_staging(),
             storage_raw_internal_metadata: HashMap::new(),
+            contents: HashMap::new(),
+            next_content_id: 0,
+            versions: HashMap::new(),
+            version_retention: DEFAULT_VERSION_RETENTION,
+            trash: HashMap::new(),
+            trash_retention_nanos: DEFAULT_TRASH_RETENTION_NANOS,
+            quotas: HashMap::new(),
+            buckets: BTreeMap::new(),
+            download_token_key: Vec::new(),
+            hash_catch_ups: BTreeSet::new(),
             certified_assets: Vec::new(),
             max_storage_size_wasm32: max_storage_size_wasm32,
         }
@@ -97,336 +433,2387 @@ impl StorageData {
             .map(|m| m.file_size)
             .sum()
     }
-}
 
-impl StorageData {
-    pub fn init_upload(
-        &mut self,
-        data: init_upload::Args,
-    ) -> Result<init_upload::InitUploadResp, init_upload::InitUploadError> {
-        trace(&format!("init_upload - file_path: {:?}", data.file_path));
+    /// Logical size counts every tracked file; physical size counts shared
+    /// content once.
+    pub fn get_stored_files_size(&self) -> get_stored_files_size::StoredFilesSize {
+        let unshared_size_bytes: u64 = self
+            .storage_raw_internal_metadata
+            .values()
+            .filter(|m| m.state != UploadState::Finalized || m.content_key.is_none())
+            .map(|m| m.file_size)
+            .sum();
+        let shared_size_bytes: u64 = self.contents.values().map(|c| c.size).sum();
 
-        validate_file_path(&data.file_path)
-            .map_err(|_| init_upload::InitUploadError::InvalidFilePath)?;
+        get_stored_files_size::StoredFilesSize {
+            logical_size_bytes: self.get_stored_files_size_bytes(),
+            physical_size_bytes: unshared_size_bytes + shared_size_bytes,
+        }
+    }
 
-        // Normalize to canonical form (no leading slashes)
-        let path = data.file_path.trim_start_matches('/').to_string();
+    /// Read the full content of a finalized file from whichever store holds it.
+    fn read_file_bytes(
+        &self,
+        path: &str,
+        metadata: &InternalRawStorageMetadata,
+    ) -> Option<Vec<u8>> {
+        let Some(content_key) = &metadata.content_key else {
+            return self.storage_raw.get(&path.to_string());
+        };
 
-        // Bound the total number of files to keep metadata heap usage predictable.
-        if self.storage_raw_internal_metadata.len() >= MAX_FILES_PER_CANISTER {
-            return Err(init_upload::InitUploadError::TooManyFiles);
+        let mut data = Vec::with_capacity(metadata.file_size as usize);
+        for chunk in self
+            .storage_chunks
+            .values_range(ChunkKey::range(content_key))
+        {
+            data.extend(chunk);
+        }
+        if data.len() as u64 != metadata.file_size {
+            return None;
         }
+        Some(data)
+    }
 
-        // Check if the file already exists
-        let existing_metadata = self.storage_raw_internal_metadata.get(&path);
+    /// Read `[start, end)` of content in the chunk store. The chunk size is taken
+    /// from the first chunk, since content may have been stored with any size.
+    fn read_content_range(&self, content_key: &str, start: u64, end: u64) -> Option<Vec<u8>> {
+        let stored_chunk_size = self
+            .storage_chunks
+            .get(&ChunkKey::new(content_key, 0))?
+            .len() as u64;
+        if stored_chunk_size == 0 {
+            return None;
+        }
 
-        if existing_metadata.is_some() {
-            return Err(init_upload::InitUploadError::FileAlreadyExists);
+        let mut data = Vec::with_capacity((end - start) as usize);
+        let mut index = start / stored_chunk_size;
+        while (data.len() as u64) < end - start {
+            let chunk = self
+                .storage_chunks
+                .get(&ChunkKey::new(content_key, index))?;
+            let chunk_start = index * stored_chunk_size;
+            let from = start.max(chunk_start) - chunk_start;
+            let to = end.min(chunk_start + chunk.len() as u64) - chunk_start;
+            if from >= to {
+                return None;
+            }
+            data.extend_from_slice(&chunk[from as usize..to as usize]);
+            index += 1;
         }
+        Some(data)
+    }
 
-        // Standard storage check only necessary for clean uploads,
-        // since a re-upload keeps the file size identical and won't consume new permanent space.
-        if self.get_free_storage_size_bytes() < (data.file_size as u128) {
-            return Err(init_upload::InitUploadError::NotEnoughStorage);
+    /// Stage the chunks a partial reupload didn't send, copied from the bytes
+    /// currently served at `path`. Copies at most about `max_bytes` per call and
+    /// returns how many were copied, or `None` if the current bytes can't be read.
+    fn stage_reused_chunks(&mut self, path: &str, upload_key: &str, max_bytes: u64) -> Option<u64> {
+        let upload = self.storage_raw_internal_metadata.get(upload_key)?;
+        let num_chunks = upload.reused_chunks.num_chunks();
+        if upload.reuse_cursor >= num_chunks {
+            return Some(0);
         }
+        let reused_chunks = upload.reused_chunks.clone();
+        let chunk_size = upload.chunks_size;
+        let file_size = upload.file_size;
+        let mut cursor = upload.reuse_cursor;
 
-        let chunk_size = data.chunk_size.unwrap_or(DEFAULT_CHUNK_SIZE);
+        let current_key = self
+            .storage_raw_internal_metadata
+            .get(path)?
+            .content_key
+            .clone();
+        let mut legacy_bytes = None;
+
+        let mut copied = 0;
+        while cursor < num_chunks && copied < max_bytes {
+            if reused_chunks.get(cursor) {
+                let start = cursor * chunk_size;
+                let end = (start + chunk_size).min(file_size);
+                let chunk = match &current_key {
+                    Some(content_key) => self.read_content_range(content_key, start, end)?,
+                    None => {
+                        if legacy_bytes.is_none() {
+                            legacy_bytes = Some(self.storage_raw.get(&path.to_string())?);
+                        }
+                        legacy_bytes
+                            .as_ref()?
+                            .get(start as usize..end as usize)?
+                            .to_vec()
+                    }
+                };
+                copied += chunk.len() as u64;
+                self.upload_staging
+                    .insert(ChunkKey::new(upload_key, cursor), chunk);
+            }
+            cursor += 1;
+        }
 
-        if chunk_size > DEFAULT_CHUNK_SIZE || chunk_size < 1 {
-            return Err(init_upload::InitUploadError::InvalidChunkSize);
+        if let Some(upload) = self.storage_raw_internal_metadata.get_mut(upload_key) {
+            upload.reuse_cursor = cursor;
         }
+        Some(copied)
+    }
 
-        let num_chunks = if data.file_size == 0 {
-            0
-        } else {
-            (data.file_size + chunk_size - 1) / chunk_size
+    /// Drop the reference a finalized file at `path` holds on its bytes, freeing
+    /// them once no other path shares them.
+    fn release_file_bytes(&mut self, path: &str, metadata: &InternalRawStorageMetadata) {
+        let Some(content_key) = &metadata.content_key else {
+            self.storage_raw.remove(&path.to_string());
+            return;
         };
 
-        if num_chunks > MAX_CHUNKS_PER_FILE {
-            return Err(init_upload::InitUploadError::TooManyChunks);
+        if let Some(content) = self.contents.get_mut(&metadata.file_hash) {
+            if &content.content_key == content_key {
+                content.refs = content.refs.saturating_sub(1);
+                if content.refs > 0 {
+                    return;
+                }
+                self.contents.remove(&metadata.file_hash);
+            }
         }
+        remove_chunks(&mut self.storage_chunks, content_key);
+    }
 
-        let metadata = InternalRawStorageMetadata {
-            file_path: path.clone(),
-            file_hash: data.file_hash,
-            file_size: data.file_size,
-            received_size: 0,
-            chunks_size: chunk_size,
-            chunks: vec![vec![]; num_chunks as usize],
-            state: UploadState::Init,
-            init_timestamp: ic_cdk::api::time(),
+    /// Write `bytes` to the chunk store as new shareable content with one
+    /// reference, and return its key.
+    fn store_content(&mut self, file_hash: &str, bytes: Vec<u8>) -> String {
+        let content_key = self.allocate_content_key();
+        for (index, chunk) in bytes.chunks(DEFAULT_CHUNK_SIZE as usize).enumerate() {
+            self.storage_chunks
+                .insert(ChunkKey::new(&content_key, index as u64), chunk.to_vec());
+        }
+        self.contents.insert(
+            file_hash.to_string(),
+            StoredContent {
+                content_key: content_key.clone(),
+                size: bytes.len() as u64,
+                refs: 1,
+            },
+        );
+        content_key
+    }
+
+    /// Move the bytes of a finalized file that predates the chunk store there,
+    /// where a later upload to `path` can't overwrite them. Returns `false` if
+    /// they can't be found.
+    fn move_to_chunk_store(
+        &mut self,
+        path: &str,
+        metadata: &mut InternalRawStorageMetadata,
+    ) -> bool {
+        if metadata.content_key.is_some() {
+            return true;
+        }
+        let Some(bytes) = self.storage_raw.remove(&path.to_string()) else {
+            return false;
+        };
+        let content_key = match self.contents.get_mut(&metadata.file_hash) {
+            Some(content) => {
+                content.refs += 1;
+                content.content_key.clone()
+            }
+            None => self.store_content(&metadata.file_hash, bytes),
+        };
+        metadata.content_key = Some(content_key);
+        true
+    }
+
+    /// Keep `previous`, just replaced at `path`, as the newest entry of the
+    /// path's version history, then prune the history to the retention count.
+    fn archive_version(&mut self, path: &str, mut previous: InternalRawStorageMetadata) {
+        if self.version_retention == 0 {
+            self.release_file_bytes(path, &previous);
+            return;
+        }
+
+        if !self.move_to_chunk_store(path, &mut previous) {
+            return;
+        }
+
+        self.versions
+            .entry(path.to_string())
+            .or_default()
+            .push(previous);
+        self.prune_versions(path);
+    }
+
+    /// Release the oldest versions of `path` beyond the retention count.
+    fn prune_versions(&mut self, path: &str) {
+        let Some(history) = self.versions.get_mut(path) else {
+            return;
         };
+        let excess = history
+            .len()
+            .saturating_sub(self.version_retention as usize);
+        let pruned: Vec<InternalRawStorageMetadata> = history.drain(..excess).collect();
+        if history.is_empty() {
+            self.versions.remove(path);
+        }
+        for version in pruned {
+            self.release_file_bytes(path, &version);
+        }
+    }
 
-        self.storage_raw_internal_metadata.insert(path, metadata);
+    /// Forget the whole version history of `path`.
+    fn drop_versions(&mut self, path: &str) {
+        for version in self.versions.remove(path).unwrap_or_default() {
+            self.release_file_bytes(path, &version);
+        }
+    }
 
-        Ok(init_upload::InitUploadResp {})
+    /// Change how many previous versions are kept per path. Histories already
+    /// longer than that are pruned right away.
+    pub fn set_version_retention(&mut self, retention: u32) {
+        self.version_retention = retention;
+        let paths: Vec<String> = self.versions.keys().cloned().collect();
+        for path in paths {
+            self.prune_versions(&path);
+        }
     }
 
-    pub fn init_reupload(
-        &mut self,
-        data: init_reupload::Args,
-    ) -> Result<init_reupload::InitReuploadResp, init_reupload::InitReuploadError> {
-        trace(&format!("init_reupload - file_path: {:?}", data.file_path));
+    /// Move the finalized file at `path` to the trash together with its variants
+    /// and version history, or delete them all if the trash is disabled.
+    fn trash_file(&mut self, path: &str) {
+        if self.trash_retention_nanos == 0 {
+            self.delete_file(path);
+            return;
+        }
 
-        validate_file_path(&data.file_path)
-            .map_err(|_| init_reupload::InitReuploadError::InvalidFilePath)?;
+        let Some(mut metadata) = self.storage_raw_internal_metadata.remove(path) else {
+            return;
+        };
+        // Without its bytes there is nothing to restore; delete the rest.
+        if !self.move_to_chunk_store(path, &mut metadata) {
+            self.remove_variants(path);
+            self.drop_versions(path);
+            return;
+        }
 
-        let path = data.file_path.trim_start_matches('/').to_string();
+        // Only the latest removal of a path is kept.
+        self.purge_trashed(path);
 
-        let existing_metadata = self
-            .storage_raw_internal_metadata
-            .get(&path)
-            .ok_or(init_reupload::InitReuploadError::FileNotFound)?;
+        // A variant whose bytes can't be found holds no reference to release.
+        let mut variants = Vec::new();
+        for (_, variant_path) in self.variant_paths(path) {
+            if let Some(mut variant) = self.storage_raw_internal_metadata.remove(&variant_path) {
+                if self.move_to_chunk_store(&variant_path, &mut variant) {
+                    variants.push(variant);
+                }
+            }
+        }
+        let versions = self.versions.remove(path).unwrap_or_default();
+
+        self.trash.insert(
+            path.to_string(),
+            TrashedFile {
+                metadata,
+                variants,
+                versions,
+                deleted_at: ic_cdk::api::time(),
+            },
+        );
+    }
 
-        if existing_metadata.state != UploadState::Finalized {
-            return Err(init_reupload::InitReuploadError::FileNotFound);
+    /// Delete the finalized file at `path` for good, with its variants and
+    /// version history. The caller uncertifies it first.
+    fn delete_file(&mut self, path: &str) {
+        self.remove_variants(path);
+        if let Some(metadata) = self.storage_raw_internal_metadata.remove(path) {
+            self.release_file_bytes(path, &metadata);
         }
+        self.drop_versions(path);
+    }
 
-        if existing_metadata.file_size != data.file_size {
-            return Err(init_reupload::InitReuploadError::FileSizeMismatch);
+    /// Delete the trashed file at `path` for good. Returns `false` if there is none.
+    fn purge_trashed(&mut self, path: &str) -> bool {
+        let Some(trashed) = self.trash.remove(path) else {
+            return false;
+        };
+        self.release_file_bytes(path, &trashed.metadata);
+        for metadata in trashed.variants.iter().chain(trashed.versions.iter()) {
+            self.release_file_bytes(&metadata.file_path, metadata);
         }
+        true
+    }
 
-        let chunk_size = data.chunk_size.unwrap_or(DEFAULT_CHUNK_SIZE);
+    /// Change how long removed files stay restorable. Files trashed longer ago
+    /// than that go at the next sweep.
+    pub fn set_trash_retention_secs(&mut self, retention_secs: u64) {
+        self.trash_retention_nanos = retention_secs.saturating_mul(1_000_000_000);
+    }
 
-        if chunk_size == 0 || chunk_size > DEFAULT_CHUNK_SIZE {
-            return Err(init_reupload::InitReuploadError::InvalidChunkSize);
+    /// Trashed files and their variants. They keep their bytes, owner and
+    /// path until purged, so they count like live files.
+    fn trashed_files(&self) -> impl Iterator<Item = &InternalRawStorageMetadata> {
+        self.trash
+            .values()
+            .flat_map(|trashed| std::iter::once(&trashed.metadata).chain(&trashed.variants))
+    }
+
+    /// Files counted against `MAX_FILES_PER_CANISTER`.
+    fn file_count(&self) -> usize {
+        self.storage_raw_internal_metadata.len() + self.trashed_files().count()
+    }
+
+    /// Bytes and number of files at every path whose current bytes `principal`
+    /// uploaded, uploads in progress and trashed files included. Pending
+    /// reuploads are charged once they replace the file.
+    fn principal_usage(&self, principal: Principal) -> (u64, u64) {
+        self.storage_raw_internal_metadata
+            .iter()
+            .filter(|(path, _)| !path.starts_with('?'))
+            .map(|(_, metadata)| metadata)
+            .chain(self.trashed_files())
+            .filter(|metadata| metadata.owner == Some(principal))
+            .fold((0, 0), |(bytes, files), metadata| {
+                (bytes + metadata.file_size, files + 1)
+            })
+    }
+
+    /// Whether `principal` stays within its quota after releasing `freed_bytes`
+    /// of its own and adding `added_files` files of `added_bytes`.
+    fn fits_quota(
+        &self,
+        principal: Principal,
+        freed_bytes: u64,
+        added_bytes: u64,
+        added_files: u64,
+    ) -> bool {
+        let Some(quota) = self.quotas.get(&principal) else {
+            return true;
+        };
+        let (used_bytes, used_files) = self.principal_usage(principal);
+        let bytes = used_bytes.saturating_sub(freed_bytes) + added_bytes;
+        quota.max_bytes.is_none_or(|max_bytes| bytes <= max_bytes)
+            && (added_files == 0
+                || quota
+                    .max_files
+                    .is_none_or(|max_files| used_files + added_files <= max_files))
+    }
+
+    /// Bytes and file count `principal` gets back when the file at `path` is
+    /// replaced: its size if it is theirs, otherwise a new file to count.
+    fn replaced_usage(&self, path: &str, principal: Principal) -> (u64, u64) {
+        match self.storage_raw_internal_metadata.get(path) {
+            Some(metadata) if metadata.owner == Some(principal) => (metadata.file_size, 0),
+            _ => (0, 1),
         }
+    }
 
-        let num_chunks = if data.file_size == 0 {
-            0
+    pub fn set_principal_quota(
+        &mut self,
+        data: set_principal_quota::Args,
+    ) -> set_principal_quota::SetPrincipalQuotaResp {
+        if data.quota == PrincipalQuota::default() {
+            self.quotas.remove(&data.principal);
         } else {
-            (data.file_size + chunk_size - 1) / chunk_size
+            self.quotas.insert(data.principal, data.quota);
+        }
+        set_principal_quota::SetPrincipalQuotaResp {}
+    }
+
+    /// Usage and quota of every principal that has either, in principal order.
+    pub fn list_principal_usage(&self) -> list_principal_usage::ListPrincipalUsageResp {
+        let mut usage: BTreeMap<Principal, (u64, u64)> = self
+            .quotas
+            .keys()
+            .map(|principal| (*principal, (0, 0)))
+            .collect();
+        let charged = self
+            .storage_raw_internal_metadata
+            .iter()
+            .filter(|(path, _)| !path.starts_with('?'))
+            .map(|(_, metadata)| metadata)
+            .chain(self.trashed_files());
+        for metadata in charged {
+            if let Some(owner) = metadata.owner {
+                let (bytes, files) = usage.entry(owner).or_default();
+                *bytes += metadata.file_size;
+                *files += 1;
+            }
+        }
+
+        let principals = usage
+            .into_iter()
+            .map(
+                |(principal, (used_bytes, file_count))| list_principal_usage::PrincipalUsage {
+                    principal,
+                    used_bytes,
+                    file_count,
+                    quota: self.quotas.get(&principal).cloned().unwrap_or_default(),
+                },
+            )
+            .collect();
+
+        list_principal_usage::ListPrincipalUsageResp { principals }
+    }
+
+    /// Name and settings of the bucket that `path` belongs to, if any.
+    fn bucket_of(&self, path: &str) -> Option<(&String, &BucketConfig)> {
+        let (name, _) = path.split_once('/')?;
+        self.buckets.get_key_value(name)
+    }
+
+    /// Headers to serve the file at `path` with: its own, or else its bucket's.
+    fn file_headers(
+        &self,
+        path: &str,
+        metadata: &InternalRawStorageMetadata,
+    ) -> Vec<(String, String)> {
+        match &metadata.http_headers {
+            Some(headers) => headers.clone(),
+            None => self
+                .bucket_of(path)
+                .map(|(_, bucket)| bucket.headers.clone())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Bytes and number of files under the bucket `name`, uploads in progress
+    /// and trashed files included.
+    fn bucket_usage(&self, name: &str) -> (u64, u64) {
+        let prefix = format!("{}/", name);
+        self.storage_raw_internal_metadata
+            .iter()
+            .filter(|(path, _)| path.starts_with(&prefix))
+            .map(|(_, metadata)| metadata)
+            .chain(
+                self.trashed_files()
+                    .filter(|metadata| metadata.file_path.starts_with(&prefix)),
+            )
+            .fold((0, 0), |(bytes, files), metadata| {
+                (bytes + metadata.file_size, files + 1)
+            })
+    }
+
+    /// Whether the bucket of `path` stays within its byte limit when a file of
+    /// `freed_bytes` there is replaced by one of `added_bytes`.
+    fn fits_bucket(&self, path: &str, freed_bytes: u64, added_bytes: u64) -> bool {
+        let Some((name, bucket)) = self.bucket_of(path) else {
+            return true;
+        };
+        let Some(max_bytes) = bucket.max_bytes else {
+            return true;
         };
+        let (used_bytes, _) = self.bucket_usage(name);
+        used_bytes.saturating_sub(freed_bytes) + added_bytes <= max_bytes
+    }
 
-        if num_chunks > MAX_CHUNKS_PER_FILE {
-            return Err(init_reupload::InitReuploadError::TooManyChunks);
+    /// Principal that uploaded the file, or is uploading it, at `path`.
+    pub fn file_owner(&self, path: &str) -> Option<Principal> {
+        self.storage_raw_internal_metadata
+            .get(path.trim_start_matches('/'))
+            .and_then(|metadata| metadata.owner)
+    }
+
+    pub fn bucket(&self, name: &str) -> Option<&BucketConfig> {
+        self.buckets.get(name)
+    }
+
+    pub fn is_bucket_writer(&self, principal: Principal) -> bool {
+        self.buckets
+            .values()
+            .any(|bucket| bucket.writers.contains(&principal))
+    }
+
+    /// Bucket serving requests for `host`, ignoring any port.
+    pub fn bucket_for_host(&self, host: &str) -> Option<&str> {
+        let host = host.split(':').next().unwrap_or(host).to_ascii_lowercase();
+        self.buckets
+            .iter()
+            .find(|(_, bucket)| bucket.hosts.contains(&host))
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Check a bucket's configuration and bring it to canonical form: lowercase
+    /// header names and hosts. `name` is the bucket being configured, whose own
+    /// hosts may be kept.
+    fn normalize_bucket_config(
+        &self,
+        name: &str,
+        mut config: BucketConfig,
+    ) -> Result<BucketConfig, BucketConfigError> {
+        config.headers =
+            normalize_file_headers(config.headers).ok_or(BucketConfigError::InvalidHeaders)?;
+        for host in config.hosts.iter_mut() {
+            *host = host.to_ascii_lowercase();
+            if !is_valid_host(host) {
+                return Err(BucketConfigError::InvalidHost);
+            }
+        }
+        let host_in_use = self.buckets.iter().any(|(other, bucket)| {
+            other != name && bucket.hosts.iter().any(|host| config.hosts.contains(host))
+        });
+        if host_in_use {
+            return Err(BucketConfigError::HostInUse);
         }
+        Ok(config)
+    }
 
-        let reupload_key = format!("?reupload:{}", path);
+    /// Drop the certified responses of every file in the bucket `name`, whose
+    /// default headers may have changed.
+    fn uncertify_bucket(&mut self, name: &str) {
+        let prefix = format!("{}/", name);
+        let certified: Vec<String> = self
+            .certified_assets
+            .iter()
+            .filter(|path| path.starts_with(&prefix))
+            .cloned()
+            .collect();
+        for path in certified {
+            self.uncertify_path(&path);
+        }
+    }
 
-        self.storage_raw_internal_metadata.insert(
-            reupload_key,
-            InternalRawStorageMetadata {
-                file_path: path,
-                file_hash: data.file_hash,
-                file_size: data.file_size,
-                received_size: 0,
-                chunks_size: chunk_size,
-                chunks: vec![vec![]; num_chunks as usize],
-                state: UploadState::InitReupload,
-                init_timestamp: ic_cdk::api::time(),
-            },
-        );
+    pub fn create_bucket(
+        &mut self,
+        data: create_bucket::Args,
+    ) -> Result<create_bucket::CreateBucketResp, create_bucket::CreateBucketError> {
+        trace(&format!("create_bucket - name: {:?}", data.name));
+
+        if !is_valid_bucket_name(&data.name) {
+            return Err(create_bucket::CreateBucketError::InvalidBucketName);
+        }
+        if self.buckets.contains_key(&data.name) {
+            return Err(create_bucket::CreateBucketError::BucketAlreadyExists);
+        }
+        // A bucket starts empty, so its limits and writers never apply to files
+        // stored without them.
+        let prefix = format!("{}/", data.name);
+        let reupload_prefix = format!("?reupload:{}", prefix);
+        if self
+            .storage_raw_internal_metadata
+            .keys()
+            .chain(self.trash.keys())
+            .any(|path| path.starts_with(&prefix) || path.starts_with(&reupload_prefix))
+        {
+            return Err(create_bucket::CreateBucketError::PrefixInUse);
+        }
+        let config = self
+            .normalize_bucket_config(&data.name, data.config)
+            .map_err(|e| match e {
+                BucketConfigError::InvalidHeaders => {
+                    create_bucket::CreateBucketError::InvalidHeaders
+                }
+                BucketConfigError::InvalidHost => create_bucket::CreateBucketError::InvalidHost,
+                BucketConfigError::HostInUse => create_bucket::CreateBucketError::HostInUse,
+            })?;
+
+        self.uncertify_bucket(&data.name);
+        self.buckets.insert(data.name, config);
+
+        Ok(create_bucket::CreateBucketResp {})
+    }
+
+    pub fn update_bucket(
+        &mut self,
+        data: update_bucket::Args,
+    ) -> Result<update_bucket::UpdateBucketResp, update_bucket::UpdateBucketError> {
+        trace(&format!("update_bucket - name: {:?}", data.name));
+
+        if !self.buckets.contains_key(&data.name) {
+            return Err(update_bucket::UpdateBucketError::BucketNotFound);
+        }
+        let config = self
+            .normalize_bucket_config(&data.name, data.config)
+            .map_err(|e| match e {
+                BucketConfigError::InvalidHeaders => {
+                    update_bucket::UpdateBucketError::InvalidHeaders
+                }
+                BucketConfigError::InvalidHost => update_bucket::UpdateBucketError::InvalidHost,
+                BucketConfigError::HostInUse => update_bucket::UpdateBucketError::HostInUse,
+            })?;
+
+        self.uncertify_bucket(&data.name);
+        self.buckets.insert(data.name, config);
+
+        Ok(update_bucket::UpdateBucketResp {})
+    }
+
+    /// Delete an empty bucket. Its paths then belong to the canister-wide
+    /// namespace again.
+    pub fn delete_bucket(
+        &mut self,
+        data: delete_bucket::Args,
+    ) -> Result<delete_bucket::DeleteBucketResp, delete_bucket::DeleteBucketError> {
+        trace(&format!("delete_bucket - name: {:?}", data.name));
+
+        if !self.buckets.contains_key(&data.name) {
+            return Err(delete_bucket::DeleteBucketError::BucketNotFound);
+        }
+        let prefix = format!("{}/", data.name);
+        let in_use = self
+            .storage_raw_internal_metadata
+            .keys()
+            .chain(self.trash.keys())
+            .any(|path| path.trim_start_matches("?reupload:").starts_with(&prefix));
+        if in_use {
+            return Err(delete_bucket::DeleteBucketError::BucketNotEmpty);
+        }
+
+        self.buckets.remove(&data.name);
+
+        Ok(delete_bucket::DeleteBucketResp {})
+    }
+
+    pub fn list_buckets(&self) -> list_buckets::ListBucketsResp {
+        let buckets = self
+            .buckets
+            .iter()
+            .map(|(name, config)| {
+                let (used_bytes, file_count) = self.bucket_usage(name);
+                list_buckets::BucketInfo {
+                    name: name.clone(),
+                    config: config.clone(),
+                    used_bytes,
+                    file_count,
+                }
+            })
+            .collect();
+
+        list_buckets::ListBucketsResp { buckets }
+    }
+
+    fn allocate_content_key(&mut self) -> String {
+        let id = self.next_content_id;
+        self.next_content_id += 1;
+        format!("#{}", id)
+    }
+
+    /// Whether a reupload of the file at `path` is in flight.
+    pub fn is_reupload_pending(&self, path: &str) -> bool {
+        self.storage_raw_internal_metadata
+            .contains_key(&format!("?reupload:{}", path.trim_start_matches('/')))
+    }
+
+    /// Metadata key of the upload in flight for `path`: the pending reupload if
+    /// there is one, the path itself otherwise.
+    fn upload_key(&self, path: &str) -> String {
+        let reupload_key = format!("?reupload:{}", path);
+        if self
+            .storage_raw_internal_metadata
+            .contains_key(&reupload_key)
+        {
+            reupload_key
+        } else {
+            path.to_string()
+        }
+    }
+
+    /// Forget an in-flight upload and release everything it staged, plus any
+    /// chunks a `Finalizing` upload already promoted. Those sit under a key of
+    /// their own until finalization completes, so no other file shares them.
+    /// Never call this for a finalized file.
+    fn discard_upload(&mut self, upload_key: &str) {
+        if let Some(metadata) = self.storage_raw_internal_metadata.remove(upload_key) {
+            if metadata.state == UploadState::Finalizing {
+                if let Some(content_key) = &metadata.content_key {
+                    remove_chunks(&mut self.storage_chunks, content_key);
+                }
+            }
+        }
+        remove_chunks(&mut self.upload_staging, upload_key);
+    }
+
+    /// Stop a finalizing upload for `error`: free its bytes but keep the entry,
+    /// marked `Failed`, so the uploader can still query what went wrong.
+    fn fail_upload(&mut self, upload_key: &str, error: &finalize_upload::FinalizeUploadError) {
+        let Some(metadata) = self.storage_raw_internal_metadata.get_mut(upload_key) else {
+            return;
+        };
+        if metadata.state == UploadState::Finalizing {
+            if let Some(content_key) = metadata.content_key.take() {
+                remove_chunks(&mut self.storage_chunks, &content_key);
+            }
+        }
+        metadata.state = UploadState::Failed(format!("{error:?}"));
+        metadata.promoted_size = 0;
+        remove_chunks(&mut self.upload_staging, upload_key);
+    }
+
+    /// Drop `path` from the certified asset cache if it is currently certified.
+    /// A variant is certified along with the file it encodes, so that file is
+    /// dropped instead. Must run before the bytes involved change.
+    fn uncertify_path(&mut self, path: &str) {
+        let path = match self
+            .storage_raw_internal_metadata
+            .get(path)
+            .and_then(|metadata| metadata.encoding)
+        {
+            Some(encoding) => path
+                .strip_suffix(encoding_suffix(encoding))
+                .unwrap_or(path)
+                .to_string(),
+            None => path.to_string(),
+        };
+        if !self.certified_assets.contains(&path) {
+            return;
+        }
+        let (assets, _) = self.certification_assets(&path);
+        if !assets.is_empty() {
+            uncertify_asset(assets, self.asset_configs(&path));
+        }
+        self.certified_assets.retain(|asset| *asset != path);
+    }
+
+    /// Finalized pre-compressed variants of the file at `path`, most preferred
+    /// first.
+    fn variant_paths(&self, path: &str) -> Vec<(ContentEncoding, String)> {
+        VARIANT_ENCODINGS
+            .iter()
+            .map(|&encoding| (encoding, format!("{}{}", path, encoding_suffix(encoding))))
+            .filter(|(encoding, variant_path)| {
+                self.storage_raw_internal_metadata
+                    .get(variant_path)
+                    .is_some_and(|metadata| {
+                        metadata.state == UploadState::Finalized
+                            && metadata.encoding == Some(*encoding)
+                    })
+            })
+            .collect()
+    }
+
+    /// The assets certified for the file at `path`, and their total size: its
+    /// own bytes followed by those of its variants, which the asset router
+    /// links by path suffix. Empty if the file's bytes can't be read.
+    fn certification_assets(&self, path: &str) -> (Vec<Asset<'static, 'static>>, u64) {
+        let Some(data) = self
+            .storage_raw_internal_metadata
+            .get(path)
+            .and_then(|metadata| self.read_file_bytes(path, metadata))
+        else {
+            return (vec![], 0);
+        };
+        let mut size = data.len() as u64;
+        let mut assets = vec![Asset::new(path.to_string(), data)];
+        for (_, variant_path) in self.variant_paths(path) {
+            if let Some(data) = self
+                .storage_raw_internal_metadata
+                .get(&variant_path)
+                .and_then(|metadata| self.read_file_bytes(&variant_path, metadata))
+            {
+                size += data.len() as u64;
+                assets.push(Asset::new(variant_path, data));
+            }
+        }
+        (assets, size)
+    }
+
+    /// Remove the variants of the file at `path`, which no longer encode its
+    /// bytes. The file must already be out of the certified cache.
+    fn remove_variants(&mut self, path: &str) {
+        for (_, variant_path) in self.variant_paths(path) {
+            if let Some(metadata) = self.storage_raw_internal_metadata.remove(&variant_path) {
+                self.release_file_bytes(&variant_path, &metadata);
+            }
+        }
+    }
+
+    /// Certification configs specific to the file at `path`, taking precedence
+    /// over the extension patterns: one if it has an explicit `content_type`,
+    /// headers of its own or variants.
+    fn asset_configs(&self, path: &str) -> Vec<AssetConfig> {
+        let Some(metadata) = self.storage_raw_internal_metadata.get(path) else {
+            return vec![];
+        };
+        let mut file_headers = self.file_headers(path, metadata);
+        if !self.variant_paths(path).is_empty() {
+            file_headers.push(("vary".to_string(), "accept-encoding".to_string()));
+        }
+        if metadata.content_type.is_none() && file_headers.is_empty() {
+            return vec![];
+        }
+        let content_type = metadata
+            .content_type
+            .clone()
+            .unwrap_or_else(|| get_content_type_for_path(path).to_string());
+        vec![file_asset_config(path, content_type, file_headers)]
+    }
+
+    /// Move chunks that older canister versions buffered on the heap into the
+    /// staging region. Called once from `post_upgrade`; finalized entries never
+    /// carry heap chunks, so only in-flight uploads are touched.
+    pub fn migrate_heap_chunks(&mut self) {
+        for (key, metadata) in self.storage_raw_internal_metadata.iter_mut() {
+            if metadata.heap_chunks.is_empty() {
+                continue;
+            }
+            let chunks = std::mem::take(&mut metadata.heap_chunks);
+            metadata.received_chunks = ChunkBitmap::new(chunks.len() as u64);
+            for (index, chunk) in chunks.into_iter().enumerate() {
+                if chunk.is_empty() {
+                    continue;
+                }
+                metadata.received_chunks.set(index as u64);
+                self.upload_staging
+                    .insert(ChunkKey::new(key, index as u64), chunk);
+            }
+        }
+    }
+}
+
+impl StorageData {
+    pub fn init_upload(
+        &mut self,
+        data: init_upload::Args,
+        caller: Principal,
+    ) -> Result<init_upload::InitUploadResp, init_upload::InitUploadError> {
+        trace(&format!("init_upload - file_path: {:?}", data.file_path));
+
+        validate_file_path(&data.file_path)
+            .map_err(|_| init_upload::InitUploadError::InvalidFilePath)?;
+
+        // Normalize to canonical form (no leading slashes)
+        let path = data.file_path.trim_start_matches('/').to_string();
+
+        // Bound the total number of files to keep metadata heap usage predictable.
+        if self.file_count() >= MAX_FILES_PER_CANISTER {
+            return Err(init_upload::InitUploadError::TooManyFiles);
+        }
+
+        let user_metadata = data.metadata.unwrap_or_default();
+        if !is_valid_user_metadata(&user_metadata) {
+            return Err(init_upload::InitUploadError::InvalidMetadata);
+        }
+
+        if !data
+            .content_type
+            .as_deref()
+            .is_none_or(is_valid_header_value)
+        {
+            return Err(init_upload::InitUploadError::InvalidContentType);
+        }
+
+        let http_headers = data
+            .headers
+            .map(normalize_file_headers)
+            .map(|headers| headers.ok_or(init_upload::InitUploadError::InvalidHeaders))
+            .transpose()?;
+
+        if data
+            .expires_at
+            .is_some_and(|expires_at| expires_at <= ic_cdk::api::time())
+        {
+            return Err(init_upload::InitUploadError::InvalidExpiresAt);
+        }
+
+        if let Some(encoding) = data.encoding {
+            let base_path = path
+                .strip_suffix(encoding_suffix(encoding))
+                .ok_or(init_upload::InitUploadError::InvalidEncoding)?;
+            let base_is_file = self
+                .storage_raw_internal_metadata
+                .get(base_path)
+                .is_some_and(|base| {
+                    base.state == UploadState::Finalized && base.encoding.is_none()
+                });
+            if !base_is_file {
+                return Err(init_upload::InitUploadError::BaseFileNotFound);
+            }
+        }
+
+        // A failed upload only blocks the path until it is uploaded again.
+        if self
+            .storage_raw_internal_metadata
+            .get(&path)
+            .is_some_and(|metadata| matches!(metadata.state, UploadState::Failed(_)))
+        {
+            self.discard_upload(&path);
+        }
+
+        // Check if the file already exists
+        let existing_metadata = self.storage_raw_internal_metadata.get(&path);
+
+        if existing_metadata.is_some() {
+            return Err(init_upload::InitUploadError::FileAlreadyExists);
+        }
+
+        if !self.fits_quota(caller, 0, data.file_size, 1)
+            || !self.fits_bucket(&path, 0, data.file_size)
+        {
+            return Err(init_upload::InitUploadError::QuotaExceeded);
+        }
+
+        let chunk_size = data.chunk_size.unwrap_or(DEFAULT_CHUNK_SIZE);
+
+        if chunk_size > DEFAULT_CHUNK_SIZE || chunk_size < 1 {
+            return Err(init_upload::InitUploadError::InvalidChunkSize);
+        }
+
+        let num_chunks = if data.file_size == 0 {
+            0
+        } else {
+            (data.file_size + chunk_size - 1) / chunk_size
+        };
+
+        if num_chunks > MAX_CHUNKS_PER_FILE {
+            return Err(init_upload::InitUploadError::TooManyChunks);
+        }
+
+        let chunk_hashes = match data.chunk_hashes {
+            None => vec![],
+            Some(hashes) => {
+                if hashes.len() as u64 != num_chunks || !hashes.iter().all(|h| is_sha256_hex(h)) {
+                    return Err(init_upload::InitUploadError::InvalidChunkHashes);
+                }
+                hashes.iter().map(|h| h.to_ascii_lowercase()).collect()
+            }
+        };
+
+        // Identical content is already stored: point the path at it, no chunks needed.
+        // Content of private files is only shared with uploads that send its
+        // bytes, so knowing its hash isn't enough to claim it.
+        let file_hash = data.file_hash.to_ascii_lowercase();
+        let is_shareable =
+            self.contents.contains_key(&file_hash) && !self.is_content_private(&file_hash);
+        if let Some(content) = self.contents.get_mut(&file_hash).filter(|_| is_shareable) {
+            if content.size == data.file_size {
+                content.refs += 1;
+                let metadata = InternalRawStorageMetadata {
+                    file_path: path.clone(),
+                    file_hash,
+                    file_size: data.file_size,
+                    received_size: data.file_size,
+                    chunks_size: chunk_size,
+                    received_chunks: ChunkBitmap::default(),
+                    heap_chunks: vec![],
+                    hash_state: IncrementalSha256::default(),
+                    hashed_chunks: 0,
+                    content_key: Some(content.content_key.clone()),
+                    promoted_size: data.file_size,
+                    chunk_hashes: vec![],
+                    content_type: data.content_type,
+                    http_headers,
+                    encoding: data.encoding,
+                    reused_chunks: ChunkBitmap::default(),
+                    reuse_cursor: 0,
+                    state: UploadState::Finalized,
+                    init_timestamp: ic_cdk::api::time(),
+                    finalized_timestamp: Some(ic_cdk::api::time()),
+                    user_metadata,
+                    version: 1,
+                    expires_at: data.expires_at,
+                    owner: Some(caller),
+                    visibility: data.visibility.unwrap_or_default(),
+                };
+                self.storage_raw_internal_metadata.insert(path, metadata);
+
+                return Ok(init_upload::InitUploadResp {
+                    deduplicated: Some(true),
+                });
+            }
+        }
+
+        // Standard storage check only necessary for clean uploads,
+        // since a re-upload keeps the file size identical and won't consume new permanent space.
+        if self.get_free_storage_size_bytes() < (data.file_size as u128) {
+            return Err(init_upload::InitUploadError::NotEnoughStorage);
+        }
+
+        let metadata = InternalRawStorageMetadata {
+            file_path: path.clone(),
+            file_hash: data.file_hash,
+            file_size: data.file_size,
+            received_size: 0,
+            chunks_size: chunk_size,
+            received_chunks: ChunkBitmap::new(num_chunks),
+            heap_chunks: vec![],
+            hash_state: IncrementalSha256::default(),
+            hashed_chunks: 0,
+            content_key: None,
+            promoted_size: 0,
+            chunk_hashes,
+            content_type: data.content_type,
+            http_headers,
+            encoding: data.encoding,
+            reused_chunks: ChunkBitmap::default(),
+            reuse_cursor: 0,
+            state: UploadState::Init,
+            init_timestamp: ic_cdk::api::time(),
+            finalized_timestamp: None,
+            user_metadata,
+            version: 1,
+            expires_at: data.expires_at,
+            owner: Some(caller),
+            visibility: data.visibility.unwrap_or_default(),
+        };
+
+        self.storage_raw_internal_metadata.insert(path, metadata);
+
+        Ok(init_upload::InitUploadResp {
+            deduplicated: Some(false),
+        })
+    }
+
+    pub fn init_reupload(
+        &mut self,
+        data: init_reupload::Args,
+        caller: Principal,
+    ) -> Result<init_reupload::InitReuploadResp, init_reupload::InitReuploadError> {
+        trace(&format!("init_reupload - file_path: {:?}", data.file_path));
+
+        validate_file_path(&data.file_path)
+            .map_err(|_| init_reupload::InitReuploadError::InvalidFilePath)?;
+
+        let path = data.file_path.trim_start_matches('/').to_string();
+
+        let existing_metadata = self
+            .storage_raw_internal_metadata
+            .get(&path)
+            .ok_or(init_reupload::InitReuploadError::FileNotFound)?;
+
+        if existing_metadata.state != UploadState::Finalized {
+            return Err(init_reupload::InitReuploadError::FileNotFound);
+        }
+
+        if !data
+            .content_type
+            .as_deref()
+            .is_none_or(is_valid_header_value)
+        {
+            return Err(init_reupload::InitReuploadError::InvalidContentType);
+        }
+
+        let http_headers = data
+            .headers
+            .map(normalize_file_headers)
+            .map(|headers| headers.ok_or(init_reupload::InitReuploadError::InvalidHeaders))
+            .transpose()?;
+
+        let current_size = existing_metadata.file_size;
+        if data.changed_chunks.is_none() && current_size != data.file_size {
+            return Err(init_reupload::InitReuploadError::FileSizeMismatch);
+        }
+
+        // The caller is charged for the new bytes instead of the old ones if
+        // those were its own.
+        let freed_bytes = if existing_metadata.owner == Some(caller) {
+            current_size
+        } else {
+            0
+        };
+        if !self.fits_quota(caller, freed_bytes, data.file_size, 0)
+            || !self.fits_bucket(&path, current_size, data.file_size)
+        {
+            return Err(init_reupload::InitReuploadError::QuotaExceeded);
+        }
+
+        // Only growth needs new room; the old bytes are freed once the swap happens.
+        if self.get_free_storage_size_bytes() < data.file_size.saturating_sub(current_size) as u128
+        {
+            return Err(init_reupload::InitReuploadError::NotEnoughStorage);
+        }
+
+        let chunk_size = data.chunk_size.unwrap_or(DEFAULT_CHUNK_SIZE);
+
+        if chunk_size == 0 || chunk_size > DEFAULT_CHUNK_SIZE {
+            return Err(init_reupload::InitReuploadError::InvalidChunkSize);
+        }
+
+        let num_chunks = if data.file_size == 0 {
+            0
+        } else {
+            (data.file_size + chunk_size - 1) / chunk_size
+        };
+
+        if num_chunks > MAX_CHUNKS_PER_FILE {
+            return Err(init_reupload::InitReuploadError::TooManyChunks);
+        }
+
+        // Every chunk that isn't re-sent must lie entirely within the current bytes.
+        let mut received_chunks = ChunkBitmap::new(num_chunks);
+        let mut reused_chunks = ChunkBitmap::default();
+        let mut received_size = 0;
+        if let Some(changed_chunks) = &data.changed_chunks {
+            reused_chunks = ChunkBitmap::new(num_chunks);
+            let mut changed = ChunkBitmap::new(num_chunks);
+            for &index in changed_chunks {
+                if index >= num_chunks {
+                    return Err(init_reupload::InitReuploadError::InvalidChangedChunks);
+                }
+                changed.set(index);
+            }
+            for index in changed.missing() {
+                let end = ((index + 1) * chunk_size).min(data.file_size);
+                if end > current_size {
+                    return Err(init_reupload::InitReuploadError::InvalidChangedChunks);
+                }
+                received_chunks.set(index);
+                reused_chunks.set(index);
+                received_size += end - index * chunk_size;
+            }
+        }
+
+        let reupload_key = format!("?reupload:{}", path);
+
+        // Starting over: drop whatever a previous reupload of this path staged.
+        self.discard_upload(&reupload_key);
+
+        self.storage_raw_internal_metadata.insert(
+            reupload_key,
+            InternalRawStorageMetadata {
+                file_path: path,
+                file_hash: data.file_hash,
+                file_size: data.file_size,
+                received_size,
+                chunks_size: chunk_size,
+                received_chunks,
+                heap_chunks: vec![],
+                hash_state: IncrementalSha256::default(),
+                hashed_chunks: 0,
+                content_key: None,
+                promoted_size: 0,
+                chunk_hashes: vec![],
+                content_type: data.content_type,
+                http_headers,
+                encoding: None,
+                reused_chunks,
+                reuse_cursor: 0,
+                state: UploadState::InitReupload,
+                init_timestamp: ic_cdk::api::time(),
+                finalized_timestamp: None,
+                user_metadata: BTreeMap::new(),
+                version: 1,
+                expires_at: None,
+                owner: Some(caller),
+                visibility: Visibility::Public,
+            },
+        );
+
+        Ok(init_reupload::InitReuploadResp {})
+    }
+
+    pub fn store_chunk(
+        &mut self,
+        data: store_chunk::Args,
+    ) -> Result<store_chunk::StoreChunkResp, store_chunk::StoreChunkError> {
+        trace(&format!("store_chunk - hash_id: {:?}", data.file_path));
+
+        validate_file_path(&data.file_path)
+            .map_err(|_| store_chunk::StoreChunkError::InvalidFilePath)?;
+
+        let path = data.file_path.trim_start_matches('/').to_string();
+        let upload_key = self.upload_key(&path);
+
+        let metadata = self
+            .storage_raw_internal_metadata
+            .get_mut(&upload_key)
+            .ok_or(store_chunk::StoreChunkError::UploadNotInitialized)?;
+
+        match metadata.state {
+            UploadState::Init => {
+                metadata.state = UploadState::InProgress;
+            }
+            UploadState::ReuploadInit => {
+                metadata.state = UploadState::InProgress;
+            }
+            UploadState::InitReupload => {
+                metadata.state = UploadState::ChunkReupload;
+            }
+            UploadState::ChunkReupload => (),
+            UploadState::InProgress => (),
+            UploadState::Finalized => {
+                return Err(store_chunk::StoreChunkError::UploadAlreadyFinalized);
+            }
+            UploadState::FinalizeReupload => {
+                return Err(store_chunk::StoreChunkError::UploadAlreadyFinalized);
+            }
+            UploadState::Finalizing => {
+                return Err(store_chunk::StoreChunkError::UploadAlreadyFinalized);
+            }
+            UploadState::Failed(_) => {
+                return Err(store_chunk::StoreChunkError::UploadNotInitialized);
+            }
+        }
+
+        let file_size = metadata.file_size;
+        let received_size = metadata.received_size;
+        let chunk_index = u64::try_from(data.chunk_id.0)
+            .map_err(|_| store_chunk::StoreChunkError::InvalidChunkId)?;
+        if chunk_index >= metadata.received_chunks.num_chunks() {
+            return Err(store_chunk::StoreChunkError::InvalidChunkId);
+        }
+
+        if received_size + (data.chunk_data.len() as u64) > file_size {
+            return Err(store_chunk::StoreChunkError::InvalidChunkData);
+        }
+
+        // A partial reupload fills the other chunks from `index * chunks_size` of
+        // the stored bytes, so the chunks it sends must line up with them.
+        if metadata.reused_chunks.num_chunks() > 0 {
+            let chunk_start = chunk_index * metadata.chunks_size;
+            let expected_len = (chunk_start + metadata.chunks_size).min(file_size) - chunk_start;
+            if data.chunk_data.len() as u64 != expected_len {
+                return Err(store_chunk::StoreChunkError::InvalidChunkSize);
+            }
+        }
+
+        // Check if the chunk has already been stored
+        if metadata.received_chunks.get(chunk_index) {
+            return Err(store_chunk::StoreChunkError::InvalidChunkData);
+        }
+
+        // Reject a corrupted chunk now rather than failing the whole file at finalize.
+        let expected_hashes: Vec<&String> = data
+            .chunk_hash
+            .iter()
+            .chain(metadata.chunk_hashes.get(chunk_index as usize))
+            .collect();
+        if !expected_hashes.is_empty() {
+            let chunk_hash = hex::encode(Sha256::digest(&data.chunk_data));
+            if expected_hashes
+                .iter()
+                .any(|expected| !expected.eq_ignore_ascii_case(&chunk_hash))
+            {
+                return Err(store_chunk::StoreChunkError::ChunkHashMismatch);
+            }
+        }
+
+        metadata.received_chunks.set(chunk_index);
+        metadata.received_size = received_size + (data.chunk_data.len() as u64);
+        self.upload_staging
+            .insert(ChunkKey::new(&upload_key, chunk_index), data.chunk_data);
+
+        // In-order uploads hash exactly the chunk just stored; an out-of-order one
+        // catches up on the staged chunks behind it, bounded per call.
+        metadata.advance_hash(
+            &upload_key,
+            &self.upload_staging,
+            HASH_CATCH_UP_BYTES_PER_CALL,
+        );
+
+        Ok(store_chunk::StoreChunkResp {})
+    }
+
+    pub fn finalize_upload(
+        &mut self,
+        data: finalize_upload::Args,
+        step_bytes: u64,
+    ) -> Result<finalize_upload::FinalizeUploadResp, finalize_upload::FinalizeUploadError> {
+        trace(&format!("finalize_upload - hash_id: {:?}", data.file_path));
+
+        validate_file_path(&data.file_path)
+            .map_err(|_| finalize_upload::FinalizeUploadError::InvalidFilePath)?;
+
+        let path = data.file_path.trim_start_matches('/').to_string();
+        let upload_key = self.upload_key(&path);
+
+        let metadata = self
+            .storage_raw_internal_metadata
+            .get_mut(&upload_key)
+            .ok_or(finalize_upload::FinalizeUploadError::UploadNotStarted)?;
+
+        match metadata.state {
+            UploadState::Init => {
+                return Err(finalize_upload::FinalizeUploadError::UploadNotStarted);
+            }
+            UploadState::ReuploadInit => {
+                return Err(finalize_upload::FinalizeUploadError::UploadNotStarted);
+            }
+            UploadState::InitReupload => {
+                return Err(finalize_upload::FinalizeUploadError::UploadNotStarted);
+            }
+            UploadState::InProgress => {}
+            UploadState::ChunkReupload => {}
+            UploadState::FinalizeReupload => {}
+            UploadState::Finalizing => {
+                return Ok(finalize_upload_resp(&path, UploadState::Finalizing));
+            }
+            UploadState::Finalized => {
+                return Err(finalize_upload::FinalizeUploadError::UploadAlreadyFinalized);
+            }
+            UploadState::Failed(_) => {
+                return Err(finalize_upload::FinalizeUploadError::UploadNotStarted);
+            }
+        }
+
+        if metadata.received_size != metadata.file_size || !metadata.received_chunks.is_complete() {
+            self.discard_upload(&upload_key);
+            return Err(finalize_upload::FinalizeUploadError::IncompleteUpload);
+        }
+
+        metadata.state = UploadState::Finalizing;
+
+        let content_key = self.allocate_content_key();
+        if let Some(metadata) = self.storage_raw_internal_metadata.get_mut(&upload_key) {
+            metadata.content_key = Some(content_key);
+        }
+
+        self.continue_finalization(&path, step_bytes)
+    }
+
+    /// Run one bounded step of an upload in `Finalizing`: stage the chunks a
+    /// partial reupload reuses, hash whatever an out-of-order upload left
+    /// unhashed, verify the digest, then move staged
+    /// chunks into the chunk store. At most `step_bytes` are hashed or moved per
+    /// call, so the work of a large file spreads over several messages.
+    ///
+    /// Returns `Finalizing` while work remains and `Finalized` once the new
+    /// bytes are live. The previous bytes of a reupload keep being served
+    /// until that last step.
+    pub fn continue_finalization(
+        &mut self,
+        file_path: &str,
+        step_bytes: u64,
+    ) -> Result<finalize_upload::FinalizeUploadResp, finalize_upload::FinalizeUploadError> {
+        let path = file_path.trim_start_matches('/').to_string();
+        let upload_key = self.upload_key(&path);
+
+        let state = self
+            .storage_raw_internal_metadata
+            .get(&upload_key)
+            .map(|metadata| metadata.state.clone())
+            .ok_or(finalize_upload::FinalizeUploadError::UploadNotStarted)?;
+
+        if state != UploadState::Finalizing {
+            return Err(finalize_upload::FinalizeUploadError::InvalidStateTransition);
+        }
+
+        let Some(copied) = self.stage_reused_chunks(&path, &upload_key, step_bytes) else {
+            let error = finalize_upload::FinalizeUploadError::IncompleteUpload;
+            self.fail_upload(&upload_key, &error);
+            return Err(error);
+        };
+
+        let metadata = self
+            .storage_raw_internal_metadata
+            .get_mut(&upload_key)
+            .ok_or(finalize_upload::FinalizeUploadError::UploadNotStarted)?;
+
+        if metadata.reuse_cursor < metadata.reused_chunks.num_chunks() {
+            return Ok(finalize_upload_resp(&path, UploadState::Finalizing));
+        }
+
+        // Chunks were hashed as they arrived or by the catch-up job; only reused
+        // chunks, or a tail the job hasn't reached yet, may be left.
+        let step_bytes = step_bytes.saturating_sub(copied);
+        let hashed_before = metadata.hash_state.bytes_hashed();
+        metadata.advance_hash(&upload_key, &self.upload_staging, step_bytes);
+        let mut budget =
+            step_bytes.saturating_sub(metadata.hash_state.bytes_hashed() - hashed_before);
+
+        if metadata.hashed_chunks < metadata.received_chunks.num_chunks() {
+            return Ok(finalize_upload_resp(&path, UploadState::Finalizing));
+        }
+
+        let calculated_hash = hex::encode(metadata.hash_state.clone().finalize());
+
+        if calculated_hash != metadata.file_hash {
+            let error = finalize_upload::FinalizeUploadError::FileHashMismatch;
+            self.fail_upload(&upload_key, &error);
+            return Err(error);
+        }
+
+        // Identical content is already stored (possibly finalized by a duplicate
+        // upload while this one was promoting): share it and drop our copy.
+        if let Some(content) = self.contents.get_mut(&calculated_hash) {
+            content.refs += 1;
+            let shared_key = content.content_key.clone();
+            if let Some(own_key) = metadata.content_key.replace(shared_key) {
+                remove_chunks(&mut self.storage_chunks, &own_key);
+            }
+            metadata.promoted_size = metadata.file_size;
+            remove_chunks(&mut self.upload_staging, &upload_key);
+            return Ok(self.complete_finalization(&path, &upload_key));
+        }
+
+        // Promote the staged chunks one by one, never holding more than one on the heap.
+        let content_key = metadata.content_key.clone().unwrap_or(path.clone());
+        let staged: Vec<ChunkKey> = self
+            .upload_staging
+            .keys_range(ChunkKey::range(&upload_key))
+            .collect();
+        for key in staged {
+            if budget == 0 {
+                return Ok(finalize_upload_resp(&path, UploadState::Finalizing));
+            }
+            if let Some(chunk) = self.upload_staging.remove(&key) {
+                budget = budget.saturating_sub(chunk.len() as u64);
+                metadata.promoted_size += chunk.len() as u64;
+                self.storage_chunks
+                    .insert(ChunkKey::new(&content_key, key.index), chunk);
+            }
+        }
+
+        self.contents.insert(
+            calculated_hash,
+            StoredContent {
+                content_key,
+                size: metadata.file_size,
+                refs: 1,
+            },
+        );
+
+        Ok(self.complete_finalization(&path, &upload_key))
+    }
+
+    /// Last finalization step, once the upload's content is in the chunk store:
+    /// swap it in at `path` and release whatever the path held before.
+    fn complete_finalization(
+        &mut self,
+        path: &str,
+        upload_key: &str,
+    ) -> finalize_upload::FinalizeUploadResp {
+        let Some(mut metadata) = self.storage_raw_internal_metadata.remove(upload_key) else {
+            return finalize_upload_resp(path, UploadState::Finalized);
+        };
+
+        // CRITICAL CACHE CLEANUP: If this file was previously certified and cached,
+        // we must clear the old asset out of the certification tree since the bytes changed.
+        self.uncertify_path(path);
+
+        // A new variant changes what its file is certified with.
+        if let Some(encoding) = metadata.encoding {
+            if let Some(base_path) = path.strip_suffix(encoding_suffix(encoding)) {
+                self.uncertify_path(base_path);
+            }
+        }
+
+        // Keep the previous bytes of a reupload as a version now that the new
+        // ones are in place.
+        if let Some(previous) = self.storage_raw_internal_metadata.remove(path) {
+            // Variants encode the previous bytes; a reuploaded variant stays one.
+            match previous.encoding {
+                Some(encoding) => metadata.encoding = Some(encoding),
+                None => self.remove_variants(path),
+            }
+            metadata.user_metadata = previous.user_metadata.clone();
+            if metadata.content_type.is_none() {
+                metadata.content_type = previous.content_type.clone();
+            }
+            if metadata.http_headers.is_none() {
+                metadata.http_headers = previous.http_headers.clone();
+            }
+            metadata.version = previous.version + 1;
+            metadata.expires_at = previous.expires_at;
+            metadata.visibility = previous.visibility;
+            self.archive_version(path, previous);
+        }
+
+        metadata.state = UploadState::Finalized;
+        metadata.finalized_timestamp = Some(ic_cdk::api::time());
+
+        self.storage_raw_internal_metadata
+            .insert(path.to_string(), metadata);
+
+        trace(&format!("finalize_upload - file_path: {:?}", path));
+
+        finalize_upload_resp(path, UploadState::Finalized)
+    }
+
+    /// File paths of every upload currently in `Finalizing`.
+    /// Claim the hash catch-up of the upload at `file_path` if it has staged
+    /// chunks its running hash hasn't reached yet. Returns whether the caller
+    /// should schedule the job; `false` while one is already scheduled.
+    pub fn start_hash_catch_up(&mut self, file_path: &str) -> bool {
+        let path = file_path.trim_start_matches('/');
+        let has_backlog = self
+            .storage_raw_internal_metadata
+            .get(&self.upload_key(path))
+            .is_some_and(|metadata| metadata.has_unhashed_chunks());
+        has_backlog && self.hash_catch_ups.insert(path.to_string())
+    }
+
+    /// Feed up to `HASH_CATCH_UP_BYTES_PER_CALL` more staged bytes into the
+    /// running hash of the upload at `file_path`. Returns whether more are
+    /// waiting. Once an upload is finalizing, its steps take over.
+    pub fn catch_up_hash(&mut self, file_path: &str) -> bool {
+        let path = file_path.trim_start_matches('/');
+        let upload_key = self.upload_key(path);
+        let has_backlog = match self.storage_raw_internal_metadata.get_mut(&upload_key) {
+            Some(metadata)
+                if matches!(
+                    metadata.state,
+                    UploadState::InProgress | UploadState::ChunkReupload
+                ) =>
+            {
+                metadata.advance_hash(
+                    &upload_key,
+                    &self.upload_staging,
+                    HASH_CATCH_UP_BYTES_PER_CALL,
+                );
+                metadata.has_unhashed_chunks()
+            }
+            _ => false,
+        };
+        if !has_backlog {
+            self.hash_catch_ups.remove(path);
+        }
+        has_backlog
+    }
+
+    /// Uploads still receiving chunks whose running hash is behind.
+    pub fn uploads_behind_on_hashing(&self) -> Vec<String> {
+        self.storage_raw_internal_metadata
+            .values()
+            .filter(|metadata| {
+                matches!(
+                    metadata.state,
+                    UploadState::InProgress | UploadState::ChunkReupload
+                ) && metadata.has_unhashed_chunks()
+            })
+            .map(|metadata| metadata.file_path.clone())
+            .collect()
+    }
+
+    pub fn finalizing_uploads(&self) -> Vec<String> {
+        self.storage_raw_internal_metadata
+            .values()
+            .filter(|metadata| metadata.state == UploadState::Finalizing)
+            .map(|metadata| metadata.file_path.clone())
+            .collect()
+    }
+
+    pub fn get_finalization_progress(
+        &self,
+        file_path: &str,
+        can_read_private: impl Fn(&str) -> bool,
+    ) -> Result<
+        get_finalization_progress::FinalizationProgress,
+        get_finalization_progress::GetFinalizationProgressError,
+    > {
+        validate_file_path(file_path).map_err(|_| {
+            get_finalization_progress::GetFinalizationProgressError::InvalidFilePath
+        })?;
+
+        let path = file_path.trim_start_matches('/');
+        let metadata = self
+            .storage_raw_internal_metadata
+            .get(&self.upload_key(path))
+            .filter(|_| self.is_visible(path, &can_read_private))
+            .ok_or(get_finalization_progress::GetFinalizationProgressError::FileNotFound)?;
+
+        let (hashed_size, stored_size) = match metadata.state {
+            UploadState::Finalized => (metadata.file_size, metadata.file_size),
+            _ => (metadata.hash_state.bytes_hashed(), metadata.promoted_size),
+        };
+
+        Ok(get_finalization_progress::FinalizationProgress {
+            state: metadata.state.clone(),
+            file_size: metadata.file_size,
+            hashed_size,
+            stored_size,
+        })
+    }
+
+    /// Where the upload in flight for `file_path` stands, so an interrupted
+    /// uploader can send just the missing chunks. `gc_ttl_nanos` is the TTL the
+    /// abandoned-upload GC applies.
+    pub fn get_upload_status(
+        &self,
+        file_path: &str,
+        gc_ttl_nanos: u64,
+        can_read_private: impl Fn(&str) -> bool,
+    ) -> Result<get_upload_status::UploadStatus, get_upload_status::GetUploadStatusError> {
+        validate_file_path(file_path)
+            .map_err(|_| get_upload_status::GetUploadStatusError::InvalidFilePath)?;
+
+        let path = file_path.trim_start_matches('/');
+        let metadata = self
+            .storage_raw_internal_metadata
+            .get(&self.upload_key(path))
+            .filter(|_| self.is_visible(path, &can_read_private))
+            .ok_or(get_upload_status::GetUploadStatusError::UploadNotFound)?;
+
+        let gc_deadline = match metadata.state {
+            UploadState::Finalized | UploadState::Finalizing => None,
+            _ => Some(metadata.init_timestamp.saturating_add(gc_ttl_nanos)),
+        };
+
+        Ok(get_upload_status::UploadStatus {
+            state: metadata.state.clone(),
+            file_size: metadata.file_size,
+            received_size: metadata.received_size,
+            chunk_size: metadata.chunks_size,
+            num_chunks: metadata.received_chunks.num_chunks(),
+            missing_chunks: metadata.received_chunks.missing().collect(),
+            init_timestamp: metadata.init_timestamp,
+            gc_deadline,
+        })
+    }
+
+    /// Sweep abandoned uploads: any entry still in `Init`, `InProgress` or
+    /// `Failed` whose `init_timestamp` is older than `ttl_nanos` is removed. Uploads that are
+    /// `Finalizing` are left alone: their timer steps are still running. Returns
+    /// the number of entries removed. Cheap to run (single iteration); intended
+    /// for an hourly heartbeat.
+    pub fn gc_abandoned_uploads(&mut self, now: u64, ttl_nanos: u64) -> usize {
+        let cutoff = now.saturating_sub(ttl_nanos);
+        let stale: Vec<String> = self
+            .storage_raw_internal_metadata
+            .iter()
+            .filter_map(|(path, m)| match m.state {
+                UploadState::Finalized | UploadState::Finalizing => None,
+                _ if m.init_timestamp <= cutoff => Some(path.clone()),
+                _ => None,
+            })
+            .collect();
+        let n = stale.len();
+        for path in stale {
+            trace(&format!("gc_abandoned_uploads: removing {path}"));
+            self.discard_upload(&path);
+        }
+        n
+    }
+
+    /// Whether the file stored at `path` is past its expiry. A variant also
+    /// expires with the file it encodes.
+    fn is_expired_at(&self, path: &str, metadata: &InternalRawStorageMetadata, now: u64) -> bool {
+        metadata.is_expired(now)
+            || metadata.encoding.is_some_and(|encoding| {
+                path.strip_suffix(encoding_suffix(encoding))
+                    .and_then(|base_path| self.storage_raw_internal_metadata.get(base_path))
+                    .is_some_and(|base| base.is_expired(now))
+            })
+    }
+
+    /// The file at `path`, as its most preferred variant that `accept_encoding`
+    /// admits, or as stored when there is none.
+    pub fn get_file_data(&self, path: &str, accept_encoding: Option<&str>) -> Option<FileData> {
+        let key = path.trim_start_matches('/');
+        let now = ic_cdk::api::time();
+        let metadata = self.storage_raw_internal_metadata.get(key)?;
+        if metadata.state != UploadState::Finalized || self.is_expired_at(key, metadata, now) {
+            return None;
+        }
+
+        let variants = self.variant_paths(key);
+        let variant = accept_encoding.and_then(|accept_encoding| {
+            variants.iter().find_map(|(encoding, variant_path)| {
+                if !accepts_encoding(accept_encoding, encoding_token(*encoding)) {
+                    return None;
+                }
+                let variant = self.storage_raw_internal_metadata.get(variant_path)?;
+                if variant.is_expired(now) {
+                    return None;
+                }
+                let data = self.read_file_bytes(variant_path, variant)?;
+                Some((data, encoding_token(*encoding)))
+            })
+        });
+        let (data, content_encoding) = match variant {
+            Some((data, token)) => (data, Some(token)),
+            None => (self.read_file_bytes(key, metadata)?, None),
+        };
+
+        let content_type = metadata
+            .content_type
+            .clone()
+            .unwrap_or_else(|| get_content_type_for_path(key).to_string());
+        let headers = self.file_headers(key, metadata);
+        Some(FileData {
+            data,
+            content_type,
+            headers,
+            content_encoding,
+            has_variants: !variants.is_empty(),
+        })
+    }
+
+    /// Version `version` of the finalized file at `path`, as stored: either the
+    /// current one or one kept in its history.
+    pub fn get_file_version_data(&self, path: &str, version: u64) -> Option<FileData> {
+        let key = path.trim_start_matches('/');
+        let current = self.storage_raw_internal_metadata.get(key)?;
+        if current.state != UploadState::Finalized
+            || self.is_expired_at(key, current, ic_cdk::api::time())
+        {
+            return None;
+        }
+        let metadata = if current.version == version {
+            current
+        } else {
+            self.versions
+                .get(key)?
+                .iter()
+                .find(|metadata| metadata.version == version)?
+        };
+
+        let data = self.read_file_bytes(key, metadata)?;
+        let content_type = metadata
+            .content_type
+            .clone()
+            .unwrap_or_else(|| get_content_type_for_path(key).to_string());
+        let headers = self.file_headers(key, metadata);
+        Some(FileData {
+            data,
+            content_type,
+            headers,
+            content_encoding: None,
+            has_variants: false,
+        })
+    }
+
+    /// Current version of the file at `file_path` and its retained history,
+    /// newest first. `can_read_private` tells which private paths the caller
+    /// may see; others are reported missing.
+    pub fn list_file_versions(
+        &self,
+        file_path: &str,
+        can_read_private: impl Fn(&str) -> bool,
+    ) -> Result<list_file_versions::ListFileVersionsResp, list_file_versions::ListFileVersionsError>
+    {
+        validate_file_path(file_path)
+            .map_err(|_| list_file_versions::ListFileVersionsError::InvalidFilePath)?;
+
+        let path = file_path.trim_start_matches('/');
+        let current = self
+            .storage_raw_internal_metadata
+            .get(path)
+            .filter(|metadata| metadata.state == UploadState::Finalized)
+            .filter(|_| self.is_visible(path, &can_read_private))
+            .ok_or(list_file_versions::ListFileVersionsError::FileNotFound)?;
+
+        // A version that was private when replaced stays hidden.
+        let versions = self
+            .versions
+            .get(path)
+            .map(|history| {
+                history
+                    .iter()
+                    .rev()
+                    .filter(|metadata| {
+                        metadata.visibility == Visibility::Public || can_read_private(path)
+                    })
+                    .map(|metadata| list_file_versions::FileVersion {
+                        version: metadata.version,
+                        file_size: metadata.file_size,
+                        file_hash: metadata.file_hash.clone(),
+                        content_type: metadata
+                            .content_type
+                            .clone()
+                            .unwrap_or_else(|| get_content_type_for_path(path).to_string()),
+                        finalized_timestamp: metadata.finalized_timestamp,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(list_file_versions::ListFileVersionsResp {
+            current_version: current.version,
+            versions,
+        })
+    }
+
+    /// Serve a retained version's bytes, content type and headers again, as a
+    /// new version. The replaced one joins the history like on a reupload, and
+    /// user metadata stays as it is.
+    pub fn restore_file_version(
+        &mut self,
+        data: restore_file_version::Args,
+        caller: Principal,
+    ) -> Result<
+        restore_file_version::RestoreFileVersionResp,
+        restore_file_version::RestoreFileVersionError,
+    > {
+        trace(&format!(
+            "restore_file_version - file_path: {:?} version: {}",
+            data.file_path, data.version
+        ));
+
+        validate_file_path(&data.file_path)
+            .map_err(|_| restore_file_version::RestoreFileVersionError::InvalidFilePath)?;
+
+        let path = data.file_path.trim_start_matches('/').to_string();
+
+        let current = self
+            .storage_raw_internal_metadata
+            .get(&path)
+            .ok_or(restore_file_version::RestoreFileVersionError::FileNotFound)?;
+        if current.state != UploadState::Finalized || self.upload_key(&path) != path {
+            return Err(restore_file_version::RestoreFileVersionError::UploadInProgress);
+        }
+
+        let mut restored = self
+            .versions
+            .get(&path)
+            .and_then(|history| {
+                history
+                    .iter()
+                    .find(|metadata| metadata.version == data.version)
+            })
+            .cloned()
+            .ok_or(restore_file_version::RestoreFileVersionError::VersionNotFound)?;
+
+        // The restored file is the caller's, like a reupload would be.
+        let (freed_bytes, added_files) = self.replaced_usage(&path, caller);
+        if !self.fits_quota(caller, freed_bytes, restored.file_size, added_files) {
+            return Err(restore_file_version::RestoreFileVersionError::QuotaExceeded);
+        }
+        restored.owner = Some(caller);
+
+        // The restored file takes a reference of its own, so pruning the
+        // version later leaves it intact.
+        match self.contents.get_mut(&restored.file_hash) {
+            Some(content) => {
+                content.refs += 1;
+                restored.content_key = Some(content.content_key.clone());
+            }
+            None => {
+                let bytes = self
+                    .read_file_bytes(&path, &restored)
+                    .ok_or(restore_file_version::RestoreFileVersionError::VersionNotFound)?;
+                restored.content_key = Some(self.store_content(&restored.file_hash, bytes));
+            }
+        }
+
+        self.uncertify_path(&path);
+        let Some(current) = self.storage_raw_internal_metadata.remove(&path) else {
+            return Err(restore_file_version::RestoreFileVersionError::FileNotFound);
+        };
+        match current.encoding {
+            Some(encoding) => {
+                restored.encoding = Some(encoding);
+                if let Some(base_path) = path.strip_suffix(encoding_suffix(encoding)) {
+                    self.uncertify_path(base_path);
+                }
+            }
+            None => self.remove_variants(&path),
+        }
+        restored.user_metadata = current.user_metadata.clone();
+        restored.expires_at = current.expires_at;
+        restored.visibility = current.visibility;
+        restored.version = current.version + 1;
+        restored.finalized_timestamp = Some(ic_cdk::api::time());
+        let version = restored.version;
+
+        self.archive_version(&path, current);
+        self.storage_raw_internal_metadata.insert(path, restored);
+
+        Ok(restore_file_version::RestoreFileVersionResp { version })
+    }
+
+    /// Public metadata of the file tracked at `file_path`, whatever its state.
+    /// A private file is reported missing unless `can_read_private` allows it.
+    pub fn get_file_metadata(
+        &self,
+        file_path: &str,
+        can_read_private: impl Fn(&str) -> bool,
+    ) -> Result<get_file_metadata::FileMetadata, get_file_metadata::GetFileMetadataError> {
+        validate_file_path(file_path)
+            .map_err(|_| get_file_metadata::GetFileMetadataError::InvalidFilePath)?;
+
+        let path = file_path.trim_start_matches('/');
+        let metadata = self
+            .storage_raw_internal_metadata
+            .get(path)
+            .filter(|_| self.is_visible(path, &can_read_private))
+            .ok_or(get_file_metadata::GetFileMetadataError::FileNotFound)?;
+
+        Ok(get_file_metadata::FileMetadata {
+            file_path: format!("/{}", path),
+            file_size: metadata.file_size,
+            file_hash: metadata.file_hash.clone(),
+            chunk_size: metadata.chunks_size,
+            content_type: metadata
+                .content_type
+                .clone()
+                .unwrap_or_else(|| get_content_type_for_path(path).to_string()),
+            state: metadata.state.clone(),
+            init_timestamp: metadata.init_timestamp,
+            finalized_timestamp: metadata.finalized_timestamp,
+            metadata: metadata.user_metadata.clone(),
+            expires_at: metadata.expires_at,
+            visibility: self.file_visibility(path),
+        })
+    }
+
+    /// Replace the response headers of the file at `path`. A certified copy
+    /// carries the old headers, so it is dropped from the cache.
+    pub fn set_file_headers(
+        &mut self,
+        data: set_file_headers::Args,
+    ) -> Result<set_file_headers::SetFileHeadersResp, set_file_headers::SetFileHeadersError> {
+        validate_file_path(&data.file_path)
+            .map_err(|_| set_file_headers::SetFileHeadersError::InvalidFilePath)?;
+
+        let headers = normalize_file_headers(data.headers)
+            .ok_or(set_file_headers::SetFileHeadersError::InvalidHeaders)?;
+
+        let path = data.file_path.trim_start_matches('/');
+        if !self.storage_raw_internal_metadata.contains_key(path) {
+            return Err(set_file_headers::SetFileHeadersError::FileNotFound);
+        }
+
+        self.uncertify_path(path);
+        if let Some(metadata) = self.storage_raw_internal_metadata.get_mut(path) {
+            metadata.http_headers = Some(headers);
+        }
+
+        Ok(set_file_headers::SetFileHeadersResp {})
+    }
+
+    /// Replace the application metadata of the file tracked at `path`.
+    pub fn set_file_metadata(
+        &mut self,
+        data: set_file_metadata::Args,
+    ) -> Result<set_file_metadata::SetFileMetadataResp, set_file_metadata::SetFileMetadataError>
+    {
+        validate_file_path(&data.file_path)
+            .map_err(|_| set_file_metadata::SetFileMetadataError::InvalidFilePath)?;
+
+        if !is_valid_user_metadata(&data.metadata) {
+            return Err(set_file_metadata::SetFileMetadataError::InvalidMetadata);
+        }
+
+        let path = data.file_path.trim_start_matches('/');
+        let metadata = self
+            .storage_raw_internal_metadata
+            .get_mut(path)
+            .ok_or(set_file_metadata::SetFileMetadataError::FileNotFound)?;
+        metadata.user_metadata = data.metadata;
+
+        Ok(set_file_metadata::SetFileMetadataResp {})
+    }
+
+    pub fn set_file_expiry(
+        &mut self,
+        data: set_file_expiry::Args,
+    ) -> Result<set_file_expiry::SetFileExpiryResp, set_file_expiry::SetFileExpiryError> {
+        validate_file_path(&data.file_path)
+            .map_err(|_| set_file_expiry::SetFileExpiryError::InvalidFilePath)?;
+
+        if data
+            .expires_at
+            .is_some_and(|expires_at| expires_at <= ic_cdk::api::time())
+        {
+            return Err(set_file_expiry::SetFileExpiryError::InvalidExpiresAt);
+        }
+
+        let path = data.file_path.trim_start_matches('/');
+        let metadata = self
+            .storage_raw_internal_metadata
+            .get_mut(path)
+            .ok_or(set_file_expiry::SetFileExpiryError::FileNotFound)?;
+        metadata.expires_at = data.expires_at;
+
+        Ok(set_file_expiry::SetFileExpiryResp {})
+    }
+
+    /// Visibility of the file at `key`, or of the file it encodes for a variant.
+    fn file_visibility(&self, key: &str) -> Visibility {
+        let Some(metadata) = self.storage_raw_internal_metadata.get(key) else {
+            return Visibility::Public;
+        };
+        match metadata.encoding {
+            Some(encoding) => key
+                .strip_suffix(encoding_suffix(encoding))
+                .map(|base| self.file_visibility(base))
+                .unwrap_or(metadata.visibility),
+            None => metadata.visibility,
+        }
+    }
+
+    /// Visibility of version `version` of the file at `key`. Each kept version
+    /// has its own; the current one and a variant's follow `file_visibility`.
+    fn version_visibility(&self, key: &str, version: u64) -> Visibility {
+        let archived = self
+            .versions
+            .get(key)
+            .and_then(|versions| versions.iter().find(|metadata| metadata.version == version));
+        match archived {
+            Some(metadata) if metadata.encoding.is_none() => metadata.visibility,
+            _ => self.file_visibility(key),
+        }
+    }
+
+    pub fn is_file_private(&self, path: &str) -> bool {
+        self.file_visibility(path.trim_start_matches('/')) == Visibility::Private
+    }
+
+    /// Whether the entry at `key` may be listed to a caller for whom
+    /// `can_read_private` tells which private files they may see.
+    fn is_visible(&self, key: &str, can_read_private: impl Fn(&str) -> bool) -> bool {
+        self.file_visibility(key) == Visibility::Public || can_read_private(key)
+    }
+
+    /// Whether a private file, one of its versions, or a private file in the
+    /// trash holds the content hashed `file_hash`.
+    fn is_content_private(&self, file_hash: &str) -> bool {
+        let live = self
+            .storage_raw_internal_metadata
+            .iter()
+            .any(|(key, metadata)| {
+                metadata.file_hash == file_hash
+                    && metadata.state == UploadState::Finalized
+                    && self.file_visibility(key) == Visibility::Private
+            });
+        let versioned = self.versions.iter().any(|(path, history)| {
+            history.iter().any(|version| {
+                version.file_hash == file_hash
+                    && (version.visibility == Visibility::Private
+                        || self.file_visibility(path) == Visibility::Private)
+            })
+        });
+        let trashed = self.trash.values().any(|trashed| {
+            trashed.metadata.visibility == Visibility::Private
+                && std::iter::once(&trashed.metadata)
+                    .chain(&trashed.variants)
+                    .chain(&trashed.versions)
+                    .any(|metadata| metadata.file_hash == file_hash)
+        });
+        live || versioned || trashed
+    }
+
+    /// Make the file at `path` public or private. A private file's certified
+    /// copy is dropped, so it is no longer served from the cache.
+    pub fn set_file_visibility(
+        &mut self,
+        data: set_file_visibility::Args,
+    ) -> Result<
+        set_file_visibility::SetFileVisibilityResp,
+        set_file_visibility::SetFileVisibilityError,
+    > {
+        validate_file_path(&data.file_path)
+            .map_err(|_| set_file_visibility::SetFileVisibilityError::InvalidFilePath)?;
+
+        let path = data.file_path.trim_start_matches('/');
+        let metadata = self
+            .storage_raw_internal_metadata
+            .get(path)
+            .ok_or(set_file_visibility::SetFileVisibilityError::FileNotFound)?;
+        if metadata.encoding.is_some() {
+            return Err(set_file_visibility::SetFileVisibilityError::EncodedVariant);
+        }
+
+        if data.visibility == Visibility::Private {
+            self.uncertify_path(path);
+        }
+        if let Some(metadata) = self.storage_raw_internal_metadata.get_mut(path) {
+            metadata.visibility = data.visibility;
+        }
+
+        Ok(set_file_visibility::SetFileVisibilityResp {})
+    }
+
+    pub fn has_download_token_key(&self) -> bool {
+        !self.download_token_key.is_empty()
+    }
+
+    /// Set the secret signing download tokens, unless there already is one.
+    pub fn init_download_token_key(&mut self, key: Vec<u8>) {
+        if self.download_token_key.is_empty() {
+            self.download_token_key = key;
+        }
+    }
 
-        Ok(init_reupload::InitReuploadResp {})
+    fn download_token_mac(&self, key: &str, expires_at: u64) -> Hmac<Sha256> {
+        let message = format!("{}\n{}", key, expires_at);
+        hmac_sha256(&self.download_token_key, message.as_bytes())
     }
 
-    pub fn store_chunk(
-        &mut self,
-        data: store_chunk::Args,
-    ) -> Result<store_chunk::StoreChunkResp, store_chunk::StoreChunkError> {
-        trace(&format!("store_chunk - hash_id: {:?}", data.file_path));
+    fn download_token(&self, key: &str, expires_at: u64) -> String {
+        hex::encode(
+            self.download_token_mac(key, expires_at)
+                .finalize()
+                .into_bytes(),
+        )
+    }
 
+    /// Sign a token granting access to the file at `file_path` until
+    /// `expires_at`. Requires the signing key to be set.
+    pub fn create_download_token(
+        &self,
+        data: create_download_token::Args,
+        now: u64,
+    ) -> create_download_token::Response {
         validate_file_path(&data.file_path)
-            .map_err(|_| store_chunk::StoreChunkError::InvalidFilePath)?;
-
-        let path = data.file_path.trim_start_matches('/').to_string();
-        let reupload_key = format!("?reupload:{}", path);
-
-        let metadata = if self
-            .storage_raw_internal_metadata
-            .contains_key(&reupload_key)
-        {
-            self.storage_raw_internal_metadata
-                .get_mut(&reupload_key)
-                .unwrap()
-        } else {
-            self.storage_raw_internal_metadata
-                .get_mut(&path)
-                .ok_or(store_chunk::StoreChunkError::UploadNotInitialized)?
-        };
+            .map_err(|_| create_download_token::CreateDownloadTokenError::InvalidFilePath)?;
 
-        match metadata.state {
-            UploadState::Init => {
-                metadata.state = UploadState::InProgress;
-            }
-            UploadState::ReuploadInit => {
-                metadata.state = UploadState::InProgress;
-            }
-            UploadState::InitReupload => {
-                metadata.state = UploadState::ChunkReupload;
-            }
-            UploadState::ChunkReupload => (),
-            UploadState::InProgress => (),
-            UploadState::Finalized => {
-                return Err(store_chunk::StoreChunkError::UploadAlreadyFinalized);
-            }
-            UploadState::FinalizeReupload => {
-                return Err(store_chunk::StoreChunkError::UploadAlreadyFinalized);
-            }
+        if data.expires_at <= now {
+            return Err(create_download_token::CreateDownloadTokenError::InvalidExpiresAt);
         }
 
-        let file_size = metadata.file_size;
-        let received_size = metadata.received_size;
-        let chunk_index = usize::try_from(data.chunk_id.0)
-            .map_err(|_| store_chunk::StoreChunkError::InvalidChunkId)?;
-        if chunk_index >= metadata.chunks.len() {
-            return Err(store_chunk::StoreChunkError::InvalidChunkId);
+        let path = data.file_path.trim_start_matches('/');
+        if !self.storage_raw_internal_metadata.contains_key(path) {
+            return Err(create_download_token::CreateDownloadTokenError::FileNotFound);
         }
-
-        if received_size + (data.chunk_data.len() as u64) > file_size {
-            return Err(store_chunk::StoreChunkError::InvalidChunkData);
+        if !self.has_download_token_key() {
+            return Err(
+                create_download_token::CreateDownloadTokenError::KeyUnavailable(
+                    "No signing key".to_string(),
+                ),
+            );
         }
 
-        // Check if the chunk has already been stored
-        if !metadata.chunks[chunk_index].is_empty() {
-            return Err(store_chunk::StoreChunkError::InvalidChunkData);
-        }
+        Ok(create_download_token::CreateDownloadTokenResp {
+            query: format!(
+                "token={}&expires={}",
+                self.download_token(path, data.expires_at),
+                data.expires_at
+            ),
+        })
+    }
 
-        metadata.chunks[chunk_index] = data.chunk_data.clone();
-        metadata.received_size = received_size + (data.chunk_data.len() as u64);
+    /// Whether a request for `path`, or for its `version`, may be answered:
+    /// always for public files, and for private ones or private versions with
+    /// an unexpired token signed for the path.
+    pub fn is_download_allowed(
+        &self,
+        path: &str,
+        version: Option<u64>,
+        token: Option<(&str, u64)>,
+        now: u64,
+    ) -> bool {
+        let key = path.trim_start_matches('/');
+        let is_private = self.file_visibility(key) == Visibility::Private
+            || version.is_some_and(|version| {
+                self.version_visibility(key, version) == Visibility::Private
+            });
+        if !is_private {
+            return true;
+        }
+        let Some((token, expires_at)) = token else {
+            return false;
+        };
+        if expires_at <= now || !self.has_download_token_key() {
+            return false;
+        }
+        // `verify_slice` compares in constant time so the token can't be
+        // guessed byte by byte.
+        hex::decode(token).is_ok_and(|token| {
+            self.download_token_mac(key, expires_at)
+                .verify_slice(&token)
+                .is_ok()
+        })
+    }
 
-        Ok(store_chunk::StoreChunkResp {})
+    /// Whether the file at `path` is past its expiry and waiting to be purged.
+    pub fn is_file_expired(&self, path: &str) -> bool {
+        let key = path.trim_start_matches('/');
+        self.storage_raw_internal_metadata
+            .get(key)
+            .is_some_and(|metadata| self.is_expired_at(key, metadata, ic_cdk::api::time()))
     }
 
-    pub fn finalize_upload(
-        &mut self,
-        data: finalize_upload::Args,
-    ) -> Result<finalize_upload::FinalizeUploadResp, finalize_upload::FinalizeUploadError> {
-        trace(&format!("finalize_upload - hash_id: {:?}", data.file_path));
+    /// Delete finalized files past their expiry like `remove_file` would,
+    /// skipping the trash. Returns the number of files deleted; intended for
+    /// the same hourly timer as the upload GC.
+    pub fn purge_expired_files(&mut self, now: u64) -> usize {
+        let expired: Vec<String> = self
+            .storage_raw_internal_metadata
+            .iter()
+            .filter(|(path, metadata)| {
+                !path.starts_with('?')
+                    && metadata.state == UploadState::Finalized
+                    && metadata.is_expired(now)
+            })
+            .map(|(path, _)| path.clone())
+            .collect();
+        let n = expired.len();
+        for path in expired {
+            trace(&format!("purge_expired_files: deleting {path}"));
+            self.discard_upload(&format!("?reupload:{}", path));
+            self.uncertify_path(&path);
+            self.delete_file(&path);
+        }
+        n
+    }
 
-        validate_file_path(&data.file_path)
-            .map_err(|_| finalize_upload::FinalizeUploadError::InvalidFilePath)?;
+    /// One page of file metadata in path order, without reading any bytes.
+    /// Reuploads in flight are not listed separately from the file they replace,
+    /// and private files only if `can_read_private` allows them.
+    pub fn list_files(
+        &self,
+        args: list_files::Args,
+        can_read_private: impl Fn(&str) -> bool,
+    ) -> list_files::ListFilesResp {
+        let prefix = args.prefix.as_deref().unwrap_or("").trim_start_matches('/');
+        let cursor = args.cursor.as_deref().map(|c| c.trim_start_matches('/'));
+        let limit = args
+            .limit
+            .map_or(LIST_FILES_DEFAULT_LIMIT, |l| l as usize)
+            .clamp(1, LIST_FILES_MAX_LIMIT);
+
+        let mut paths: Vec<&String> = self
+            .storage_raw_internal_metadata
+            .keys()
+            .filter(|path| !path.starts_with('?') && path.starts_with(prefix))
+            .filter(|path| cursor.is_none_or(|cursor| path.as_str() > cursor))
+            .filter(|path| self.is_visible(path, &can_read_private))
+            .collect();
+        paths.sort_unstable();
 
-        let path = data.file_path.trim_start_matches('/').to_string();
-        let reupload_key = format!("?reupload:{}", path);
+        let files: Vec<list_files::FileInfo> = paths
+            .iter()
+            .take(limit)
+            .filter_map(|path| {
+                let metadata = self.storage_raw_internal_metadata.get(*path)?;
+                Some(list_files::FileInfo {
+                    file_path: format!("/{}", path),
+                    file_size: metadata.file_size,
+                    file_hash: metadata.file_hash.clone(),
+                    content_type: metadata
+                        .content_type
+                        .clone()
+                        .unwrap_or_else(|| get_content_type_for_path(path).to_string()),
+                    state: metadata.state.clone(),
+                    init_timestamp: metadata.init_timestamp,
+                    finalized_timestamp: metadata.finalized_timestamp,
+                    metadata: metadata.user_metadata.clone(),
+                    expires_at: metadata.expires_at,
+                })
+            })
+            .collect();
 
-        let is_reupload = self
-            .storage_raw_internal_metadata
-            .contains_key(&reupload_key);
-        let mut metadata = if is_reupload {
-            self.storage_raw_internal_metadata
-                .remove(&reupload_key)
-                .unwrap()
+        let next_cursor = if paths.len() > limit {
+            files.last().map(|file| file.file_path.clone())
         } else {
-            self.storage_raw_internal_metadata
-                .remove(&path)
-                .ok_or(finalize_upload::FinalizeUploadError::UploadNotStarted)?
+            None
         };
 
-        match metadata.state {
-            UploadState::Init => {
-                self.storage_raw_internal_metadata
-                    .insert(path.clone(), metadata);
-                return Err(finalize_upload::FinalizeUploadError::UploadNotStarted);
-            }
-            UploadState::ReuploadInit => {
-                self.storage_raw_internal_metadata
-                    .insert(path.clone(), metadata);
-                return Err(finalize_upload::FinalizeUploadError::UploadNotStarted);
-            }
-            UploadState::InitReupload => {
-                self.storage_raw_internal_metadata
-                    .insert(reupload_key, metadata);
-                return Err(finalize_upload::FinalizeUploadError::UploadNotStarted);
-            }
-            UploadState::InProgress => {}
-            UploadState::ChunkReupload => {}
-            UploadState::FinalizeReupload => {}
-            UploadState::Finalized => {
-                return Err(finalize_upload::FinalizeUploadError::UploadAlreadyFinalized);
-            }
-        }
+        list_files::ListFilesResp { files, next_cursor }
+    }
 
-        let file_size = metadata.file_size as u128;
-        let received_size = metadata.received_size as u128;
+    /// Files in the trash, in ascending path order, leaving out private ones
+    /// `can_read_private` doesn't allow.
+    pub fn list_trash(
+        &self,
+        args: list_trash::Args,
+        can_read_private: impl Fn(&str) -> bool,
+    ) -> list_trash::ListTrashResp {
+        let prefix = args.prefix.as_deref().unwrap_or("").trim_start_matches('/');
+
+        let mut paths: Vec<&String> = self
+            .trash
+            .iter()
+            .filter(|(path, _)| path.starts_with(prefix))
+            .filter(|(path, trashed)| {
+                trashed.metadata.visibility == Visibility::Public || can_read_private(path)
+            })
+            .map(|(path, _)| path)
+            .collect();
+        paths.sort_unstable();
+
+        let files = paths
+            .into_iter()
+            .filter_map(|path| {
+                let trashed = self.trash.get(path)?;
+                Some(list_trash::TrashedFile {
+                    file_path: format!("/{}", path),
+                    file_size: trashed.metadata.file_size,
+                    file_hash: trashed.metadata.file_hash.clone(),
+                    content_type: trashed
+                        .metadata
+                        .content_type
+                        .clone()
+                        .unwrap_or_else(|| get_content_type_for_path(path).to_string()),
+                    deleted_at: trashed.deleted_at,
+                    purge_at: trashed
+                        .deleted_at
+                        .saturating_add(self.trash_retention_nanos),
+                })
+            })
+            .collect();
 
-        if received_size != file_size {
-            return Err(finalize_upload::FinalizeUploadError::IncompleteUpload);
-        }
+        list_trash::ListTrashResp { files }
+    }
 
-        // Drain chunks into the assembled buffer to avoid an extra full-file clone.
-        let mut file_data = Vec::with_capacity(file_size as usize);
-        for chunk in metadata.chunks.drain(..) {
-            file_data.extend(chunk);
-        }
+    /// Put a trashed file back at its path, with its version history and any
+    /// variant whose path is still free.
+    pub fn restore_file(
+        &mut self,
+        data: restore_file::Args,
+        caller: Principal,
+    ) -> Result<restore_file::RestoreFileResp, restore_file::RestoreFileError> {
+        trace(&format!("restore_file - file_path: {:?}", data.file_path));
 
-        if (file_data.len() as u64) != metadata.file_size {
-            return Err(finalize_upload::FinalizeUploadError::FileSizeMismatch);
-        }
+        validate_file_path(&data.file_path)
+            .map_err(|_| restore_file::RestoreFileError::InvalidFilePath)?;
 
-        let mut hasher = Sha256::new();
-        hasher.update(&file_data);
-        let calculated_hash = hex::encode(hasher.finalize());
+        let path = data.file_path.trim_start_matches('/').to_string();
 
-        if calculated_hash != metadata.file_hash {
-            return Err(finalize_upload::FinalizeUploadError::FileHashMismatch);
+        if !self.trash.contains_key(&path) {
+            return Err(restore_file::RestoreFileError::NotInTrash);
+        }
+        if self.storage_raw_internal_metadata.contains_key(&path) || self.upload_key(&path) != path
+        {
+            return Err(restore_file::RestoreFileError::DestinationExists);
         }
 
-        if is_reupload {
-            metadata.state = UploadState::FinalizeReupload;
+        // The restored file and variants are the caller's. Trashed files already
+        // count toward their owner and the file cap, so only those changing
+        // hands are charged.
+        let Some(trashed) = self.trash.get(&path) else {
+            return Err(restore_file::RestoreFileError::NotInTrash);
+        };
+        let restored: Vec<&InternalRawStorageMetadata> = std::iter::once(&trashed.metadata)
+            .chain(trashed.variants.iter().filter(|variant| {
+                !self
+                    .storage_raw_internal_metadata
+                    .contains_key(&variant.file_path)
+            }))
+            .filter(|metadata| metadata.owner != Some(caller))
+            .collect();
+        let added_bytes = restored.iter().map(|metadata| metadata.file_size).sum();
+        if !self.fits_quota(caller, 0, added_bytes, restored.len() as u64) {
+            return Err(restore_file::RestoreFileError::QuotaExceeded);
         }
 
-        // CRITICAL CACHE CLEANUP: If this file was previously certified and cached,
-        // we must clear the old asset out of the certification tree since the bytes changed.
-        if self.certified_assets.contains(&path) {
-            if let Some(old_data) = self.storage_raw.get(&path) {
-                uncertify_asset(vec![Asset::new(path.clone(), old_data)]);
+        let Some(mut trashed) = self.trash.remove(&path) else {
+            return Err(restore_file::RestoreFileError::NotInTrash);
+        };
+        trashed.metadata.owner = Some(caller);
+
+        for mut variant in trashed.variants {
+            let variant_path = variant.file_path.clone();
+            match self.storage_raw_internal_metadata.entry(variant_path) {
+                Entry::Vacant(entry) => {
+                    variant.owner = Some(caller);
+                    entry.insert(variant);
+                }
+                Entry::Occupied(entry) => {
+                    let variant_path = entry.key().clone();
+                    self.release_file_bytes(&variant_path, &variant);
+                }
             }
-            self.certified_assets.retain(|asset| asset != &path);
         }
+        if !trashed.versions.is_empty() {
+            self.versions.insert(path.clone(), trashed.versions);
+            self.prune_versions(&path);
+        }
+        self.storage_raw_internal_metadata
+            .insert(path, trashed.metadata);
 
-        metadata.state = UploadState::Finalized;
+        Ok(restore_file::RestoreFileResp {})
+    }
 
-        // Overwrite the raw bytes and re-insert finalized metadata
-        self.storage_raw.insert(path.clone(), file_data);
-        self.storage_raw_internal_metadata
-            .insert(path.clone(), metadata);
+    /// Delete a trashed file right away instead of waiting for the sweep.
+    pub fn purge_file(
+        &mut self,
+        data: purge_file::Args,
+    ) -> Result<purge_file::PurgeFileResp, purge_file::PurgeFileError> {
+        trace(&format!("purge_file - file_path: {:?}", data.file_path));
 
-        trace(&format!("finalize_upload - file_path: {:?}", path));
+        validate_file_path(&data.file_path)
+            .map_err(|_| purge_file::PurgeFileError::InvalidFilePath)?;
 
-        Ok(finalize_upload::FinalizeUploadResp {
-            url: format!(
-                "https://{}.raw.icp0.io/{}",
-                ic_cdk::api::canister_self().to_string(),
-                path
-            ),
-        })
+        let path = data.file_path.trim_start_matches('/');
+        if !self.purge_trashed(path) {
+            return Err(purge_file::PurgeFileError::NotInTrash);
+        }
+
+        Ok(purge_file::PurgeFileResp {})
     }
 
-    /// Sweep abandoned uploads: any entry still in `Init` or `InProgress` whose
-    /// `init_timestamp` is older than `ttl_nanos` is removed. Returns the number
-    /// of entries removed. Cheap to run (single iteration); intended for an
-    /// hourly heartbeat.
-    pub fn gc_abandoned_uploads(&mut self, now: u64, ttl_nanos: u64) -> usize {
-        let cutoff = now.saturating_sub(ttl_nanos);
-        let stale: Vec<String> = self
-            .storage_raw_internal_metadata
+    /// Delete trashed files whose retention period is over. Returns the number
+    /// of files deleted; intended for the same hourly timer as the upload GC.
+    pub fn purge_expired_trash(&mut self, now: u64) -> usize {
+        let retention = self.trash_retention_nanos;
+        let expired: Vec<String> = self
+            .trash
             .iter()
-            .filter_map(|(path, m)| match m.state {
-                UploadState::Finalized => None,
-                _ if m.init_timestamp <= cutoff => Some(path.clone()),
-                _ => None,
-            })
+            .filter(|(_, trashed)| trashed.deleted_at.saturating_add(retention) <= now)
+            .map(|(path, _)| path.clone())
             .collect();
-        let n = stale.len();
-        for path in stale {
-            trace(&format!("gc_abandoned_uploads: removing {path}"));
-            self.storage_raw_internal_metadata.remove(&path);
+        let n = expired.len();
+        for path in expired {
+            trace(&format!("purge_expired_trash: purging {path}"));
+            self.purge_trashed(&path);
         }
         n
     }
 
-    pub fn get_file_data(&self, path: &str) -> Option<(Vec<u8>, &'static str)> {
-        let key = path.trim_start_matches('/');
-        let metadata = self.storage_raw_internal_metadata.get(key)?;
-        if metadata.state != UploadState::Finalized {
-            return None;
-        }
-        let data = self.storage_raw.get(&key.to_string())?;
-        Some((data, get_content_type_for_path(key)))
-    }
-
     pub fn get_all_files(&self) -> Vec<(InternalRawStorageMetadata, Vec<u8>)> {
         self.storage_raw_internal_metadata
             .iter()
@@ -434,7 +2821,7 @@ impl StorageData {
                 if metadata.state != UploadState::Finalized {
                     return None;
                 }
-                match self.storage_raw.get(hash_id) {
+                match self.read_file_bytes(hash_id, metadata) {
                     Some(raw_data) => Some((metadata.clone(), raw_data)),
                     None => {
                         trace(&format!(
@@ -461,7 +2848,7 @@ impl StorageData {
             .storage_raw_internal_metadata
             .contains_key(&reupload_key)
         {
-            self.storage_raw_internal_metadata.remove(&reupload_key);
+            self.discard_upload(&reupload_key);
             return Ok(cancel_upload::CancelUploadResp {});
         }
 
@@ -474,7 +2861,7 @@ impl StorageData {
             Some(_) => {}
         }
 
-        self.storage_raw_internal_metadata.remove(&path);
+        self.discard_upload(&path);
         Ok(cancel_upload::CancelUploadResp {})
     }
 
@@ -488,31 +2875,304 @@ impl StorageData {
         let path = file_path.trim_start_matches('/').to_string();
         let reupload_key = format!("?reupload:{}", path);
 
-        self.storage_raw_internal_metadata.remove(&reupload_key);
+        self.discard_upload(&reupload_key);
 
         let metadata = self
             .storage_raw_internal_metadata
-            .remove(&path)
+            .get(&path)
             .ok_or(remove_file::RemoveFileError::UploadNotInitialized)?;
+        let file_size = metadata.file_size;
+
+        if metadata.state == UploadState::Finalized {
+            // Remove certified asset if present
+            self.uncertify_path(&path);
+            self.trash_file(&path);
+        } else {
+            self.discard_upload(&path);
+        }
+
+        trace(&format!(
+            "remove_file: removed {} ({}) bytes",
+            path, file_size
+        ));
+
+        Ok(remove_file::RemoveFileResp {})
+    }
+
+    /// Re-key a finalized file from `from` to `to` within a single message, so
+    /// no caller ever observes both paths or neither. The bytes themselves stay
+    /// where they are unless the file predates the chunk store.
+    pub fn move_file(
+        &mut self,
+        data: move_file::Args,
+    ) -> Result<move_file::MoveFileResp, move_file::MoveFileError> {
+        trace(&format!(
+            "move_file - from: {:?} to: {:?}",
+            data.from, data.to
+        ));
+
+        validate_file_path(&data.from).map_err(|_| move_file::MoveFileError::InvalidFilePath)?;
+        validate_file_path(&data.to).map_err(|_| move_file::MoveFileError::InvalidFilePath)?;
+
+        let from = data.from.trim_start_matches('/').to_string();
+        let to = data.to.trim_start_matches('/').to_string();
+
+        let source = self
+            .storage_raw_internal_metadata
+            .get(&from)
+            .ok_or(move_file::MoveFileError::FileNotFound)?;
+        if source.state != UploadState::Finalized {
+            return Err(move_file::MoveFileError::UploadInProgress);
+        }
+        if from == to {
+            return Ok(move_file::MoveFileResp {});
+        }
+
+        // A reupload in flight is tied to its path; moving under it would orphan it.
+        for path in [&from, &to] {
+            if self.upload_key(path) != *path {
+                return Err(move_file::MoveFileError::UploadInProgress);
+            }
+        }
+
+        // A move within a bucket doesn't grow it.
+        let replaced_bytes = self
+            .storage_raw_internal_metadata
+            .get(&to)
+            .map_or(0, |destination| destination.file_size);
+        let freed_bytes = if self.bucket_of(&from).map(|(name, _)| name)
+            == self.bucket_of(&to).map(|(name, _)| name)
+        {
+            replaced_bytes + source.file_size
+        } else {
+            replaced_bytes
+        };
+        if !self.fits_bucket(&to, freed_bytes, source.file_size) {
+            return Err(move_file::MoveFileError::QuotaExceeded);
+        }
+
+        if let Some(destination) = self.storage_raw_internal_metadata.get(&to) {
+            if destination.state != UploadState::Finalized {
+                return Err(move_file::MoveFileError::UploadInProgress);
+            }
+            if !data.overwrite {
+                return Err(move_file::MoveFileError::DestinationExists);
+            }
 
-        // Remove certified asset if present
-        if self.certified_assets.contains(&path) {
-            if let Some(data) = self.storage_raw.get(&path) {
-                uncertify_asset(vec![Asset::new(path.clone(), data)]);
+            self.uncertify_path(&to);
+            self.remove_variants(&to);
+            if let Some(destination) = self.storage_raw_internal_metadata.remove(&to) {
+                self.release_file_bytes(&to, &destination);
             }
+            self.drop_versions(&to);
+        }
+
+        // Variants are linked by path, so they don't follow the file, and a
+        // moved variant becomes a file of its own, as visible as the one it encoded.
+        let visibility = self.file_visibility(&from);
+        self.uncertify_path(&from);
+        self.remove_variants(&from);
 
-            self.certified_assets.retain(|asset| asset != &path);
+        let Some(mut metadata) = self.storage_raw_internal_metadata.remove(&from) else {
+            return Err(move_file::MoveFileError::FileNotFound);
+        };
+        metadata.encoding = None;
+        metadata.visibility = visibility;
+        if metadata.content_key.is_none() {
+            if let Some(bytes) = self.storage_raw.remove(&from) {
+                self.storage_raw.insert(to.clone(), bytes);
+            }
+        }
+        metadata.file_path = to.clone();
+        // History follows the file; its bytes are all in the chunk store.
+        if let Some(mut history) = self.versions.remove(&from) {
+            for version in history.iter_mut() {
+                version.file_path = to.clone();
+            }
+            self.versions.insert(to.clone(), history);
         }
+        self.storage_raw_internal_metadata.insert(to, metadata);
 
-        // Remove raw bytes
-        self.storage_raw.remove(&path);
+        Ok(move_file::MoveFileResp {})
+    }
 
+    /// Publish a finalized file under a second path. The copy shares stored
+    /// content with the same hash; only a file that predates the chunk store and
+    /// whose content isn't shared yet is physically copied, and only then is
+    /// free storage needed.
+    pub fn copy_file(
+        &mut self,
+        data: copy_file::Args,
+        caller: Principal,
+    ) -> Result<copy_file::CopyFileResp, copy_file::CopyFileError> {
         trace(&format!(
-            "remove_file: removed {} ({}) bytes",
-            path, metadata.file_size
+            "copy_file - from: {:?} to: {:?}",
+            data.from, data.to
         ));
 
-        Ok(remove_file::RemoveFileResp {})
+        validate_file_path(&data.from).map_err(|_| copy_file::CopyFileError::InvalidFilePath)?;
+        validate_file_path(&data.to).map_err(|_| copy_file::CopyFileError::InvalidFilePath)?;
+
+        let from = data.from.trim_start_matches('/').to_string();
+        let to = data.to.trim_start_matches('/').to_string();
+
+        let source = self
+            .storage_raw_internal_metadata
+            .get(&from)
+            .ok_or(copy_file::CopyFileError::FileNotFound)?;
+        if source.state != UploadState::Finalized {
+            return Err(copy_file::CopyFileError::UploadInProgress);
+        }
+        if from == to {
+            return Ok(copy_file::CopyFileResp {});
+        }
+        if self.upload_key(&to) != to {
+            return Err(copy_file::CopyFileError::UploadInProgress);
+        }
+
+        match self.storage_raw_internal_metadata.get(&to) {
+            Some(destination) if destination.state != UploadState::Finalized => {
+                return Err(copy_file::CopyFileError::UploadInProgress);
+            }
+            Some(_) if !data.overwrite => {
+                return Err(copy_file::CopyFileError::DestinationExists);
+            }
+            Some(_) => {}
+            None => {
+                if self.file_count() >= MAX_FILES_PER_CANISTER {
+                    return Err(copy_file::CopyFileError::TooManyFiles);
+                }
+            }
+        }
+
+        // The copy is the caller's, replacing their own file at `to` if there is one.
+        let (freed_bytes, added_files) = self.replaced_usage(&to, caller);
+        let replaced_bytes = self
+            .storage_raw_internal_metadata
+            .get(&to)
+            .map_or(0, |destination| destination.file_size);
+        if !self.fits_quota(caller, freed_bytes, source.file_size, added_files)
+            || !self.fits_bucket(&to, replaced_bytes, source.file_size)
+        {
+            return Err(copy_file::CopyFileError::QuotaExceeded);
+        }
+
+        // A copied variant is a file of its own, as visible as the one it encodes.
+        let mut metadata = source.clone();
+        metadata.owner = Some(caller);
+        metadata.file_path = to.clone();
+        metadata.finalized_timestamp = Some(ic_cdk::api::time());
+        metadata.encoding = None;
+        metadata.visibility = self.file_visibility(&from);
+        metadata.version = 1;
+
+        // Any stored content with this hash holds the same bytes.
+        if let Some(content) = self.contents.get_mut(&metadata.file_hash) {
+            content.refs += 1;
+            metadata.content_key = Some(content.content_key.clone());
+        } else {
+            if self.get_free_storage_size_bytes() < (metadata.file_size as u128) {
+                return Err(copy_file::CopyFileError::NotEnoughStorage);
+            }
+            let bytes = self
+                .read_file_bytes(&from, &metadata)
+                .ok_or(copy_file::CopyFileError::FileNotFound)?;
+            metadata.content_key = Some(self.store_content(&metadata.file_hash, bytes));
+        }
+
+        self.uncertify_path(&to);
+        self.remove_variants(&to);
+        if let Some(destination) = self.storage_raw_internal_metadata.remove(&to) {
+            self.release_file_bytes(&to, &destination);
+        }
+        self.drop_versions(&to);
+        self.storage_raw_internal_metadata.insert(to, metadata);
+
+        Ok(copy_file::CopyFileResp {})
+    }
+
+    /// Upload and finalize many small files in one call. Each entry goes
+    /// through the regular init/store/finalize steps and either ends up
+    /// finalized or leaves nothing behind.
+    pub fn batch_upload(
+        &mut self,
+        data: batch_upload::Args,
+        caller: Principal,
+    ) -> Result<batch_upload::BatchUploadResp, batch_upload::BatchUploadError> {
+        trace(&format!("batch_upload - entries: {:?}", data.entries.len()));
+
+        if data.entries.len() > MAX_BATCH_UPLOAD_ENTRIES {
+            return Err(batch_upload::BatchUploadError::TooManyEntries);
+        }
+
+        let results = data
+            .entries
+            .into_iter()
+            .map(|entry| self.upload_batch_entry(entry, caller))
+            .collect();
+
+        Ok(batch_upload::BatchUploadResp { results })
+    }
+
+    fn upload_batch_entry(
+        &mut self,
+        entry: batch_upload::BatchUploadEntry,
+        caller: Principal,
+    ) -> Result<finalize_upload::FinalizeUploadResp, batch_upload::BatchUploadEntryError> {
+        let path = entry.file_path.trim_start_matches('/').to_string();
+
+        let init = self
+            .init_upload(
+                init_upload::Args {
+                    file_path: entry.file_path.clone(),
+                    file_hash: entry.expected_hash,
+                    file_size: entry.content.len() as u64,
+                    chunk_size: None,
+                    chunk_hashes: None,
+                    metadata: None,
+                    content_type: entry.content_type,
+                    headers: None,
+                    encoding: None,
+                    expires_at: None,
+                    visibility: None,
+                    bucket: None,
+                },
+                caller,
+            )
+            .map_err(batch_upload::BatchUploadEntryError::InitUpload)?;
+
+        if init.deduplicated != Some(true) {
+            for (index, chunk) in entry
+                .content
+                .chunks(DEFAULT_CHUNK_SIZE as usize)
+                .enumerate()
+            {
+                if let Err(e) = self.store_chunk(store_chunk::Args {
+                    file_path: entry.file_path.clone(),
+                    chunk_id: (index as u64).into(),
+                    chunk_data: chunk.to_vec(),
+                    chunk_hash: None,
+                    bucket: None,
+                }) {
+                    self.discard_upload(&path);
+                    return Err(batch_upload::BatchUploadEntryError::StoreChunk(e));
+                }
+            }
+
+            // The whole entry is already in this message, so finalize it in one step.
+            if let Err(e) = self.finalize_upload(
+                finalize_upload::Args {
+                    file_path: entry.file_path.clone(),
+                    bucket: None,
+                },
+                u64::MAX,
+            ) {
+                self.discard_upload(&path);
+                return Err(batch_upload::BatchUploadEntryError::FinalizeUpload(e));
+            }
+        }
+
+        Ok(finalize_upload_resp(&path, UploadState::Finalized))
     }
 
     pub fn cache_miss(&mut self, env: &CanisterEnv, path: String) -> Result<(), String> {
@@ -540,11 +3200,25 @@ impl StorageData {
             return Err("Upload not finalized".to_string());
         }
 
-        let file_size = metadata.file_size as u64;
+        if metadata.is_expired(ic_cdk::api::time()) {
+            return Err("File expired".to_string());
+        }
 
-        let file_data = self.storage_raw.get(&path).ok_or_else(|| {
-            format!("cache_miss: metadata marked Finalized but raw bytes missing for {path}")
-        })?;
+        // Variants are only certified along with the file they encode.
+        if metadata.encoding.is_some() {
+            return Err("Pre-compressed variant".to_string());
+        }
+
+        if self.file_visibility(&path) == Visibility::Private {
+            return Err("Private file".to_string());
+        }
+
+        let (assets, file_size) = self.certification_assets(&path);
+        if assets.is_empty() {
+            return Err(format!(
+                "cache_miss: metadata marked Finalized but raw bytes missing for {path}"
+            ));
+        }
 
         if free_heap_size < file_size {
             trace(&format!(
@@ -556,7 +3230,8 @@ impl StorageData {
 
         trace(&format!("certify_asset path.clone() : {:?}", path.clone()));
 
-        certify_asset(vec![Asset::new(path.clone(), file_data)]);
+        let configs = self.asset_configs(&path);
+        certify_asset(assets, configs);
         self.certified_assets.push(path.clone());
 
         Ok(())
@@ -585,22 +3260,16 @@ impl StorageData {
                 continue;
             }
 
-            let file_size = metadata.file_size as u64;
-            let file_data = match self.storage_raw.get(&key) {
-                Some(d) => d,
-                None => {
-                    trace(&format!(
-                        "free_http_cache: metadata marked Finalized but raw bytes missing for {key}, dropping from certified set"
-                    ));
-                    self.certified_assets.retain(|asset| asset != &key);
-                    continue;
-                }
-            };
+            let (assets, file_size) = self.certification_assets(&key);
+            if assets.is_empty() {
+                trace(&format!(
+                    "free_http_cache: metadata marked Finalized but raw bytes missing for {key}, dropping from certified set"
+                ));
+                self.certified_assets.retain(|asset| asset != &key);
+                continue;
+            }
 
-            uncertify_asset(vec![Asset::new(
-                metadata.file_path.clone(),
-                file_data.clone(),
-            )]);
+            uncertify_asset(assets, self.asset_configs(&key));
 
             self.certified_assets.retain(|asset| asset != &key);
 
@@ -1 +1,49 @@
+use crate::guards::{caller_is_admin, caller_is_controller};
+use crate::state::mutate_state;
+pub use bity_ic_storage_canister_api::add_authorized_principals;
+pub use bity_ic_storage_canister_api::create_bucket;
+pub use bity_ic_storage_canister_api::delete_bucket;
+pub use bity_ic_storage_canister_api::grant_role;
+pub use bity_ic_storage_canister_api::remove_authorized_principals;
+pub use bity_ic_storage_canister_api::revoke_role;
+pub use bity_ic_storage_canister_api::update_bucket;
+use ic_cdk::update;
 
+#[update(guard = "caller_is_admin")]
+pub fn create_bucket(data: create_bucket::Args) -> create_bucket::Response {
+    mutate_state(|state| state.data.storage.create_bucket(data))
+}
+
+#[update(guard = "caller_is_admin")]
+pub fn update_bucket(data: update_bucket::Args) -> update_bucket::Response {
+    mutate_state(|state| state.data.storage.update_bucket(data))
+}
+
+#[update(guard = "caller_is_admin")]
+pub fn delete_bucket(data: delete_bucket::Args) -> delete_bucket::Response {
+    mutate_state(|state| state.data.storage.delete_bucket(data))
+}
+
+#[update(guard = "caller_is_admin")]
+pub fn grant_role(data: grant_role::Args) -> grant_role::Response {
+    mutate_state(|state| state.data.grant_role(data))
+}
+
+#[update(guard = "caller_is_admin")]
+pub fn revoke_role(data: revoke_role::Args) -> revoke_role::Response {
+    mutate_state(|state| state.data.revoke_role(data))
+}
+
+#[update(guard = "caller_is_controller")]
+pub fn add_authorized_principals(
+    data: add_authorized_principals::Args,
+) -> add_authorized_principals::Response {
+    mutate_state(|state| state.data.add_authorized_principals(data))
+}
+
+#[update(guard = "caller_is_controller")]
+pub fn remove_authorized_principals(
+    data: remove_authorized_principals::Args,
+) -> remove_authorized_principals::Response {
+    mutate_state(|state| state.data.remove_authorized_principals(data))
+}
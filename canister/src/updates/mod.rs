@@ -1,4 +1,5 @@
 pub mod management;
 pub mod storage;
 
+pub use management::*;
 pub use storage::*;
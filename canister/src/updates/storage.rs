@@ -1,57 +1,326 @@
-use crate::guards::caller_is_governance_principal;
-use crate::state::mutate_state;
+use crate::guards::{caller_has_file_access, caller_is_admin};
+use crate::jobs::{schedule_finalization, schedule_hash_catch_up};
+use crate::state::{mutate_state, read_state};
+use crate::types::storage::finalize_step_bytes;
+pub use bity_ic_storage_canister_api::batch_upload;
 pub use bity_ic_storage_canister_api::cancel_upload;
+pub use bity_ic_storage_canister_api::copy_file;
+pub use bity_ic_storage_canister_api::create_download_token;
+pub use bity_ic_storage_canister_api::create_upload_ticket;
 pub use bity_ic_storage_canister_api::finalize_upload;
 pub use bity_ic_storage_canister_api::init_reupload;
 pub use bity_ic_storage_canister_api::init_upload;
+pub use bity_ic_storage_canister_api::move_file;
+pub use bity_ic_storage_canister_api::purge_file;
 pub use bity_ic_storage_canister_api::remove_file;
+pub use bity_ic_storage_canister_api::restore_file;
+pub use bity_ic_storage_canister_api::restore_file_version;
+pub use bity_ic_storage_canister_api::revoke_upload_ticket;
+pub use bity_ic_storage_canister_api::set_file_expiry;
+pub use bity_ic_storage_canister_api::set_file_headers;
+pub use bity_ic_storage_canister_api::set_file_metadata;
+pub use bity_ic_storage_canister_api::set_file_visibility;
+pub use bity_ic_storage_canister_api::set_principal_quota;
 pub use bity_ic_storage_canister_api::store_chunk;
+use bity_ic_storage_canister_api::types::storage::{Role, UploadState};
+use bity_ic_utils::env::Environment;
+use ic_cdk::management_canister::raw_rand;
 use ic_cdk::update;
 
-#[update(guard = "caller_is_governance_principal")]
+#[update(guard = "caller_has_file_access")]
 pub fn init_upload(data: init_upload::Args) -> init_upload::Response {
-    match mutate_state(|state| state.data.init_upload(data)) {
-        Ok(_) => Ok(init_upload::InitUploadResp {}),
-        Err(e) => Err(e),
-    }
+    mutate_state(|state| {
+        let (file_path, ticket_id) = state
+            .upload_file_path(data.bucket.as_deref(), &data.file_path, &[Role::Uploader])
+            .map_err(init_upload::InitUploadError::BucketAccess)?;
+        let file_size = data.file_size;
+        let ticket = ticket_id.and_then(|id| state.data.upload_tickets.get(&id));
+        // Charged up front, so the ticket never covers more than `max_bytes`.
+        if ticket
+            .is_some_and(|ticket| ticket.used_bytes.saturating_add(file_size) > ticket.max_bytes)
+        {
+            return Err(init_upload::InitUploadError::QuotaExceeded);
+        }
+        let data = init_upload::Args { file_path, ..data };
+        let caller = state.env.caller();
+        let resp = state.data.init_upload(data, caller)?;
+        if let Some(ticket) = ticket_id.and_then(|id| state.data.upload_tickets.get_mut(&id)) {
+            ticket.used_bytes += file_size;
+        }
+        Ok(resp)
+    })
 }
 
-#[update(guard = "caller_is_governance_principal")]
+#[update(guard = "caller_has_file_access")]
 pub fn init_reupload(data: init_reupload::Args) -> init_reupload::Response {
-    match mutate_state(|state| state.data.init_reupload(data)) {
+    match mutate_state(|state| {
+        let file_path = state
+            .scoped_file_path(data.bucket.as_deref(), &data.file_path, &[Role::Editor])
+            .map_err(init_reupload::InitReuploadError::BucketAccess)?;
+        let data = init_reupload::Args { file_path, ..data };
+        let caller = state.env.caller();
+        state.data.init_reupload(data, caller)
+    }) {
         Ok(_) => Ok(init_reupload::InitReuploadResp {}),
         Err(e) => Err(e),
     }
 }
 
-#[update(guard = "caller_is_governance_principal")]
+#[update(guard = "caller_has_file_access")]
 pub fn store_chunk(data: store_chunk::Args) -> store_chunk::Response {
-    match mutate_state(|state| state.data.store_chunk(data)) {
-        Ok(_) => Ok(store_chunk::StoreChunkResp {}),
+    match mutate_state(|state| {
+        let file_path = state
+            .upload_in_flight_path(data.bucket.as_deref(), &data.file_path)
+            .map(|(file_path, _)| file_path)
+            .map_err(store_chunk::StoreChunkError::BucketAccess)?;
+        let data = store_chunk::Args {
+            file_path: file_path.clone(),
+            ..data
+        };
+        state.data.store_chunk(data)?;
+        Ok(file_path)
+    }) {
+        Ok(file_path) => {
+            // This chunk may have filled a gap with more staged chunks behind it.
+            if mutate_state(|state| state.data.storage.start_hash_catch_up(&file_path)) {
+                schedule_hash_catch_up(file_path);
+            }
+            Ok(store_chunk::StoreChunkResp {})
+        }
         Err(e) => Err(e),
     }
 }
 
-#[update(guard = "caller_is_governance_principal")]
+#[update(guard = "caller_has_file_access")]
 pub fn finalize_upload(data: finalize_upload::Args) -> finalize_upload::Response {
-    match mutate_state(|state| state.data.finalize_upload(data)) {
-        Ok(resp) => Ok(resp),
+    let file_path =
+        read_state(|state| state.upload_in_flight_path(data.bucket.as_deref(), &data.file_path))
+            .map(|(file_path, _)| file_path)
+            .map_err(finalize_upload::FinalizeUploadError::BucketAccess)?;
+    let data = finalize_upload::Args {
+        file_path: file_path.clone(),
+        ..data
+    };
+    match mutate_state(|state| {
+        let step_bytes = finalize_step_bytes(&state.env);
+        state.data.finalize_upload(data, step_bytes)
+    }) {
+        Ok(resp) => {
+            if resp.state == Some(UploadState::Finalizing) {
+                schedule_finalization(file_path);
+            }
+            Ok(resp)
+        }
         Err(e) => Err(e),
     }
 }
 
-#[update(guard = "caller_is_governance_principal")]
+#[update(guard = "caller_has_file_access")]
 pub fn remove_file(data: remove_file::Args) -> remove_file::Response {
-    match mutate_state(|state| state.data.remove_file(data.file_path)) {
+    match mutate_state(|state| {
+        let file_path = state
+            .scoped_file_path(data.bucket.as_deref(), &data.file_path, &[Role::Deleter])
+            .map_err(remove_file::RemoveFileError::BucketAccess)?;
+        state.data.remove_file(file_path)
+    }) {
         Ok(_) => Ok(remove_file::RemoveFileResp {}),
         Err(e) => Err(e),
     }
 }
 
-#[update(guard = "caller_is_governance_principal")]
+#[update(guard = "caller_has_file_access")]
 pub fn cancel_upload(data: cancel_upload::Args) -> cancel_upload::Response {
-    match mutate_state(|state| state.data.cancel_upload(data.file_path)) {
+    match mutate_state(|state| {
+        let file_path = state
+            .upload_in_flight_path(data.bucket.as_deref(), &data.file_path)
+            .map(|(file_path, _)| file_path)
+            .map_err(cancel_upload::CancelUploadError::BucketAccess)?;
+        state.data.cancel_upload(file_path)
+    }) {
         Ok(_) => Ok(cancel_upload::CancelUploadResp {}),
         Err(e) => Err(e),
     }
 }
+
+#[update(guard = "caller_has_file_access")]
+pub fn move_file(data: move_file::Args) -> move_file::Response {
+    mutate_state(|state| {
+        let scoped = |file_path: &str| {
+            state
+                .scoped_file_path(data.bucket.as_deref(), file_path, &[Role::Editor])
+                .map_err(move_file::MoveFileError::BucketAccess)
+        };
+        let (from, to) = (scoped(&data.from)?, scoped(&data.to)?);
+        state.data.move_file(move_file::Args { from, to, ..data })
+    })
+}
+
+#[update(guard = "caller_has_file_access")]
+pub fn copy_file(data: copy_file::Args) -> copy_file::Response {
+    // Only replacing a file takes an Editor; a new copy is an upload. Reading
+    // the source takes any role on it, as sharing it would.
+    let role = if data.overwrite {
+        Role::Editor
+    } else {
+        Role::Uploader
+    };
+    mutate_state(|state| {
+        let bucket = data.bucket.as_deref();
+        let from = state
+            .scoped_file_path(
+                bucket,
+                &data.from,
+                &[Role::Uploader, Role::Editor, Role::Deleter],
+            )
+            .map_err(copy_file::CopyFileError::BucketAccess)?;
+        let to = state
+            .scoped_file_path(bucket, &data.to, &[role])
+            .map_err(copy_file::CopyFileError::BucketAccess)?;
+        let caller = state.env.caller();
+        state
+            .data
+            .copy_file(copy_file::Args { from, to, ..data }, caller)
+    })
+}
+
+#[update(guard = "caller_has_file_access")]
+pub fn batch_upload(data: batch_upload::Args) -> batch_upload::Response {
+    mutate_state(|state| {
+        let entries = data
+            .entries
+            .into_iter()
+            .map(|entry| {
+                state
+                    .scoped_file_path(data.bucket.as_deref(), &entry.file_path, &[Role::Uploader])
+                    .map(|file_path| batch_upload::BatchUploadEntry { file_path, ..entry })
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(batch_upload::BatchUploadError::BucketAccess)?;
+        let data = batch_upload::Args { entries, ..data };
+        let caller = state.env.caller();
+        state.data.batch_upload(data, caller)
+    })
+}
+
+#[update(guard = "caller_has_file_access")]
+pub fn set_file_metadata(data: set_file_metadata::Args) -> set_file_metadata::Response {
+    mutate_state(|state| {
+        if !state.caller_has_role(&[Role::Editor], &data.file_path) {
+            return Err(set_file_metadata::SetFileMetadataError::NotAuthorized);
+        }
+        state.data.set_file_metadata(data)
+    })
+}
+
+#[update(guard = "caller_has_file_access")]
+pub fn set_file_headers(data: set_file_headers::Args) -> set_file_headers::Response {
+    mutate_state(|state| {
+        if !state.caller_has_role(&[Role::Editor], &data.file_path) {
+            return Err(set_file_headers::SetFileHeadersError::NotAuthorized);
+        }
+        state.data.set_file_headers(data)
+    })
+}
+
+#[update(guard = "caller_has_file_access")]
+pub fn restore_file_version(data: restore_file_version::Args) -> restore_file_version::Response {
+    mutate_state(|state| {
+        if !state.caller_has_role(&[Role::Editor], &data.file_path) {
+            return Err(restore_file_version::RestoreFileVersionError::NotAuthorized);
+        }
+        let caller = state.env.caller();
+        state.data.restore_file_version(data, caller)
+    })
+}
+
+#[update(guard = "caller_has_file_access")]
+pub fn restore_file(data: restore_file::Args) -> restore_file::Response {
+    mutate_state(|state| {
+        if !state.caller_has_role(&[Role::Deleter], &data.file_path) {
+            return Err(restore_file::RestoreFileError::NotAuthorized);
+        }
+        let caller = state.env.caller();
+        state.data.restore_file(data, caller)
+    })
+}
+
+#[update(guard = "caller_has_file_access")]
+pub fn purge_file(data: purge_file::Args) -> purge_file::Response {
+    mutate_state(|state| {
+        if !state.caller_has_role(&[Role::Deleter], &data.file_path) {
+            return Err(purge_file::PurgeFileError::NotAuthorized);
+        }
+        state.data.purge_file(data)
+    })
+}
+
+#[update(guard = "caller_has_file_access")]
+pub fn set_file_expiry(data: set_file_expiry::Args) -> set_file_expiry::Response {
+    mutate_state(|state| {
+        if !state.caller_has_role(&[Role::Editor], &data.file_path) {
+            return Err(set_file_expiry::SetFileExpiryError::NotAuthorized);
+        }
+        state.data.set_file_expiry(data)
+    })
+}
+
+#[update(guard = "caller_has_file_access")]
+pub fn set_file_visibility(data: set_file_visibility::Args) -> set_file_visibility::Response {
+    mutate_state(|state| {
+        if !state.caller_has_role(&[Role::Editor], &data.file_path) {
+            return Err(set_file_visibility::SetFileVisibilityError::NotAuthorized);
+        }
+        state.data.set_file_visibility(data)
+    })
+}
+
+/// Any role on the path allows sharing the file. The signing key is drawn
+/// from the management canister on first use.
+#[update(guard = "caller_has_file_access")]
+pub async fn create_download_token(
+    data: create_download_token::Args,
+) -> create_download_token::Response {
+    if !read_state(|state| state.caller_may_read(&data.file_path)) {
+        return Err(create_download_token::CreateDownloadTokenError::NotAuthorized);
+    }
+    if !read_state(|state| state.data.storage.has_download_token_key()) {
+        let key = raw_rand().await.map_err(|e| {
+            create_download_token::CreateDownloadTokenError::KeyUnavailable(e.to_string())
+        })?;
+        mutate_state(|state| state.data.storage.init_download_token_key(key));
+    }
+    read_state(|state| {
+        state
+            .data
+            .storage
+            .create_download_token(data, ic_cdk::api::time())
+    })
+}
+
+/// Tickets only delegate what the issuer may do: uploading to the path.
+#[update(guard = "caller_has_file_access")]
+pub fn create_upload_ticket(data: create_upload_ticket::Args) -> create_upload_ticket::Response {
+    mutate_state(|state| {
+        if !state.caller_has_role(&[Role::Uploader], &data.file_path) {
+            return Err(create_upload_ticket::CreateUploadTicketError::NotAuthorized);
+        }
+        let caller = state.env.caller();
+        state
+            .data
+            .create_upload_ticket(data, caller, ic_cdk::api::time())
+    })
+}
+
+#[update(guard = "caller_has_file_access")]
+pub fn revoke_upload_ticket(data: revoke_upload_ticket::Args) -> revoke_upload_ticket::Response {
+    mutate_state(|state| {
+        let caller = state.env.caller();
+        let is_admin = state.is_caller_admin();
+        state.data.revoke_upload_ticket(data, caller, is_admin)
+    })
+}
+
+#[update(guard = "caller_is_admin")]
+pub fn set_principal_quota(data: set_principal_quota::Args) -> set_principal_quota::Response {
+    mutate_state(|state| state.data.set_principal_quota(data))
+}
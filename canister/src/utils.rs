@@ -35,6 +35,74 @@ pub fn validate_file_path(p: &str) -> Result<(), &'static str> {
     Ok(())
 }
 
+/// Whether `path` is `prefix` or a path under it. `prefix` is in canonical form,
+/// without leading or trailing '/'.
+pub fn is_under_prefix(path: &str, prefix: &str) -> bool {
+    path.trim_start_matches('/')
+        .strip_prefix(prefix)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+pub const MAX_HEADER_VALUE_LEN: usize = 256;
+
+/// Whether `s` can be sent as an HTTP header value: non-empty, bounded, and
+/// free of control characters that would end the header early.
+pub fn is_valid_header_value(s: &str) -> bool {
+    !s.is_empty() && s.len() <= MAX_HEADER_VALUE_LEN && !s.chars().any(|c| c.is_control())
+}
+
+/// Response headers a file may override or add. Anything else is either set
+/// by the canister for every response or not worth the per-file heap cost.
+pub const ALLOWED_FILE_HEADERS: &[&str] = &[
+    "cache-control",
+    "content-disposition",
+    "content-language",
+    "x-robots-tag",
+    "x-content-version",
+];
+
+/// Lowercase the names of per-file response headers, or `None` if a name is
+/// not in `ALLOWED_FILE_HEADERS`, appears twice, or has an invalid value.
+pub fn normalize_file_headers(headers: Vec<(String, String)>) -> Option<Vec<(String, String)>> {
+    let mut normalized: Vec<(String, String)> = Vec::with_capacity(headers.len());
+    for (name, value) in headers {
+        let name = name.to_ascii_lowercase();
+        if !ALLOWED_FILE_HEADERS.contains(&name.as_str())
+            || !is_valid_header_value(&value)
+            || normalized.iter().any(|(existing, _)| existing == &name)
+        {
+            return None;
+        }
+        normalized.push((name, value));
+    }
+    Some(normalized)
+}
+
+/// Whether an `Accept-Encoding` header value admits the `token` coding, either
+/// by name or through `*`, with a non-zero quality.
+pub fn accepts_encoding(accept_encoding: &str, token: &str) -> bool {
+    let mut wildcard = false;
+    for entry in accept_encoding.split(',') {
+        let mut parts = entry.split(';');
+        let name = parts.next().unwrap_or("").trim();
+        let accepted = parts
+            .filter_map(|param| param.trim().strip_prefix("q="))
+            .all(|q| q.trim().parse::<f32>().is_ok_and(|q| q > 0.0));
+        if name.eq_ignore_ascii_case(token) {
+            return accepted;
+        }
+        if name == "*" {
+            wildcard = accepted;
+        }
+    }
+    wildcard
+}
+
+/// Whether `s` is a hex-encoded SHA-256 digest (64 hex digits, any case).
+pub fn is_sha256_hex(s: &str) -> bool {
+    s.len() == 64 && s.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
 pub fn get_content_type_for_path(path: &str) -> &'static str {
     let ext = path.rsplit('.').next().unwrap_or("");
     match ext {
@@ -138,6 +206,75 @@ mod tests {
         assert!(validate_file_path("/foo#bar").is_err());
     }
 
+    #[test]
+    fn is_valid_header_value_rejects_empty_long_and_control_chars() {
+        assert!(is_valid_header_value("image/webp"));
+        assert!(!is_valid_header_value(""));
+        assert!(!is_valid_header_value("text/html\r\nx-evil: 1"));
+        assert!(!is_valid_header_value(
+            &"a".repeat(MAX_HEADER_VALUE_LEN + 1)
+        ));
+    }
+
+    #[test]
+    fn normalize_file_headers_lowercases_allowed_names() {
+        let headers = vec![
+            ("Cache-Control".to_string(), "no-cache".to_string()),
+            ("X-Robots-Tag".to_string(), "noindex".to_string()),
+        ];
+        assert_eq!(
+            normalize_file_headers(headers),
+            Some(vec![
+                ("cache-control".to_string(), "no-cache".to_string()),
+                ("x-robots-tag".to_string(), "noindex".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn normalize_file_headers_rejects_unknown_duplicate_and_invalid() {
+        let header = |name: &str, value: &str| (name.to_string(), value.to_string());
+        assert_eq!(
+            normalize_file_headers(vec![header("x-frame-options", "ALLOW")]),
+            None
+        );
+        assert_eq!(
+            normalize_file_headers(vec![header("content-type", "text/html")]),
+            None
+        );
+        assert_eq!(
+            normalize_file_headers(vec![
+                header("cache-control", "a"),
+                header("Cache-Control", "b")
+            ]),
+            None
+        );
+        assert_eq!(
+            normalize_file_headers(vec![header("cache-control", "")]),
+            None
+        );
+    }
+
+    #[test]
+    fn accepts_encoding_honours_quality_and_wildcard() {
+        assert!(accepts_encoding("gzip, deflate, br", "br"));
+        assert!(accepts_encoding("GZIP", "gzip"));
+        assert!(!accepts_encoding("gzip", "br"));
+        assert!(!accepts_encoding("br;q=0, gzip", "br"));
+        assert!(accepts_encoding("br;q=0.5", "br"));
+        assert!(accepts_encoding("*", "br"));
+        assert!(!accepts_encoding("*, br;q=0", "br"));
+        assert!(!accepts_encoding("", "gzip"));
+    }
+
+    #[test]
+    fn is_sha256_hex_checks_length_and_digits() {
+        assert!(is_sha256_hex(&"ab".repeat(32)));
+        assert!(is_sha256_hex(&"AB".repeat(32)));
+        assert!(!is_sha256_hex(&"ab".repeat(31)));
+        assert!(!is_sha256_hex(&"zz".repeat(32)));
+    }
+
     #[test]
     fn validate_file_path_enforces_length_limit() {
         let ok_path = "/".to_string() + &"a".repeat(MAX_FILE_PATH_LEN - 1);
@@ -146,4 +283,13 @@ mod tests {
         let too_long = "/".to_string() + &"a".repeat(MAX_FILE_PATH_LEN);
         assert!(validate_file_path(&too_long).is_err());
     }
+
+    #[test]
+    fn is_under_prefix_matches_whole_segments() {
+        assert!(is_under_prefix("/images/cat.png", "images"));
+        assert!(is_under_prefix("images/2024/cat.png", "images/2024"));
+        assert!(is_under_prefix("/images", "images"));
+        assert!(!is_under_prefix("/images-old/cat.png", "images"));
+        assert!(!is_under_prefix("/docs/images/cat.png", "images"));
+    }
 }
@@ -1,15 +1,33 @@
 use crate::{generate_pocket_query_call, generate_pocket_update_call};
 
 use bity_ic_storage_canister_api::queries::{
-    get_storage_size, get_stored_files_size_bytes, http_request,
+    get_file_metadata, get_finalization_progress, get_storage_size, get_stored_files_size,
+    get_stored_files_size_bytes, get_upload_status, http_request, list_authorized_principals,
+    list_buckets, list_file_versions, list_files, list_principal_usage, list_roles, list_trash,
 };
 use bity_ic_storage_canister_api::updates::{
-    cancel_upload, finalize_upload, init_reupload, init_upload, remove_file, store_chunk,
+    add_authorized_principals, batch_upload, cancel_upload, copy_file, create_bucket,
+    create_download_token, create_upload_ticket, delete_bucket, finalize_upload, grant_role,
+    init_reupload, init_upload, move_file, purge_file, remove_authorized_principals, remove_file,
+    restore_file, restore_file_version, revoke_role, revoke_upload_ticket, set_file_expiry,
+    set_file_headers, set_file_metadata, set_file_visibility, set_principal_quota, store_chunk,
+    update_bucket,
 };
 
 generate_pocket_query_call!(get_storage_size);
 generate_pocket_query_call!(http_request);
 generate_pocket_query_call!(get_stored_files_size_bytes);
+generate_pocket_query_call!(get_finalization_progress);
+generate_pocket_query_call!(get_upload_status);
+generate_pocket_query_call!(list_files);
+generate_pocket_query_call!(get_file_metadata);
+generate_pocket_query_call!(list_file_versions);
+generate_pocket_query_call!(list_trash);
+generate_pocket_query_call!(list_principal_usage);
+generate_pocket_query_call!(list_buckets);
+generate_pocket_query_call!(list_roles);
+generate_pocket_query_call!(list_authorized_principals);
+generate_pocket_query_call!(get_stored_files_size);
 
 generate_pocket_update_call!(init_upload);
 generate_pocket_update_call!(init_reupload);
@@ -17,3 +35,24 @@ generate_pocket_update_call!(store_chunk);
 generate_pocket_update_call!(finalize_upload);
 generate_pocket_update_call!(cancel_upload);
 generate_pocket_update_call!(remove_file);
+generate_pocket_update_call!(move_file);
+generate_pocket_update_call!(copy_file);
+generate_pocket_update_call!(batch_upload);
+generate_pocket_update_call!(set_file_metadata);
+generate_pocket_update_call!(set_file_headers);
+generate_pocket_update_call!(restore_file_version);
+generate_pocket_update_call!(restore_file);
+generate_pocket_update_call!(purge_file);
+generate_pocket_update_call!(set_file_expiry);
+generate_pocket_update_call!(set_principal_quota);
+generate_pocket_update_call!(create_bucket);
+generate_pocket_update_call!(update_bucket);
+generate_pocket_update_call!(delete_bucket);
+generate_pocket_update_call!(grant_role);
+generate_pocket_update_call!(revoke_role);
+generate_pocket_update_call!(add_authorized_principals);
+generate_pocket_update_call!(remove_authorized_principals);
+generate_pocket_update_call!(set_file_visibility);
+generate_pocket_update_call!(create_download_token);
+generate_pocket_update_call!(create_upload_ticket);
+generate_pocket_update_call!(revoke_upload_ticket);
@@ -109,6 +109,8 @@ impl TestEnvBuilder {
             version: BuildVersion::min(),
             commit_hash: "commit_hash".to_string(),
             authorized_principals: vec![self.controller.clone()],
+            version_retention: None,
+            trash_retention_secs: None,
         });
 
         let storage_canister_id = match override_wasm {
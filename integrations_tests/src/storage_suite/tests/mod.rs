@@ -3,3 +3,27 @@ pub mod test_storage;
 pub mod test_storage_old_to_new_compat;
 pub mod test_storage_upgrade;
 pub mod test_remove_and_reupload;
+pub mod test_upload_staging;
+pub mod test_finalize_in_steps;
+pub mod test_chunk_hash_verification;
+pub mod test_upload_status;
+pub mod test_dedup;
+pub mod test_move_file;
+pub mod test_copy_file;
+pub mod test_batch_upload;
+pub mod test_partial_reupload;
+pub mod test_list_files;
+pub mod test_file_metadata;
+pub mod test_file_user_metadata;
+pub mod test_content_type;
+pub mod test_file_headers;
+pub mod test_encoded_variants;
+pub mod test_file_versions;
+pub mod test_trash;
+pub mod test_file_expiry;
+pub mod test_principal_quotas;
+pub mod test_buckets;
+pub mod test_roles;
+pub mod test_authorized_principals;
+pub mod test_private_files;
+pub mod test_upload_tickets;
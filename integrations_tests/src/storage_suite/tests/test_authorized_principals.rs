@@ -0,0 +1,98 @@
+//! Controllers manage the authorized principals, which `/metrics` reports,
+//! but can't remove the last admin.
+
+use crate::client::storage::{
+    add_authorized_principals, http_request, list_authorized_principals,
+    remove_authorized_principals,
+};
+use crate::storage_suite::setup::default_test_setup;
+use crate::storage_suite::setup::setup::TestEnv;
+use crate::utils::random_principal;
+use bity_ic_storage_canister_api::add_authorized_principals;
+use bity_ic_storage_canister_api::remove_authorized_principals;
+use bity_ic_storage_canister_api::remove_authorized_principals::RemoveAuthorizedPrincipalsError;
+use candid::Principal;
+use ic_http_certification::HttpRequest;
+use pocket_ic::PocketIc;
+
+fn metrics_body(pic: &PocketIc, canister: Principal) -> String {
+    let req = HttpRequest::get("/metrics").build();
+    let response = http_request(pic, Principal::anonymous(), canister, &req);
+    String::from_utf8(response.body().to_vec()).expect("metrics are not utf-8")
+}
+
+#[test]
+fn controllers_manage_authorized_principals() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        storage_canister_id,
+        controller,
+        ..
+    } = test_env;
+    let operator = random_principal();
+
+    add_authorized_principals(
+        pic,
+        controller,
+        storage_canister_id,
+        &add_authorized_principals::Args {
+            principals: vec![operator, operator],
+        },
+    );
+    let principals =
+        list_authorized_principals(pic, controller, storage_canister_id, &()).principals;
+    assert_eq!(
+        principals
+            .iter()
+            .filter(|principal| **principal == operator)
+            .count(),
+        1
+    );
+    assert!(metrics_body(pic, storage_canister_id).contains(&operator.to_text()));
+
+    let result = remove_authorized_principals(
+        pic,
+        controller,
+        storage_canister_id,
+        &remove_authorized_principals::Args {
+            principals: vec![random_principal()],
+        },
+    );
+    assert!(
+        matches!(
+            result,
+            Err(RemoveAuthorizedPrincipalsError::PrincipalNotFound(_))
+        ),
+        "expected PrincipalNotFound, got {result:?}"
+    );
+
+    let result = remove_authorized_principals(
+        pic,
+        controller,
+        storage_canister_id,
+        &remove_authorized_principals::Args {
+            principals: principals.clone(),
+        },
+    );
+    assert!(
+        matches!(result, Err(RemoveAuthorizedPrincipalsError::LastAdmin)),
+        "expected LastAdmin, got {result:?}"
+    );
+
+    remove_authorized_principals(
+        pic,
+        controller,
+        storage_canister_id,
+        &remove_authorized_principals::Args {
+            principals: vec![operator],
+        },
+    )
+    .expect("remove_authorized_principals failed");
+    assert!(
+        !list_authorized_principals(pic, controller, storage_canister_id, &())
+            .principals
+            .contains(&operator)
+    );
+    assert!(!metrics_body(pic, storage_canister_id).contains(&operator.to_text()));
+}
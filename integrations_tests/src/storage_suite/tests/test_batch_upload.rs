@@ -0,0 +1,119 @@
+//! `batch_upload` finalizes many small files in one call and reports a result
+//! per entry; a rejected entry leaves nothing behind and does not affect the
+//! others.
+
+use crate::client::storage::{batch_upload, get_upload_status, http_request};
+use crate::storage_suite::setup::default_test_setup;
+use crate::storage_suite::setup::setup::TestEnv;
+use bity_ic_storage_canister_api::batch_upload;
+use bity_ic_storage_canister_api::finalize_upload::FinalizeUploadError;
+use bity_ic_storage_canister_api::get_upload_status;
+use bity_ic_storage_canister_api::init_upload::InitUploadError;
+use ic_http_certification::{HttpRequest, StatusCode};
+use sha2::{Digest, Sha256};
+
+fn entry(
+    file_path: &str,
+    content: &[u8],
+    content_type: Option<&str>,
+) -> batch_upload::BatchUploadEntry {
+    batch_upload::BatchUploadEntry {
+        file_path: file_path.to_string(),
+        content: content.to_vec(),
+        expected_hash: hex::encode(Sha256::digest(content)),
+        content_type: content_type.map(str::to_string),
+    }
+}
+
+#[test]
+fn batch_upload_reports_per_entry_results() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        storage_canister_id,
+        controller,
+        ..
+    } = test_env;
+
+    let icon = b"<svg></svg>".to_vec();
+    let mut corrupted = entry("/icons/corrupted.svg", b"<svg/>", None);
+    corrupted.expected_hash = "00".repeat(32);
+
+    let resp = batch_upload(
+        pic,
+        controller,
+        storage_canister_id,
+        &(batch_upload::Args {
+            entries: vec![
+                entry("/icons/a.svg", &icon, None),
+                entry("/icons/b", b"plain", Some("text/plain")),
+                corrupted,
+                entry("/icons/a.svg", b"again", None),
+            ],
+            bucket: None,
+        }),
+    )
+    .expect("batch_upload failed");
+
+    assert_eq!(resp.results.len(), 4);
+    assert!(resp.results[0].is_ok(), "got {:?}", resp.results[0]);
+    assert!(resp.results[1].is_ok(), "got {:?}", resp.results[1]);
+    assert!(
+        matches!(
+            resp.results[2],
+            Err(batch_upload::BatchUploadEntryError::FinalizeUpload(
+                FinalizeUploadError::FileHashMismatch
+            ))
+        ),
+        "got {:?}",
+        resp.results[2]
+    );
+    assert!(
+        matches!(
+            resp.results[3],
+            Err(batch_upload::BatchUploadEntryError::InitUpload(
+                InitUploadError::FileAlreadyExists
+            ))
+        ),
+        "got {:?}",
+        resp.results[3]
+    );
+
+    // The rejected entry left no upload behind.
+    let status = get_upload_status(
+        pic,
+        controller,
+        storage_canister_id,
+        &(get_upload_status::Args {
+            file_path: "/icons/corrupted.svg".to_string(),
+        }),
+    );
+    assert!(
+        matches!(
+            status,
+            Err(get_upload_status::GetUploadStatusError::UploadNotFound)
+        ),
+        "got {status:?}"
+    );
+
+    let get = |path: &str| {
+        HttpRequest::get(path)
+            .with_headers(vec![(
+                "host".to_string(),
+                format!("{}.raw.icp0.io", storage_canister_id),
+            )])
+            .build()
+    };
+
+    let resp = http_request(pic, controller, storage_canister_id, &get("/icons/a.svg"));
+    assert_eq!(resp.status_code(), StatusCode::OK);
+    assert_eq!(resp.body(), &icon);
+
+    let resp = http_request(pic, controller, storage_canister_id, &get("/icons/b"));
+    assert_eq!(resp.status_code(), StatusCode::OK);
+    assert_eq!(resp.body(), b"plain");
+    assert!(resp
+        .headers()
+        .iter()
+        .any(|(name, value)| name.eq_ignore_ascii_case("content-type") && value == "text/plain"));
+}
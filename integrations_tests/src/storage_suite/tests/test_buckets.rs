@@ -0,0 +1,304 @@
+//! Buckets namespace files under `/<name>/`: their writers may upload, move
+//! and copy there only, within the bucket's byte limit, and a mapped host
+//! serves the bucket.
+
+use crate::client::storage::{
+    copy_file, create_bucket, delete_bucket, finalize_upload, http_request, init_upload,
+    list_buckets, move_file, store_chunk,
+};
+use crate::storage_suite::setup::default_test_setup;
+use crate::storage_suite::setup::setup::TestEnv;
+use crate::utils::{random_principal, upload_bytes};
+use bity_ic_storage_canister_api::copy_file;
+use bity_ic_storage_canister_api::create_bucket;
+use bity_ic_storage_canister_api::delete_bucket;
+use bity_ic_storage_canister_api::finalize_upload;
+use bity_ic_storage_canister_api::init_upload;
+use bity_ic_storage_canister_api::move_file;
+use bity_ic_storage_canister_api::store_chunk;
+use bity_ic_storage_canister_api::types::storage::{BucketAccessError, BucketConfig};
+use candid::{Nat, Principal};
+use ic_http_certification::{HttpRequest, HttpResponse, StatusCode};
+use pocket_ic::PocketIc;
+use sha2::{Digest, Sha256};
+
+fn upload_args(bucket: Option<&str>, content: &[u8]) -> init_upload::Args {
+    init_upload::Args {
+        file_path: "/cat.txt".to_string(),
+        file_hash: hex::encode(Sha256::digest(content)),
+        file_size: content.len() as u64,
+        chunk_size: None,
+        chunk_hashes: None,
+        metadata: None,
+        content_type: Some("text/plain".to_string()),
+        headers: None,
+        encoding: None,
+        expires_at: None,
+        visibility: None,
+        bucket: bucket.map(str::to_string),
+    }
+}
+
+fn get(pic: &PocketIc, canister: Principal, host: String, path: &str) -> HttpResponse<'static> {
+    let req = HttpRequest::get(path)
+        .with_headers(vec![("host".to_string(), host)])
+        .build();
+    http_request(pic, Principal::anonymous(), canister, &req)
+}
+
+#[test]
+fn bucket_writers_upload_within_their_bucket() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        storage_canister_id,
+        controller,
+        ..
+    } = test_env;
+    let writer = random_principal();
+
+    create_bucket(
+        pic,
+        controller,
+        storage_canister_id,
+        &create_bucket::Args {
+            name: "photos".to_string(),
+            config: BucketConfig {
+                writers: vec![writer],
+                max_bytes: Some(16),
+                headers: vec![("Cache-Control".to_string(), "max-age=60".to_string())],
+                hosts: vec!["Media.Example.com".to_string()],
+            },
+        },
+    )
+    .expect("create_bucket failed");
+
+    let content = b"meow meow!".to_vec();
+    let result = init_upload(
+        pic,
+        writer,
+        storage_canister_id,
+        &upload_args(None, &content),
+    );
+    assert!(
+        matches!(
+            result,
+            Err(init_upload::InitUploadError::BucketAccess(
+                BucketAccessError::NotAuthorized
+            ))
+        ),
+        "expected NotAuthorized outside the bucket, got {result:?}"
+    );
+
+    init_upload(
+        pic,
+        writer,
+        storage_canister_id,
+        &upload_args(Some("photos"), &content),
+    )
+    .expect("init_upload in the bucket failed");
+    store_chunk(
+        pic,
+        writer,
+        storage_canister_id,
+        &store_chunk::Args {
+            file_path: "/cat.txt".to_string(),
+            chunk_id: Nat::from(0u64),
+            chunk_data: content.clone(),
+            chunk_hash: None,
+            bucket: Some("photos".to_string()),
+        },
+    )
+    .expect("store_chunk failed");
+    finalize_upload(
+        pic,
+        writer,
+        storage_canister_id,
+        &finalize_upload::Args {
+            file_path: "/cat.txt".to_string(),
+            bucket: Some("photos".to_string()),
+        },
+    )
+    .expect("finalize_upload failed");
+
+    // 10 more bytes would take the bucket past its 16 bytes.
+    let mut second = upload_args(Some("photos"), &content);
+    second.file_path = "/dog.txt".to_string();
+    let result = init_upload(pic, writer, storage_canister_id, &second);
+    assert!(
+        matches!(result, Err(init_upload::InitUploadError::QuotaExceeded)),
+        "expected QuotaExceeded past the bucket limit, got {result:?}"
+    );
+
+    let raw = get(
+        pic,
+        storage_canister_id,
+        format!("{}.raw.icp0.io", storage_canister_id),
+        "/photos/cat.txt",
+    );
+    assert_eq!(raw.status_code(), StatusCode::OK);
+    assert_eq!(raw.body(), content.as_slice());
+    assert!(raw
+        .headers()
+        .iter()
+        .any(|(k, v)| k == "cache-control" && v == "max-age=60"));
+
+    let mapped = get(
+        pic,
+        storage_canister_id,
+        "media.example.com".to_string(),
+        "/cat.txt",
+    );
+    assert_eq!(mapped.status_code(), StatusCode::OK);
+    assert_eq!(mapped.body(), content.as_slice());
+
+    let buckets = list_buckets(pic, controller, storage_canister_id, &());
+    let photos = buckets
+        .buckets
+        .iter()
+        .find(|bucket| bucket.name == "photos")
+        .expect("bucket missing");
+    assert_eq!(photos.used_bytes, content.len() as u64);
+    assert_eq!(photos.file_count, 1);
+    assert_eq!(photos.config.hosts, vec!["media.example.com".to_string()]);
+
+    let result = delete_bucket(
+        pic,
+        controller,
+        storage_canister_id,
+        &delete_bucket::Args {
+            name: "photos".to_string(),
+        },
+    );
+    assert!(
+        matches!(
+            result,
+            Err(delete_bucket::DeleteBucketError::BucketNotEmpty)
+        ),
+        "expected BucketNotEmpty, got {result:?}"
+    );
+}
+
+#[test]
+fn moves_and_copies_stay_within_bucket_scope_and_limit() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        storage_canister_id,
+        controller,
+        ..
+    } = test_env;
+    let writer = random_principal();
+
+    upload_bytes(
+        pic,
+        controller,
+        storage_canister_id,
+        b"meow meow!",
+        "/loose.txt",
+    )
+    .expect("upload failed");
+    upload_bytes(
+        pic,
+        controller,
+        storage_canister_id,
+        b"woof",
+        "/photos/dog.txt",
+    )
+    .expect("upload failed");
+
+    let bucket_args = |name: &str| create_bucket::Args {
+        name: name.to_string(),
+        config: BucketConfig {
+            writers: vec![writer],
+            max_bytes: Some(16),
+            headers: vec![],
+            hosts: vec![],
+        },
+    };
+    let result = create_bucket(pic, controller, storage_canister_id, &bucket_args("photos"));
+    assert!(
+        matches!(result, Err(create_bucket::CreateBucketError::PrefixInUse)),
+        "expected PrefixInUse over existing files, got {result:?}"
+    );
+    create_bucket(pic, controller, storage_canister_id, &bucket_args("pics"))
+        .expect("create_bucket failed");
+
+    let copy = |to: &str, bucket: Option<&str>| copy_file::Args {
+        from: "/loose.txt".to_string(),
+        to: to.to_string(),
+        overwrite: false,
+        bucket: bucket.map(str::to_string),
+    };
+    let result = copy_file(
+        pic,
+        writer,
+        storage_canister_id,
+        &copy("/pics/cat.txt", None),
+    );
+    assert!(
+        matches!(
+            result,
+            Err(copy_file::CopyFileError::BucketAccess(
+                BucketAccessError::NotAuthorized
+            ))
+        ),
+        "expected NotAuthorized copying from outside the bucket, got {result:?}"
+    );
+
+    copy_file(
+        pic,
+        controller,
+        storage_canister_id,
+        &copy("/pics/cat.txt", None),
+    )
+    .expect("copy into the bucket failed");
+    // 10 more bytes would take the bucket past its 16 bytes.
+    let result = copy_file(
+        pic,
+        controller,
+        storage_canister_id,
+        &copy("/pics/cat-2.txt", None),
+    );
+    assert!(
+        matches!(result, Err(copy_file::CopyFileError::QuotaExceeded)),
+        "expected QuotaExceeded past the bucket limit, got {result:?}"
+    );
+    let result = move_file(
+        pic,
+        controller,
+        storage_canister_id,
+        &move_file::Args {
+            from: "/loose.txt".to_string(),
+            to: "/pics/cat-2.txt".to_string(),
+            overwrite: false,
+            bucket: None,
+        },
+    );
+    assert!(
+        matches!(result, Err(move_file::MoveFileError::QuotaExceeded)),
+        "expected QuotaExceeded past the bucket limit, got {result:?}"
+    );
+
+    // Within the bucket, a move doesn't grow it.
+    move_file(
+        pic,
+        writer,
+        storage_canister_id,
+        &move_file::Args {
+            from: "/cat.txt".to_string(),
+            to: "/kitten.txt".to_string(),
+            overwrite: false,
+            bucket: Some("pics".to_string()),
+        },
+    )
+    .expect("move within the bucket failed");
+    let raw = get(
+        pic,
+        storage_canister_id,
+        format!("{}.raw.icp0.io", storage_canister_id),
+        "/pics/kitten.txt",
+    );
+    assert_eq!(raw.status_code(), StatusCode::OK);
+    assert_eq!(raw.body(), b"meow meow!");
+}
@@ -0,0 +1,192 @@
+//! A chunk whose SHA-256 does not match the `chunk_hash` sent with it, or the
+//! manifest given at `init_upload`, is rejected on its own and can be resent
+//! without restarting the upload.
+
+use crate::client::storage::{finalize_upload, init_upload, store_chunk};
+use crate::storage_suite::setup::default_test_setup;
+use crate::storage_suite::setup::setup::TestEnv;
+use bity_ic_storage_canister_api::finalize_upload;
+use bity_ic_storage_canister_api::init_upload;
+use bity_ic_storage_canister_api::store_chunk;
+use candid::Nat;
+use sha2::{Digest, Sha256};
+
+fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+#[test]
+fn corrupted_chunk_is_rejected_and_can_be_resent() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        storage_canister_id,
+        controller,
+        ..
+    } = test_env;
+
+    let upload_path = "/verified.bin";
+    let chunk_size: u64 = 1024;
+    let content: Vec<u8> = (0..2 * chunk_size).map(|i| (i % 251) as u8).collect();
+    let chunks: Vec<&[u8]> = content.chunks(chunk_size as usize).collect();
+
+    init_upload(
+        pic,
+        controller,
+        storage_canister_id,
+        &(init_upload::Args {
+            file_path: upload_path.to_string(),
+            file_hash: sha256_hex(&content),
+            file_size: content.len() as u64,
+            chunk_size: Some(chunk_size),
+            chunk_hashes: None,
+            metadata: None,
+            content_type: None,
+            headers: None,
+            encoding: None,
+            expires_at: None,
+            visibility: None,
+            bucket: None,
+        }),
+    )
+    .expect("init_upload failed");
+
+    let mut corrupted = chunks[0].to_vec();
+    corrupted[0] ^= 0xff;
+    let result = store_chunk(
+        pic,
+        controller,
+        storage_canister_id,
+        &(store_chunk::Args {
+            file_path: upload_path.to_string(),
+            chunk_id: Nat::from(0u64),
+            chunk_data: corrupted,
+            chunk_hash: Some(sha256_hex(chunks[0])),
+            bucket: None,
+        }),
+    );
+    assert!(
+        matches!(result, Err(store_chunk::StoreChunkError::ChunkHashMismatch)),
+        "corrupted chunk should be rejected, got {result:?}"
+    );
+
+    for (index, chunk) in chunks.iter().enumerate() {
+        store_chunk(
+            pic,
+            controller,
+            storage_canister_id,
+            &(store_chunk::Args {
+                file_path: upload_path.to_string(),
+                chunk_id: Nat::from(index as u64),
+                chunk_data: chunk.to_vec(),
+                chunk_hash: Some(sha256_hex(chunk)),
+                bucket: None,
+            }),
+        )
+        .expect("store_chunk failed");
+    }
+
+    finalize_upload(
+        pic,
+        controller,
+        storage_canister_id,
+        &(finalize_upload::Args {
+            file_path: upload_path.to_string(),
+            bucket: None,
+        }),
+    )
+    .expect("finalize_upload failed");
+}
+
+#[test]
+fn init_upload_manifest_is_enforced() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        storage_canister_id,
+        controller,
+        ..
+    } = test_env;
+
+    let upload_path = "/manifest.bin";
+    let chunk_size: u64 = 1024;
+    let content: Vec<u8> = (0..2 * chunk_size).map(|i| (i % 239) as u8).collect();
+    let chunks: Vec<&[u8]> = content.chunks(chunk_size as usize).collect();
+    let manifest: Vec<String> = chunks.iter().map(|chunk| sha256_hex(chunk)).collect();
+
+    let args = |chunk_hashes: Vec<String>| init_upload::Args {
+        file_path: upload_path.to_string(),
+        file_hash: sha256_hex(&content),
+        file_size: content.len() as u64,
+        chunk_size: Some(chunk_size),
+        chunk_hashes: Some(chunk_hashes),
+        metadata: None,
+        content_type: None,
+        headers: None,
+        encoding: None,
+        expires_at: None,
+        visibility: None,
+        bucket: None,
+    };
+
+    // One hash per chunk, no more and no less.
+    let result = init_upload(
+        pic,
+        controller,
+        storage_canister_id,
+        &args(manifest[..1].to_vec()),
+    );
+    assert!(
+        matches!(
+            result,
+            Err(init_upload::InitUploadError::InvalidChunkHashes)
+        ),
+        "short manifest should be rejected, got {result:?}"
+    );
+
+    init_upload(pic, controller, storage_canister_id, &args(manifest)).expect("init_upload failed");
+
+    let result = store_chunk(
+        pic,
+        controller,
+        storage_canister_id,
+        &(store_chunk::Args {
+            file_path: upload_path.to_string(),
+            chunk_id: Nat::from(1u64),
+            chunk_data: chunks[0].to_vec(),
+            chunk_hash: None,
+            bucket: None,
+        }),
+    );
+    assert!(
+        matches!(result, Err(store_chunk::StoreChunkError::ChunkHashMismatch)),
+        "chunk not matching the manifest should be rejected, got {result:?}"
+    );
+
+    for (index, chunk) in chunks.iter().enumerate() {
+        store_chunk(
+            pic,
+            controller,
+            storage_canister_id,
+            &(store_chunk::Args {
+                file_path: upload_path.to_string(),
+                chunk_id: Nat::from(index as u64),
+                chunk_data: chunk.to_vec(),
+                chunk_hash: None,
+                bucket: None,
+            }),
+        )
+        .expect("store_chunk failed");
+    }
+
+    finalize_upload(
+        pic,
+        controller,
+        storage_canister_id,
+        &(finalize_upload::Args {
+            file_path: upload_path.to_string(),
+            bucket: None,
+        }),
+    )
+    .expect("finalize_upload failed");
+}
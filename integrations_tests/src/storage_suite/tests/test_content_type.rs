@@ -0,0 +1,107 @@
+//! A `content_type` given at upload time is served instead of the one guessed
+//! from the path extension, and survives a reupload that doesn't set one.
+
+use crate::client::storage::{http_request, init_reupload, init_upload};
+use crate::storage_suite::setup::default_test_setup;
+use crate::storage_suite::setup::setup::TestEnv;
+use crate::utils::send_and_finalize;
+use bity_ic_storage_canister_api::init_reupload;
+use bity_ic_storage_canister_api::init_upload;
+use candid::Principal;
+use ic_http_certification::{HttpRequest, StatusCode};
+use pocket_ic::PocketIc;
+use sha2::{Digest, Sha256};
+
+fn served_content_type(
+    pic: &PocketIc,
+    controller: Principal,
+    canister: Principal,
+    path: &str,
+) -> String {
+    let req = HttpRequest::get(path)
+        .with_headers(vec![(
+            "host".to_string(),
+            format!("{}.raw.icp0.io", canister),
+        )])
+        .build();
+    let resp = http_request(pic, controller, canister, &req);
+    assert_eq!(resp.status_code(), StatusCode::OK);
+    resp.headers()
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("content-type"))
+        .map(|(_, value)| value.clone())
+        .expect("no content-type header")
+}
+
+#[test]
+fn explicit_content_type_is_served_and_kept() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        storage_canister_id,
+        controller,
+        ..
+    } = test_env;
+
+    let path = "/thumbnails/42";
+    let content = b"RIFF....WEBP".to_vec();
+    let args = |content_type: &str| init_upload::Args {
+        file_path: path.to_string(),
+        file_hash: hex::encode(Sha256::digest(&content)),
+        file_size: content.len() as u64,
+        chunk_size: None,
+        chunk_hashes: None,
+        metadata: None,
+        content_type: Some(content_type.to_string()),
+        headers: None,
+        encoding: None,
+        expires_at: None,
+        visibility: None,
+        bucket: None,
+    };
+
+    let result = init_upload(
+        pic,
+        controller,
+        storage_canister_id,
+        &args("image/webp\r\nx-evil: 1"),
+    );
+    assert!(
+        matches!(
+            result,
+            Err(init_upload::InitUploadError::InvalidContentType)
+        ),
+        "expected InvalidContentType, got {result:?}"
+    );
+
+    init_upload(pic, controller, storage_canister_id, &args("image/webp"))
+        .expect("init_upload failed");
+    send_and_finalize(pic, controller, storage_canister_id, path, &content);
+    assert_eq!(
+        served_content_type(pic, controller, storage_canister_id, path),
+        "image/webp"
+    );
+
+    let updated = b"RIFF....WEBp".to_vec();
+    init_reupload(
+        pic,
+        controller,
+        storage_canister_id,
+        &init_reupload::Args {
+            file_path: path.to_string(),
+            file_hash: hex::encode(Sha256::digest(&updated)),
+            file_size: updated.len() as u64,
+            chunk_size: None,
+            changed_chunks: None,
+            content_type: None,
+            headers: None,
+            bucket: None,
+        },
+    )
+    .expect("init_reupload failed");
+    send_and_finalize(pic, controller, storage_canister_id, path, &updated);
+    assert_eq!(
+        served_content_type(pic, controller, storage_canister_id, path),
+        "image/webp"
+    );
+}
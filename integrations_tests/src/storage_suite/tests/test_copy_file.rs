@@ -0,0 +1,86 @@
+//! `copy_file` publishes a finalized file under a second path without the bytes
+//! leaving the canister; the copy shares the source's stored content.
+
+use crate::client::storage::{copy_file, get_stored_files_size, http_request, remove_file};
+use crate::storage_suite::setup::default_test_setup;
+use crate::storage_suite::setup::setup::TestEnv;
+use crate::utils::upload_bytes;
+use bity_ic_storage_canister_api::copy_file;
+use bity_ic_storage_canister_api::remove_file;
+use ic_http_certification::{HttpRequest, StatusCode};
+
+#[test]
+fn copy_file_shares_bytes_with_source() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        storage_canister_id,
+        controller,
+        ..
+    } = test_env;
+
+    let content = b"release 1.2.3".to_vec();
+    let size = content.len() as u64;
+    upload_bytes(
+        pic,
+        controller,
+        storage_canister_id,
+        &content,
+        "/v1.2.3/app.js",
+    )
+    .expect("upload failed");
+    upload_bytes(
+        pic,
+        controller,
+        storage_canister_id,
+        b"older",
+        "/latest/app.js",
+    )
+    .expect("upload failed");
+
+    let before = get_stored_files_size(pic, controller, storage_canister_id, &());
+
+    let args = |overwrite: bool| copy_file::Args {
+        from: "/v1.2.3/app.js".to_string(),
+        to: "/latest/app.js".to_string(),
+        overwrite,
+        bucket: None,
+    };
+
+    let result = copy_file(pic, controller, storage_canister_id, &args(false));
+    assert!(
+        matches!(result, Err(copy_file::CopyFileError::DestinationExists)),
+        "copying onto an existing file without overwrite should fail, got {result:?}"
+    );
+
+    copy_file(pic, controller, storage_canister_id, &args(true)).expect("copy_file failed");
+
+    // The replaced file's bytes are released and the copy adds none.
+    let after = get_stored_files_size(pic, controller, storage_canister_id, &());
+    assert_eq!(
+        after.logical_size_bytes,
+        before.logical_size_bytes - 5 + size
+    );
+    assert_eq!(after.physical_size_bytes, before.physical_size_bytes - 5);
+
+    remove_file(
+        pic,
+        controller,
+        storage_canister_id,
+        &(remove_file::Args {
+            file_path: "/v1.2.3/app.js".to_string(),
+            bucket: None,
+        }),
+    )
+    .expect("remove_file failed");
+
+    let req = HttpRequest::get("/latest/app.js")
+        .with_headers(vec![(
+            "host".to_string(),
+            format!("{}.raw.icp0.io", storage_canister_id),
+        )])
+        .build();
+    let resp = http_request(pic, controller, storage_canister_id, &req);
+    assert_eq!(resp.status_code(), StatusCode::OK);
+    assert_eq!(resp.body(), &content);
+}
@@ -0,0 +1,152 @@
+//! Files with identical content share one copy of the bytes. A second
+//! `init_upload` with a known hash completes without chunks, and the bytes are
+//! freed only once the last path referencing them is removed and purged.
+
+use crate::client::storage::{
+    get_stored_files_size, http_request, init_upload, purge_file, remove_file,
+};
+use crate::storage_suite::setup::default_test_setup;
+use crate::storage_suite::setup::setup::TestEnv;
+use crate::utils::upload_bytes;
+use bity_ic_storage_canister_api::init_upload;
+use bity_ic_storage_canister_api::purge_file;
+use bity_ic_storage_canister_api::remove_file;
+use ic_http_certification::{HttpRequest, StatusCode};
+use sha2::{Digest, Sha256};
+
+#[test]
+fn identical_files_share_bytes_until_last_reference_is_removed() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        storage_canister_id,
+        controller,
+        ..
+    } = test_env;
+
+    let content: Vec<u8> = (0..3000u32).map(|i| (i % 251) as u8).collect();
+    let size = content.len() as u64;
+
+    let initial = get_stored_files_size(pic, controller, storage_canister_id, &());
+
+    upload_bytes(pic, controller, storage_canister_id, &content, "/first.bin")
+        .expect("first upload failed");
+
+    let second = init_upload::Args {
+        file_path: "/second.bin".to_string(),
+        file_hash: hex::encode(Sha256::digest(&content)),
+        file_size: size,
+        chunk_size: None,
+        chunk_hashes: None,
+        metadata: None,
+        content_type: None,
+        headers: None,
+        encoding: None,
+        expires_at: None,
+        visibility: None,
+        bucket: None,
+    };
+
+    // Known content doesn't skip the checks of the upload's own arguments.
+    let result = init_upload(
+        pic,
+        controller,
+        storage_canister_id,
+        &init_upload::Args {
+            chunk_size: Some(0),
+            ..second.clone()
+        },
+    );
+    assert!(
+        matches!(result, Err(init_upload::InitUploadError::InvalidChunkSize)),
+        "expected InvalidChunkSize, got {result:?}"
+    );
+
+    let resp =
+        init_upload(pic, controller, storage_canister_id, &second).expect("init_upload failed");
+    assert_eq!(
+        resp.deduplicated,
+        Some(true),
+        "known content should be deduplicated"
+    );
+
+    let sizes = get_stored_files_size(pic, controller, storage_canister_id, &());
+    assert_eq!(
+        sizes.logical_size_bytes,
+        initial.logical_size_bytes + 2 * size
+    );
+    assert_eq!(
+        sizes.physical_size_bytes,
+        initial.physical_size_bytes + size
+    );
+
+    let get = |path: &str| {
+        HttpRequest::get(path)
+            .with_headers(vec![(
+                "host".to_string(),
+                format!("{}.raw.icp0.io", storage_canister_id),
+            )])
+            .build()
+    };
+
+    let resp = http_request(pic, controller, storage_canister_id, &get("/second.bin"));
+    assert_eq!(resp.status_code(), StatusCode::OK);
+    assert_eq!(resp.body(), &content);
+
+    remove_file(
+        pic,
+        controller,
+        storage_canister_id,
+        &(remove_file::Args {
+            file_path: "/first.bin".to_string(),
+            bucket: None,
+        }),
+    )
+    .expect("remove_file failed");
+
+    // The other path still holds a reference, so the bytes stay.
+    let resp = http_request(pic, controller, storage_canister_id, &get("/second.bin"));
+    assert_eq!(resp.status_code(), StatusCode::OK);
+    assert_eq!(resp.body(), &content);
+
+    let sizes = get_stored_files_size(pic, controller, storage_canister_id, &());
+    assert_eq!(sizes.logical_size_bytes, initial.logical_size_bytes + size);
+    assert_eq!(
+        sizes.physical_size_bytes,
+        initial.physical_size_bytes + size
+    );
+
+    remove_file(
+        pic,
+        controller,
+        storage_canister_id,
+        &(remove_file::Args {
+            file_path: "/second.bin".to_string(),
+            bucket: None,
+        }),
+    )
+    .expect("remove_file failed");
+
+    // Trashed files keep their bytes until purged.
+    let sizes = get_stored_files_size(pic, controller, storage_canister_id, &());
+    assert_eq!(sizes.logical_size_bytes, initial.logical_size_bytes);
+    assert_eq!(
+        sizes.physical_size_bytes,
+        initial.physical_size_bytes + size
+    );
+
+    for path in ["/first.bin", "/second.bin"] {
+        purge_file(
+            pic,
+            controller,
+            storage_canister_id,
+            &(purge_file::Args {
+                file_path: path.to_string(),
+            }),
+        )
+        .expect("purge_file failed");
+    }
+
+    let sizes = get_stored_files_size(pic, controller, storage_canister_id, &());
+    assert_eq!(sizes, initial);
+}
@@ -0,0 +1,132 @@
+//! Pre-compressed variants are uploaded next to a file and served in its place
+//! to clients whose `accept-encoding` admits them.
+
+use crate::client::storage::{finalize_upload, http_request, init_upload, store_chunk};
+use crate::storage_suite::setup::default_test_setup;
+use crate::storage_suite::setup::setup::TestEnv;
+use crate::utils::upload_bytes;
+use bity_ic_storage_canister_api::finalize_upload;
+use bity_ic_storage_canister_api::init_upload;
+use bity_ic_storage_canister_api::store_chunk;
+use bity_ic_storage_canister_api::types::storage::ContentEncoding;
+use candid::Nat;
+use ic_http_certification::{HttpRequest, StatusCode};
+use sha2::{Digest, Sha256};
+
+#[test]
+fn variant_is_served_when_accepted() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        storage_canister_id,
+        controller,
+        ..
+    } = test_env;
+
+    let original = b"console.log('hello');".to_vec();
+    let compressed = b"\x1f\x8b pretend gzip".to_vec();
+    upload_bytes(pic, controller, storage_canister_id, &original, "/app.js")
+        .expect("upload failed");
+
+    let args = |file_path: &str, encoding: ContentEncoding| init_upload::Args {
+        file_path: file_path.to_string(),
+        file_hash: hex::encode(Sha256::digest(&compressed)),
+        file_size: compressed.len() as u64,
+        chunk_size: None,
+        chunk_hashes: None,
+        metadata: None,
+        content_type: None,
+        headers: None,
+        encoding: Some(encoding),
+        expires_at: None,
+        visibility: None,
+        bucket: None,
+    };
+
+    let result = init_upload(
+        pic,
+        controller,
+        storage_canister_id,
+        &args("/app.js.gz", ContentEncoding::Brotli),
+    );
+    assert!(
+        matches!(result, Err(init_upload::InitUploadError::InvalidEncoding)),
+        "the suffix must match the encoding, got {result:?}"
+    );
+
+    let result = init_upload(
+        pic,
+        controller,
+        storage_canister_id,
+        &args("/missing.js.gz", ContentEncoding::Gzip),
+    );
+    assert!(
+        matches!(result, Err(init_upload::InitUploadError::BaseFileNotFound)),
+        "a variant needs the file it encodes, got {result:?}"
+    );
+
+    init_upload(
+        pic,
+        controller,
+        storage_canister_id,
+        &args("/app.js.gz", ContentEncoding::Gzip),
+    )
+    .expect("init_upload failed");
+    store_chunk(
+        pic,
+        controller,
+        storage_canister_id,
+        &store_chunk::Args {
+            file_path: "/app.js.gz".to_string(),
+            chunk_id: Nat::from(0u64),
+            chunk_data: compressed.clone(),
+            chunk_hash: None,
+            bucket: None,
+        },
+    )
+    .expect("store_chunk failed");
+    finalize_upload(
+        pic,
+        controller,
+        storage_canister_id,
+        &finalize_upload::Args {
+            file_path: "/app.js.gz".to_string(),
+            bucket: None,
+        },
+    )
+    .expect("finalize_upload failed");
+
+    let get = |accept_encoding: &str| {
+        let req = HttpRequest::get("/app.js")
+            .with_headers(vec![
+                (
+                    "host".to_string(),
+                    format!("{}.raw.icp0.io", storage_canister_id),
+                ),
+                ("accept-encoding".to_string(), accept_encoding.to_string()),
+            ])
+            .build();
+        http_request(pic, controller, storage_canister_id, &req)
+    };
+    let header = |resp: &ic_http_certification::HttpResponse, name: &str| {
+        resp.headers()
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.clone())
+    };
+
+    let resp = get("br, gzip");
+    assert_eq!(resp.status_code(), StatusCode::OK);
+    assert_eq!(resp.body(), &compressed);
+    assert_eq!(header(&resp, "content-encoding").as_deref(), Some("gzip"));
+    assert_eq!(header(&resp, "vary").as_deref(), Some("accept-encoding"));
+    assert_eq!(
+        header(&resp, "content-type").as_deref(),
+        Some("application/javascript")
+    );
+
+    let resp = get("identity");
+    assert_eq!(resp.body(), &original);
+    assert_eq!(header(&resp, "content-encoding"), None);
+    assert_eq!(header(&resp, "vary").as_deref(), Some("accept-encoding"));
+}
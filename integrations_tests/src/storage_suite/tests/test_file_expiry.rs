@@ -0,0 +1,227 @@
+//! A file with an `expires_at` stops being served once it passes, along with
+//! its variants, and is deleted by the hourly sweep.
+
+use std::time::Duration;
+
+use crate::client::storage::{
+    finalize_upload, get_file_metadata, http_request, init_upload, set_file_expiry, store_chunk,
+};
+use crate::storage_suite::setup::default_test_setup;
+use crate::storage_suite::setup::setup::TestEnv;
+use crate::utils::{send_and_finalize, tick_n_blocks};
+use bity_ic_storage_canister_api::finalize_upload;
+use bity_ic_storage_canister_api::get_file_metadata;
+use bity_ic_storage_canister_api::init_upload;
+use bity_ic_storage_canister_api::set_file_expiry;
+use bity_ic_storage_canister_api::store_chunk;
+use bity_ic_storage_canister_api::types::storage::ContentEncoding;
+use candid::{Nat, Principal};
+use ic_http_certification::{HttpRequest, StatusCode};
+use pocket_ic::PocketIc;
+use sha2::{Digest, Sha256};
+
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+fn raw_status(
+    pic: &PocketIc,
+    controller: Principal,
+    canister: Principal,
+    path: &str,
+) -> StatusCode {
+    let req = HttpRequest::get(path)
+        .with_headers(vec![(
+            "host".to_string(),
+            format!("{}.raw.icp0.io", canister),
+        )])
+        .build();
+    http_request(pic, controller, canister, &req).status_code()
+}
+
+#[test]
+fn expired_file_is_hidden_then_deleted() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        storage_canister_id,
+        controller,
+        ..
+    } = test_env;
+
+    let path = "/exports/report.csv";
+    let content = b"id,total\n1,42\n".to_vec();
+    let now = pic.get_time().as_nanos_since_unix_epoch();
+    let args = |expires_at: u64| init_upload::Args {
+        file_path: path.to_string(),
+        file_hash: hex::encode(Sha256::digest(&content)),
+        file_size: content.len() as u64,
+        chunk_size: None,
+        chunk_hashes: None,
+        metadata: None,
+        content_type: None,
+        headers: None,
+        encoding: None,
+        expires_at: Some(expires_at),
+        visibility: None,
+        bucket: None,
+    };
+
+    let result = init_upload(pic, controller, storage_canister_id, &args(now - 1));
+    assert!(
+        matches!(result, Err(init_upload::InitUploadError::InvalidExpiresAt)),
+        "expected InvalidExpiresAt, got {result:?}"
+    );
+
+    let expires_at = now + DAY.as_nanos() as u64;
+    init_upload(pic, controller, storage_canister_id, &args(expires_at))
+        .expect("init_upload failed");
+    store_chunk(
+        pic,
+        controller,
+        storage_canister_id,
+        &(store_chunk::Args {
+            file_path: path.to_string(),
+            chunk_id: Nat::from(0u64),
+            chunk_data: content.clone(),
+            chunk_hash: None,
+            bucket: None,
+        }),
+    )
+    .expect("store_chunk failed");
+    finalize_upload(
+        pic,
+        controller,
+        storage_canister_id,
+        &(finalize_upload::Args {
+            file_path: path.to_string(),
+            bucket: None,
+        }),
+    )
+    .expect("finalize_upload failed");
+
+    let metadata = get_file_metadata(
+        pic,
+        controller,
+        storage_canister_id,
+        &get_file_metadata::Args {
+            file_path: path.to_string(),
+        },
+    )
+    .expect("get_file_metadata failed");
+    assert_eq!(metadata.expires_at, Some(expires_at));
+    assert_eq!(
+        raw_status(pic, controller, storage_canister_id, path),
+        StatusCode::OK
+    );
+
+    // Pushing the expiry back keeps the file past its original date.
+    set_file_expiry(
+        pic,
+        controller,
+        storage_canister_id,
+        &set_file_expiry::Args {
+            file_path: path.to_string(),
+            expires_at: Some(now + (3 * DAY).as_nanos() as u64),
+        },
+    )
+    .expect("set_file_expiry failed");
+    pic.advance_time(2 * DAY);
+    tick_n_blocks(pic, 10);
+    assert_eq!(
+        raw_status(pic, controller, storage_canister_id, path),
+        StatusCode::OK
+    );
+
+    pic.advance_time(DAY + Duration::from_secs(60));
+    pic.tick();
+    assert_eq!(
+        raw_status(pic, controller, storage_canister_id, path),
+        StatusCode::NOT_FOUND
+    );
+
+    pic.advance_time(Duration::from_secs(60 * 60));
+    tick_n_blocks(pic, 10);
+    let result = get_file_metadata(
+        pic,
+        controller,
+        storage_canister_id,
+        &get_file_metadata::Args {
+            file_path: path.to_string(),
+        },
+    );
+    assert!(
+        matches!(
+            result,
+            Err(get_file_metadata::GetFileMetadataError::FileNotFound)
+        ),
+        "expected FileNotFound, got {result:?}"
+    );
+}
+
+#[test]
+fn variant_expires_with_its_file() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        storage_canister_id,
+        controller,
+        ..
+    } = test_env;
+
+    let original = b"console.log('bye');".to_vec();
+    let compressed = b"\x1f\x8b pretend gzip".to_vec();
+    let now = pic.get_time().as_nanos_since_unix_epoch();
+    let args = |path: &str, content: &[u8], encoding, expires_at| init_upload::Args {
+        file_path: path.to_string(),
+        file_hash: hex::encode(Sha256::digest(content)),
+        file_size: content.len() as u64,
+        chunk_size: None,
+        chunk_hashes: None,
+        metadata: None,
+        content_type: None,
+        headers: None,
+        encoding,
+        expires_at,
+        visibility: None,
+        bucket: None,
+    };
+
+    let expires_at = now + Duration::from_secs(60).as_nanos() as u64;
+    init_upload(
+        pic,
+        controller,
+        storage_canister_id,
+        &args("/app.js", &original, None, Some(expires_at)),
+    )
+    .expect("init_upload failed");
+    send_and_finalize(pic, controller, storage_canister_id, "/app.js", &original);
+    init_upload(
+        pic,
+        controller,
+        storage_canister_id,
+        &args("/app.js.gz", &compressed, Some(ContentEncoding::Gzip), None),
+    )
+    .expect("init_upload of the variant failed");
+    send_and_finalize(
+        pic,
+        controller,
+        storage_canister_id,
+        "/app.js.gz",
+        &compressed,
+    );
+    assert_eq!(
+        raw_status(pic, controller, storage_canister_id, "/app.js.gz"),
+        StatusCode::OK
+    );
+
+    // Past the file's expiry but before the hourly sweep deletes it.
+    pic.advance_time(Duration::from_secs(120));
+    pic.tick();
+    assert_eq!(
+        raw_status(pic, controller, storage_canister_id, "/app.js"),
+        StatusCode::NOT_FOUND
+    );
+    assert_eq!(
+        raw_status(pic, controller, storage_canister_id, "/app.js.gz"),
+        StatusCode::NOT_FOUND
+    );
+}
@@ -0,0 +1,79 @@
+//! Per-file response headers replace the defaults on the raw path, and only
+//! allow-listed headers are accepted.
+
+use crate::client::storage::{http_request, set_file_headers};
+use crate::storage_suite::setup::default_test_setup;
+use crate::storage_suite::setup::setup::TestEnv;
+use crate::utils::upload_bytes;
+use bity_ic_storage_canister_api::set_file_headers;
+use ic_http_certification::{HttpRequest, StatusCode};
+
+#[test]
+fn file_headers_override_defaults() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        storage_canister_id,
+        controller,
+        ..
+    } = test_env;
+
+    let path = "/reports/latest.pdf";
+    upload_bytes(pic, controller, storage_canister_id, b"%PDF-1.7", path).expect("upload failed");
+
+    let args = |headers: &[(&str, &str)]| set_file_headers::Args {
+        file_path: path.to_string(),
+        headers: headers
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect(),
+    };
+
+    let result = set_file_headers(
+        pic,
+        controller,
+        storage_canister_id,
+        &args(&[("x-frame-options", "SAMEORIGIN")]),
+    );
+    assert!(
+        matches!(
+            result,
+            Err(set_file_headers::SetFileHeadersError::InvalidHeaders)
+        ),
+        "a header outside the allow-list should be rejected, got {result:?}"
+    );
+
+    set_file_headers(
+        pic,
+        controller,
+        storage_canister_id,
+        &args(&[
+            ("Cache-Control", "public, no-cache"),
+            ("Content-Disposition", "attachment; filename=\"report.pdf\""),
+        ]),
+    )
+    .expect("set_file_headers failed");
+
+    let req = HttpRequest::get(path)
+        .with_headers(vec![(
+            "host".to_string(),
+            format!("{}.raw.icp0.io", storage_canister_id),
+        )])
+        .build();
+    let resp = http_request(pic, controller, storage_canister_id, &req);
+    assert_eq!(resp.status_code(), StatusCode::OK);
+
+    let values = |name: &str| -> Vec<String> {
+        resp.headers()
+            .iter()
+            .filter(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.clone())
+            .collect()
+    };
+    assert_eq!(values("cache-control"), ["public, no-cache"]);
+    assert_eq!(
+        values("content-disposition"),
+        ["attachment; filename=\"report.pdf\""]
+    );
+    assert_eq!(values("content-type"), ["application/pdf"]);
+}
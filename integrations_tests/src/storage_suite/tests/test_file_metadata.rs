@@ -0,0 +1,62 @@
+//! `get_file_metadata` reports a stored file's metadata without an HTTP request.
+
+use crate::client::storage::get_file_metadata;
+use crate::storage_suite::setup::default_test_setup;
+use crate::storage_suite::setup::setup::TestEnv;
+use crate::utils::upload_bytes;
+use bity_ic_storage_canister_api::get_file_metadata;
+use bity_ic_storage_canister_api::types::storage::UploadState;
+use sha2::{Digest, Sha256};
+
+#[test]
+fn get_file_metadata_reports_stored_file() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        storage_canister_id,
+        controller,
+        ..
+    } = test_env;
+
+    let content = b"<svg></svg>".to_vec();
+    upload_bytes(
+        pic,
+        controller,
+        storage_canister_id,
+        &content,
+        "/icons/app.svg",
+    )
+    .expect("upload failed");
+
+    let args = |file_path: &str| get_file_metadata::Args {
+        file_path: file_path.to_string(),
+    };
+
+    let metadata = get_file_metadata(
+        pic,
+        controller,
+        storage_canister_id,
+        &args("/icons/app.svg"),
+    )
+    .expect("get_file_metadata failed");
+    assert_eq!(metadata.file_path, "/icons/app.svg");
+    assert_eq!(metadata.file_size, content.len() as u64);
+    assert_eq!(metadata.file_hash, hex::encode(Sha256::digest(&content)));
+    assert_eq!(metadata.content_type, "image/svg+xml");
+    assert_eq!(metadata.state, UploadState::Finalized);
+    assert!(metadata.finalized_timestamp.unwrap() >= metadata.init_timestamp);
+
+    let missing = get_file_metadata(
+        pic,
+        controller,
+        storage_canister_id,
+        &args("/icons/none.svg"),
+    );
+    assert!(
+        matches!(
+            missing,
+            Err(get_file_metadata::GetFileMetadataError::FileNotFound)
+        ),
+        "expected FileNotFound, got {missing:?}"
+    );
+}
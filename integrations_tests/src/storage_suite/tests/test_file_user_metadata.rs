@@ -0,0 +1,102 @@
+//! Application key/value metadata is stored with a file, can be replaced with
+//! `set_file_metadata` and is returned by the metadata queries.
+
+use crate::client::storage::{get_file_metadata, list_files, set_file_metadata};
+use crate::storage_suite::setup::default_test_setup;
+use crate::storage_suite::setup::setup::TestEnv;
+use crate::utils::upload_bytes;
+use bity_ic_storage_canister_api::get_file_metadata;
+use bity_ic_storage_canister_api::list_files;
+use bity_ic_storage_canister_api::set_file_metadata;
+use std::collections::BTreeMap;
+
+#[test]
+fn set_file_metadata_is_returned_by_queries() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        storage_canister_id,
+        controller,
+        ..
+    } = test_env;
+
+    upload_bytes(
+        pic,
+        controller,
+        storage_canister_id,
+        b"jpeg bytes",
+        "/photos/cat.jpg",
+    )
+    .expect("upload failed");
+
+    let metadata = BTreeMap::from([
+        ("alt".to_string(), "A cat on a sofa".to_string()),
+        ("license".to_string(), "CC-BY-4.0".to_string()),
+    ]);
+    let args = |file_path: &str, metadata: BTreeMap<String, String>| set_file_metadata::Args {
+        file_path: file_path.to_string(),
+        metadata,
+    };
+
+    set_file_metadata(
+        pic,
+        controller,
+        storage_canister_id,
+        &args("/photos/cat.jpg", metadata.clone()),
+    )
+    .expect("set_file_metadata failed");
+
+    let stored = get_file_metadata(
+        pic,
+        controller,
+        storage_canister_id,
+        &get_file_metadata::Args {
+            file_path: "/photos/cat.jpg".to_string(),
+        },
+    )
+    .expect("get_file_metadata failed");
+    assert_eq!(stored.metadata, metadata);
+
+    let listed = list_files(
+        pic,
+        controller,
+        storage_canister_id,
+        &list_files::Args {
+            prefix: Some("/photos/".to_string()),
+            cursor: None,
+            limit: None,
+        },
+    );
+    assert_eq!(listed.files[0].metadata, metadata);
+
+    let too_many: BTreeMap<String, String> = (0..17)
+        .map(|i| (format!("tag{i}"), String::new()))
+        .collect();
+    let result = set_file_metadata(
+        pic,
+        controller,
+        storage_canister_id,
+        &args("/photos/cat.jpg", too_many),
+    );
+    assert!(
+        matches!(
+            result,
+            Err(set_file_metadata::SetFileMetadataError::InvalidMetadata)
+        ),
+        "expected InvalidMetadata, got {result:?}"
+    );
+
+    let result = set_file_metadata(
+        pic,
+        controller,
+        storage_canister_id,
+        &args("/photos/dog.jpg", metadata),
+    );
+    assert!(
+        matches!(
+            result,
+            Err(set_file_metadata::SetFileMetadataError::FileNotFound)
+        ),
+        "expected FileNotFound, got {result:?}"
+    );
+}
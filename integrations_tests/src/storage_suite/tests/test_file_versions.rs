@@ -0,0 +1,212 @@
+//! A reupload keeps the replaced bytes as a numbered version that can be
+//! fetched at `<path>?version=<n>` and restored.
+
+use crate::client::storage::{
+    http_request, init_reupload, init_upload, list_file_versions, restore_file_version,
+};
+use crate::storage_suite::setup::default_test_setup;
+use crate::storage_suite::setup::setup::TestEnv;
+use crate::utils::send_and_finalize;
+use bity_ic_storage_canister_api::init_reupload;
+use bity_ic_storage_canister_api::init_upload;
+use bity_ic_storage_canister_api::list_file_versions;
+use bity_ic_storage_canister_api::restore_file_version;
+use candid::Principal;
+use ic_http_certification::{HttpRequest, StatusCode};
+use pocket_ic::PocketIc;
+use sha2::{Digest, Sha256};
+
+fn fetch_raw(pic: &PocketIc, controller: Principal, canister: Principal, url: &str) -> Vec<u8> {
+    let req = HttpRequest::get(url)
+        .with_headers(vec![(
+            "host".to_string(),
+            format!("{}.raw.icp0.io", canister),
+        )])
+        .build();
+    let resp = http_request(pic, controller, canister, &req);
+    assert_eq!(resp.status_code(), StatusCode::OK, "GET {url}");
+    resp.body().to_vec()
+}
+
+fn reupload(
+    pic: &mut PocketIc,
+    controller: Principal,
+    canister: Principal,
+    path: &str,
+    content: &[u8],
+) {
+    init_reupload(
+        pic,
+        controller,
+        canister,
+        &init_reupload::Args {
+            file_path: path.to_string(),
+            file_hash: hex::encode(Sha256::digest(content)),
+            file_size: content.len() as u64,
+            chunk_size: None,
+            changed_chunks: None,
+            content_type: None,
+            headers: None,
+            bucket: None,
+        },
+    )
+    .expect("init_reupload failed");
+    send_and_finalize(pic, controller, canister, path, content);
+}
+
+#[test]
+fn reupload_keeps_versions_that_can_be_served_and_restored() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        storage_canister_id,
+        controller,
+        ..
+    } = test_env;
+
+    let path = "/app/index.js";
+    let v1 = b"console.log('v1');".to_vec();
+    let v2 = b"console.log('v2 - broken');".to_vec();
+
+    init_upload(
+        pic,
+        controller,
+        storage_canister_id,
+        &init_upload::Args {
+            file_path: path.to_string(),
+            file_hash: hex::encode(Sha256::digest(&v1)),
+            file_size: v1.len() as u64,
+            chunk_size: None,
+            chunk_hashes: None,
+            metadata: None,
+            content_type: None,
+            headers: None,
+            encoding: None,
+            expires_at: None,
+            visibility: None,
+            bucket: None,
+        },
+    )
+    .expect("init_upload failed");
+    send_and_finalize(pic, controller, storage_canister_id, path, &v1);
+    reupload(pic, controller, storage_canister_id, path, &v2);
+
+    let versions = list_file_versions(
+        pic,
+        controller,
+        storage_canister_id,
+        &list_file_versions::Args {
+            file_path: path.to_string(),
+        },
+    )
+    .expect("list_file_versions failed");
+    assert_eq!(versions.current_version, 2);
+    assert_eq!(versions.versions.len(), 1);
+    assert_eq!(versions.versions[0].version, 1);
+    assert_eq!(
+        versions.versions[0].file_hash,
+        hex::encode(Sha256::digest(&v1))
+    );
+
+    assert_eq!(fetch_raw(pic, controller, storage_canister_id, path), v2);
+    assert_eq!(
+        fetch_raw(
+            pic,
+            controller,
+            storage_canister_id,
+            &format!("{path}?version=1")
+        ),
+        v1
+    );
+
+    let result = restore_file_version(
+        pic,
+        controller,
+        storage_canister_id,
+        &restore_file_version::Args {
+            file_path: path.to_string(),
+            version: 7,
+        },
+    );
+    assert!(
+        matches!(
+            result,
+            Err(restore_file_version::RestoreFileVersionError::VersionNotFound)
+        ),
+        "expected VersionNotFound, got {result:?}"
+    );
+
+    let restored = restore_file_version(
+        pic,
+        controller,
+        storage_canister_id,
+        &restore_file_version::Args {
+            file_path: path.to_string(),
+            version: 1,
+        },
+    )
+    .expect("restore_file_version failed");
+    assert_eq!(restored.version, 3);
+    assert_eq!(fetch_raw(pic, controller, storage_canister_id, path), v1);
+    assert_eq!(
+        fetch_raw(
+            pic,
+            controller,
+            storage_canister_id,
+            &format!("{path}?version=2")
+        ),
+        v2
+    );
+}
+
+#[test]
+fn history_is_pruned_to_the_retention_count() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        storage_canister_id,
+        controller,
+        ..
+    } = test_env;
+
+    let path = "/config.json";
+    let content = |n: u32| format!("{{\"build\":{n}}}").into_bytes();
+
+    init_upload(
+        pic,
+        controller,
+        storage_canister_id,
+        &init_upload::Args {
+            file_path: path.to_string(),
+            file_hash: hex::encode(Sha256::digest(content(1))),
+            file_size: content(1).len() as u64,
+            chunk_size: None,
+            chunk_hashes: None,
+            metadata: None,
+            content_type: None,
+            headers: None,
+            encoding: None,
+            expires_at: None,
+            visibility: None,
+            bucket: None,
+        },
+    )
+    .expect("init_upload failed");
+    send_and_finalize(pic, controller, storage_canister_id, path, &content(1));
+    for n in 2..=6 {
+        reupload(pic, controller, storage_canister_id, path, &content(n));
+    }
+
+    let versions = list_file_versions(
+        pic,
+        controller,
+        storage_canister_id,
+        &list_file_versions::Args {
+            file_path: path.to_string(),
+        },
+    )
+    .expect("list_file_versions failed");
+    assert_eq!(versions.current_version, 6);
+    let kept: Vec<u64> = versions.versions.iter().map(|v| v.version).collect();
+    assert_eq!(kept, vec![5, 4, 3]);
+}
@@ -0,0 +1,325 @@
+//! Finalizing a file larger than one step's budget returns `Finalizing` right
+//! away and completes over later timer-driven messages, observable through
+//! `get_finalization_progress`.
+
+use crate::client::storage::{
+    finalize_upload, get_finalization_progress, http_request, init_upload, store_chunk,
+};
+use crate::storage_suite::setup::default_test_setup;
+use crate::storage_suite::setup::setup::TestEnv;
+use crate::utils::tick_n_blocks;
+use bity_ic_storage_canister_api::finalize_upload;
+use bity_ic_storage_canister_api::get_finalization_progress;
+use bity_ic_storage_canister_api::init_upload;
+use bity_ic_storage_canister_api::store_chunk;
+use bity_ic_storage_canister_api::types::storage::UploadState;
+use candid::Nat;
+use ic_http_certification::{HttpRequest, StatusCode};
+use sha2::{Digest, Sha256};
+
+#[test]
+fn large_file_finalizes_over_several_messages() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        storage_canister_id,
+        controller,
+        ..
+    } = test_env;
+
+    let upload_path = "/large.bin";
+    let chunk_size: u64 = 1024 * 1024;
+    let num_chunks: u64 = 20;
+    let content: Vec<u8> = (0..num_chunks * chunk_size)
+        .map(|i| (i % 253) as u8)
+        .collect();
+
+    let mut hasher = Sha256::new();
+    hasher.update(&content);
+    let file_hash = format!("{:x}", hasher.finalize());
+
+    init_upload(
+        pic,
+        controller,
+        storage_canister_id,
+        &(init_upload::Args {
+            file_path: upload_path.to_string(),
+            file_hash,
+            file_size: content.len() as u64,
+            chunk_size: Some(chunk_size),
+            chunk_hashes: None,
+            metadata: None,
+            content_type: None,
+            headers: None,
+            encoding: None,
+            expires_at: None,
+            visibility: None,
+            bucket: None,
+        }),
+    )
+    .expect("init_upload failed");
+
+    for (index, chunk) in content.chunks(chunk_size as usize).enumerate() {
+        store_chunk(
+            pic,
+            controller,
+            storage_canister_id,
+            &(store_chunk::Args {
+                file_path: upload_path.to_string(),
+                chunk_id: Nat::from(index as u64),
+                chunk_data: chunk.to_vec(),
+                chunk_hash: None,
+                bucket: None,
+            }),
+        )
+        .expect("store_chunk failed");
+    }
+
+    let resp = finalize_upload(
+        pic,
+        controller,
+        storage_canister_id,
+        &(finalize_upload::Args {
+            file_path: upload_path.to_string(),
+            bucket: None,
+        }),
+    )
+    .expect("finalize_upload failed");
+    assert_eq!(resp.state, Some(UploadState::Finalizing));
+
+    let progress = get_finalization_progress(
+        pic,
+        controller,
+        storage_canister_id,
+        &(get_finalization_progress::Args {
+            file_path: upload_path.to_string(),
+        }),
+    )
+    .expect("get_finalization_progress failed");
+    assert_eq!(progress.state, UploadState::Finalizing);
+    assert_eq!(progress.hashed_size, content.len() as u64);
+    assert!(progress.stored_size < content.len() as u64);
+
+    tick_n_blocks(pic, 5);
+
+    let progress = get_finalization_progress(
+        pic,
+        controller,
+        storage_canister_id,
+        &(get_finalization_progress::Args {
+            file_path: upload_path.to_string(),
+        }),
+    )
+    .expect("get_finalization_progress failed");
+    assert_eq!(progress.state, UploadState::Finalized);
+    assert_eq!(progress.stored_size, content.len() as u64);
+
+    let req = HttpRequest::get(upload_path)
+        .with_headers(vec![(
+            "host".to_string(),
+            format!("{}.raw.icp0.io", storage_canister_id),
+        )])
+        .build();
+    let resp = http_request(pic, controller, storage_canister_id, &req);
+    assert_eq!(resp.status_code(), StatusCode::OK);
+    assert_eq!(resp.body(), &content);
+}
+
+#[test]
+fn out_of_order_chunks_are_hashed_before_finalize() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        storage_canister_id,
+        controller,
+        ..
+    } = test_env;
+
+    let upload_path = "/reversed.bin";
+    let chunk_size: u64 = 1024 * 1024;
+    let num_chunks: u64 = 40;
+    let content: Vec<u8> = (0..num_chunks * chunk_size)
+        .map(|i| (i % 247) as u8)
+        .collect();
+
+    init_upload(
+        pic,
+        controller,
+        storage_canister_id,
+        &(init_upload::Args {
+            file_path: upload_path.to_string(),
+            file_hash: hex::encode(Sha256::digest(&content)),
+            file_size: content.len() as u64,
+            chunk_size: Some(chunk_size),
+            chunk_hashes: None,
+            metadata: None,
+            content_type: None,
+            headers: None,
+            encoding: None,
+            expires_at: None,
+            visibility: None,
+            bucket: None,
+        }),
+    )
+    .expect("init_upload failed");
+
+    for (index, chunk) in content.chunks(chunk_size as usize).enumerate().rev() {
+        store_chunk(
+            pic,
+            controller,
+            storage_canister_id,
+            &(store_chunk::Args {
+                file_path: upload_path.to_string(),
+                chunk_id: Nat::from(index as u64),
+                chunk_data: chunk.to_vec(),
+                chunk_hash: None,
+                bucket: None,
+            }),
+        )
+        .expect("store_chunk failed");
+    }
+
+    // The last chunk only hashed a bounded slice; the catch-up job does the rest.
+    tick_n_blocks(pic, 5);
+
+    let progress = get_finalization_progress(
+        pic,
+        controller,
+        storage_canister_id,
+        &(get_finalization_progress::Args {
+            file_path: upload_path.to_string(),
+        }),
+    )
+    .expect("get_finalization_progress failed");
+    assert_eq!(progress.state, UploadState::InProgress);
+    assert_eq!(progress.hashed_size, content.len() as u64);
+
+    finalize_upload(
+        pic,
+        controller,
+        storage_canister_id,
+        &(finalize_upload::Args {
+            file_path: upload_path.to_string(),
+            bucket: None,
+        }),
+    )
+    .expect("finalize_upload failed");
+    tick_n_blocks(pic, 5);
+
+    let req = HttpRequest::get(upload_path)
+        .with_headers(vec![(
+            "host".to_string(),
+            format!("{}.raw.icp0.io", storage_canister_id),
+        )])
+        .build();
+    let resp = http_request(pic, controller, storage_canister_id, &req);
+    assert_eq!(resp.status_code(), StatusCode::OK);
+    assert_eq!(resp.body(), &content);
+}
+
+#[test]
+fn failed_finalization_step_is_reported_as_failed() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        storage_canister_id,
+        controller,
+        ..
+    } = test_env;
+
+    let upload_path = "/mismatch.bin";
+    let chunk_size: u64 = 1024 * 1024;
+    let num_chunks: u64 = 40;
+    let content: Vec<u8> = (0..num_chunks * chunk_size)
+        .map(|i| (i % 251) as u8)
+        .collect();
+
+    init_upload(
+        pic,
+        controller,
+        storage_canister_id,
+        &(init_upload::Args {
+            file_path: upload_path.to_string(),
+            file_hash: "0".repeat(64),
+            file_size: content.len() as u64,
+            chunk_size: Some(chunk_size),
+            chunk_hashes: None,
+            metadata: None,
+            content_type: None,
+            headers: None,
+            encoding: None,
+            expires_at: None,
+            visibility: None,
+            bucket: None,
+        }),
+    )
+    .expect("init_upload failed");
+
+    // In reverse order, so nothing can be hashed until the last chunk arrives.
+    for (index, chunk) in content.chunks(chunk_size as usize).enumerate().rev() {
+        store_chunk(
+            pic,
+            controller,
+            storage_canister_id,
+            &(store_chunk::Args {
+                file_path: upload_path.to_string(),
+                chunk_id: Nat::from(index as u64),
+                chunk_data: chunk.to_vec(),
+                chunk_hash: None,
+                bucket: None,
+            }),
+        )
+        .expect("store_chunk failed");
+    }
+
+    let resp = finalize_upload(
+        pic,
+        controller,
+        storage_canister_id,
+        &(finalize_upload::Args {
+            file_path: upload_path.to_string(),
+            bucket: None,
+        }),
+    )
+    .expect("finalize_upload failed");
+    assert_eq!(resp.state, Some(UploadState::Finalizing));
+
+    tick_n_blocks(pic, 5);
+
+    let progress = get_finalization_progress(
+        pic,
+        controller,
+        storage_canister_id,
+        &(get_finalization_progress::Args {
+            file_path: upload_path.to_string(),
+        }),
+    )
+    .expect("get_finalization_progress failed");
+    assert_eq!(
+        progress.state,
+        UploadState::Failed("FileHashMismatch".to_string())
+    );
+    assert_eq!(progress.stored_size, 0);
+
+    // The path can be uploaded again.
+    init_upload(
+        pic,
+        controller,
+        storage_canister_id,
+        &(init_upload::Args {
+            file_path: upload_path.to_string(),
+            file_hash: "0".repeat(64),
+            file_size: content.len() as u64,
+            chunk_size: Some(chunk_size),
+            chunk_hashes: None,
+            metadata: None,
+            content_type: None,
+            headers: None,
+            encoding: None,
+            expires_at: None,
+            visibility: None,
+            bucket: None,
+        }),
+    )
+    .expect("init_upload after a failed finalization failed");
+}
@@ -67,6 +67,14 @@ fn abandoned_upload_init_timestamp_survives_upgrade() {
             file_hash: "00".repeat(32),
             file_size,
             chunk_size: None,
+            chunk_hashes: None,
+            metadata: None,
+            content_type: None,
+            headers: None,
+            encoding: None,
+            expires_at: None,
+            visibility: None,
+            bucket: None,
         }),
     );
     assert!(
@@ -83,6 +91,8 @@ fn abandoned_upload_init_timestamp_survives_upgrade() {
             file_path: file_path.clone(),
             chunk_id: Nat::from(0u64),
             chunk_data: chunk,
+            chunk_hash: None,
+            bucket: None,
         }),
     );
     assert!(
@@ -101,6 +111,8 @@ fn abandoned_upload_init_timestamp_survives_upgrade() {
         Args::Upgrade(UpgradeArgs {
             version: BuildVersion::min(),
             commit_hash: "gc-upgrade-test".to_string(),
+            version_retention: None,
+            trash_retention_secs: None,
         }),
         controller,
     );
@@ -117,6 +129,14 @@ fn abandoned_upload_init_timestamp_survives_upgrade() {
             file_hash: "00".repeat(32),
             file_size,
             chunk_size: None,
+            chunk_hashes: None,
+            metadata: None,
+            content_type: None,
+            headers: None,
+            encoding: None,
+            expires_at: None,
+            visibility: None,
+            bucket: None,
         }),
     );
     assert!(
@@ -143,6 +163,14 @@ fn abandoned_upload_init_timestamp_survives_upgrade() {
             file_hash: "00".repeat(32),
             file_size,
             chunk_size: None,
+            chunk_hashes: None,
+            metadata: None,
+            content_type: None,
+            headers: None,
+            encoding: None,
+            expires_at: None,
+            visibility: None,
+            bucket: None,
         }),
     );
     assert!(
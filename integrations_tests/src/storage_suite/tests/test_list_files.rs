@@ -0,0 +1,62 @@
+//! `list_files` pages through file metadata in path order, filtered by prefix.
+
+use crate::client::storage::list_files;
+use crate::storage_suite::setup::default_test_setup;
+use crate::storage_suite::setup::setup::TestEnv;
+use crate::utils::upload_bytes;
+use bity_ic_storage_canister_api::list_files;
+use bity_ic_storage_canister_api::types::storage::UploadState;
+
+#[test]
+fn list_files_pages_through_prefix() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        storage_canister_id,
+        controller,
+        ..
+    } = test_env;
+
+    for (i, path) in ["/docs/c.txt", "/docs/a.txt", "/img/logo.png", "/docs/b.txt"]
+        .iter()
+        .enumerate()
+    {
+        upload_bytes(
+            pic,
+            controller,
+            storage_canister_id,
+            format!("file {i}").as_bytes(),
+            path,
+        )
+        .expect("upload failed");
+    }
+
+    let page = |cursor: Option<String>| {
+        list_files(
+            pic,
+            controller,
+            storage_canister_id,
+            &list_files::Args {
+                prefix: Some("/docs/".to_string()),
+                cursor,
+                limit: Some(2),
+            },
+        )
+    };
+
+    let first = page(None);
+    let paths: Vec<&str> = first.files.iter().map(|f| f.file_path.as_str()).collect();
+    assert_eq!(paths, ["/docs/a.txt", "/docs/b.txt"]);
+    assert_eq!(first.next_cursor.as_deref(), Some("/docs/b.txt"));
+
+    let file = &first.files[0];
+    assert_eq!(file.file_size, 6);
+    assert_eq!(file.content_type, "text/plain");
+    assert_eq!(file.state, UploadState::Finalized);
+    assert!(file.finalized_timestamp.is_some());
+
+    let second = page(first.next_cursor);
+    let paths: Vec<&str> = second.files.iter().map(|f| f.file_path.as_str()).collect();
+    assert_eq!(paths, ["/docs/c.txt"]);
+    assert_eq!(second.next_cursor, None);
+}
@@ -0,0 +1,83 @@
+//! `move_file` re-keys a finalized file in one call: the old path disappears,
+//! the new one serves the same bytes, and an existing destination is only
+//! replaced when `overwrite` is set.
+
+use crate::client::storage::{http_request, move_file};
+use crate::storage_suite::setup::default_test_setup;
+use crate::storage_suite::setup::setup::TestEnv;
+use crate::utils::upload_bytes;
+use bity_ic_storage_canister_api::move_file;
+use ic_http_certification::{HttpRequest, StatusCode};
+
+#[test]
+fn move_file_respects_overwrite_policy() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        storage_canister_id,
+        controller,
+        ..
+    } = test_env;
+
+    let source = b"source content".to_vec();
+    let existing = b"existing content".to_vec();
+    upload_bytes(pic, controller, storage_canister_id, &source, "/from.txt")
+        .expect("upload failed");
+    upload_bytes(pic, controller, storage_canister_id, &existing, "/to.txt")
+        .expect("upload failed");
+
+    let get = |path: &str| {
+        HttpRequest::get(path)
+            .with_headers(vec![(
+                "host".to_string(),
+                format!("{}.raw.icp0.io", storage_canister_id),
+            )])
+            .build()
+    };
+
+    let args = |from: &str, overwrite: bool| move_file::Args {
+        from: from.to_string(),
+        to: "/to.txt".to_string(),
+        overwrite,
+        bucket: None,
+    };
+
+    let result = move_file(
+        pic,
+        controller,
+        storage_canister_id,
+        &args("/missing.txt", true),
+    );
+    assert!(
+        matches!(result, Err(move_file::MoveFileError::FileNotFound)),
+        "moving a missing file should fail, got {result:?}"
+    );
+
+    let result = move_file(
+        pic,
+        controller,
+        storage_canister_id,
+        &args("/from.txt", false),
+    );
+    assert!(
+        matches!(result, Err(move_file::MoveFileError::DestinationExists)),
+        "moving onto an existing file without overwrite should fail, got {result:?}"
+    );
+    let resp = http_request(pic, controller, storage_canister_id, &get("/to.txt"));
+    assert_eq!(resp.body(), &existing);
+
+    move_file(
+        pic,
+        controller,
+        storage_canister_id,
+        &args("/from.txt", true),
+    )
+    .expect("move_file failed");
+
+    let resp = http_request(pic, controller, storage_canister_id, &get("/from.txt"));
+    assert_eq!(resp.status_code(), StatusCode::NOT_FOUND);
+
+    let resp = http_request(pic, controller, storage_canister_id, &get("/to.txt"));
+    assert_eq!(resp.status_code(), StatusCode::OK);
+    assert_eq!(resp.body(), &source);
+}
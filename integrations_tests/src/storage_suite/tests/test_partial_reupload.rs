@@ -0,0 +1,124 @@
+//! A partial reupload sends only the changed chunks; the others are copied from
+//! the file's current bytes before the new hash is verified.
+
+use crate::client::storage::{finalize_upload, http_request, init_reupload, store_chunk};
+use crate::storage_suite::setup::default_test_setup;
+use crate::storage_suite::setup::setup::TestEnv;
+use crate::utils::upload_bytes;
+use bity_ic_storage_canister_api::finalize_upload;
+use bity_ic_storage_canister_api::init_reupload;
+use bity_ic_storage_canister_api::store_chunk;
+use candid::Nat;
+use ic_http_certification::{HttpRequest, StatusCode};
+use sha2::{Digest, Sha256};
+
+#[test]
+fn partial_reupload_replaces_only_changed_chunks() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        storage_canister_id,
+        controller,
+        ..
+    } = test_env;
+
+    let path = "/media/clip.bin";
+    let original = b"0123456789abcdefghijOLD TAIL...".to_vec();
+    upload_bytes(pic, controller, storage_canister_id, &original, path).expect("upload failed");
+
+    // Same first two 10-byte chunks, longer third chunk.
+    let mut updated = original[..20].to_vec();
+    updated.extend_from_slice(b"NEW, LONGER TAIL");
+    let args = |changed_chunks: Vec<u64>, file_size: u64| init_reupload::Args {
+        file_path: path.to_string(),
+        file_hash: hex::encode(Sha256::digest(&updated)),
+        file_size,
+        chunk_size: Some(10),
+        changed_chunks: Some(changed_chunks),
+        content_type: None,
+        headers: None,
+        bucket: None,
+    };
+
+    let result = init_reupload(pic, controller, storage_canister_id, &args(vec![4], 36));
+    assert!(
+        matches!(
+            result,
+            Err(init_reupload::InitReuploadError::InvalidChangedChunks)
+        ),
+        "an out-of-range chunk index should be rejected, got {result:?}"
+    );
+
+    // Chunk 3 would be taken from beyond the current 31 bytes.
+    let result = init_reupload(pic, controller, storage_canister_id, &args(vec![2], 36));
+    assert!(
+        matches!(
+            result,
+            Err(init_reupload::InitReuploadError::InvalidChangedChunks)
+        ),
+        "reusing bytes past the current end should be rejected, got {result:?}"
+    );
+
+    init_reupload(
+        pic,
+        controller,
+        storage_canister_id,
+        &args(vec![2, 3], updated.len() as u64),
+    )
+    .expect("init_reupload failed");
+
+    let chunk_args = |index: usize| store_chunk::Args {
+        file_path: path.to_string(),
+        chunk_id: Nat::from(index),
+        chunk_data: updated[index * 10..updated.len().min((index + 1) * 10)].to_vec(),
+        chunk_hash: None,
+        bucket: None,
+    };
+
+    let result = store_chunk(pic, controller, storage_canister_id, &chunk_args(0));
+    assert!(
+        result.is_err(),
+        "a reused chunk should not be accepted again, got {result:?}"
+    );
+
+    // Only the last chunk may be shorter than `chunk_size`, and only by the file's end.
+    for (index, len) in [(2, 5), (3, 10)] {
+        let result = store_chunk(
+            pic,
+            controller,
+            storage_canister_id,
+            &store_chunk::Args {
+                chunk_data: vec![b'x'; len],
+                ..chunk_args(index)
+            },
+        );
+        assert!(
+            matches!(result, Err(store_chunk::StoreChunkError::InvalidChunkSize)),
+            "a {len}-byte chunk {index} should be rejected, got {result:?}"
+        );
+    }
+
+    store_chunk(pic, controller, storage_canister_id, &chunk_args(2)).expect("store_chunk failed");
+    store_chunk(pic, controller, storage_canister_id, &chunk_args(3)).expect("store_chunk failed");
+
+    finalize_upload(
+        pic,
+        controller,
+        storage_canister_id,
+        &(finalize_upload::Args {
+            file_path: path.to_string(),
+            bucket: None,
+        }),
+    )
+    .expect("finalize_upload failed");
+
+    let req = HttpRequest::get(path)
+        .with_headers(vec![(
+            "host".to_string(),
+            format!("{}.raw.icp0.io", storage_canister_id),
+        )])
+        .build();
+    let resp = http_request(pic, controller, storage_canister_id, &req);
+    assert_eq!(resp.status_code(), StatusCode::OK);
+    assert_eq!(resp.body(), &updated);
+}
@@ -0,0 +1,262 @@
+//! A principal's byte and file-count quota is enforced at `init_upload`,
+//! `init_reupload` and whenever a copy or restore makes a file the caller's,
+//! and its usage is reported by `list_principal_usage`. Trashed files count
+//! until they are purged.
+
+use crate::client::storage::{
+    copy_file, grant_role, init_reupload, init_upload, list_principal_usage, purge_file,
+    remove_file, restore_file, set_principal_quota,
+};
+use crate::storage_suite::setup::default_test_setup;
+use crate::storage_suite::setup::setup::TestEnv;
+use crate::utils::{random_principal, upload_bytes};
+use bity_ic_storage_canister_api::copy_file;
+use bity_ic_storage_canister_api::init_reupload;
+use bity_ic_storage_canister_api::init_upload;
+use bity_ic_storage_canister_api::purge_file;
+use bity_ic_storage_canister_api::remove_file;
+use bity_ic_storage_canister_api::restore_file;
+use bity_ic_storage_canister_api::set_principal_quota;
+use bity_ic_storage_canister_api::types::storage::{PrincipalQuota, Role, RoleAssignment};
+use sha2::{Digest, Sha256};
+
+#[test]
+fn uploads_beyond_the_quota_are_rejected() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        storage_canister_id,
+        controller,
+        ..
+    } = test_env;
+
+    set_principal_quota(
+        pic,
+        controller,
+        storage_canister_id,
+        &set_principal_quota::Args {
+            principal: controller,
+            quota: PrincipalQuota {
+                max_bytes: Some(100),
+                max_files: Some(1),
+            },
+        },
+    );
+
+    upload_bytes(pic, controller, storage_canister_id, &[1u8; 60], "/a.bin")
+        .expect("upload within quota failed");
+
+    let second = vec![2u8; 10];
+    let result = init_upload(
+        pic,
+        controller,
+        storage_canister_id,
+        &init_upload::Args {
+            file_path: "/b.bin".to_string(),
+            file_hash: hex::encode(Sha256::digest(&second)),
+            file_size: second.len() as u64,
+            chunk_size: None,
+            chunk_hashes: None,
+            metadata: None,
+            content_type: None,
+            headers: None,
+            encoding: None,
+            expires_at: None,
+            visibility: None,
+            bucket: None,
+        },
+    );
+    assert!(
+        matches!(result, Err(init_upload::InitUploadError::QuotaExceeded)),
+        "expected QuotaExceeded for a second file, got {result:?}"
+    );
+
+    let reupload = |size: u64| init_reupload::Args {
+        file_path: "/a.bin".to_string(),
+        file_hash: hex::encode(Sha256::digest(vec![3u8; size as usize])),
+        file_size: size,
+        chunk_size: None,
+        changed_chunks: Some(vec![0]),
+        content_type: None,
+        headers: None,
+        bucket: None,
+    };
+    // The reupload replaces the caller's own 60 bytes, so only the new size counts.
+    let result = init_reupload(pic, controller, storage_canister_id, &reupload(101));
+    assert!(
+        matches!(result, Err(init_reupload::InitReuploadError::QuotaExceeded)),
+        "expected QuotaExceeded for an oversized reupload, got {result:?}"
+    );
+    init_reupload(pic, controller, storage_canister_id, &reupload(90))
+        .expect("reupload within quota failed");
+
+    let usage = list_principal_usage(pic, controller, storage_canister_id, &());
+    let entry = usage
+        .principals
+        .iter()
+        .find(|entry| entry.principal == controller)
+        .expect("controller usage missing");
+    assert_eq!(entry.used_bytes, 60);
+    assert_eq!(entry.file_count, 1);
+    assert_eq!(entry.quota.max_bytes, Some(100));
+}
+
+#[test]
+fn copies_are_charged_to_the_caller() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        storage_canister_id,
+        controller,
+        ..
+    } = test_env;
+    let uploader = random_principal();
+
+    grant_role(
+        pic,
+        controller,
+        storage_canister_id,
+        &RoleAssignment {
+            principal: uploader,
+            role: Role::Uploader,
+            path_prefix: None,
+        },
+    )
+    .expect("grant_role failed");
+    set_principal_quota(
+        pic,
+        controller,
+        storage_canister_id,
+        &set_principal_quota::Args {
+            principal: uploader,
+            quota: PrincipalQuota {
+                max_bytes: Some(100),
+                max_files: None,
+            },
+        },
+    );
+
+    upload_bytes(
+        pic,
+        controller,
+        storage_canister_id,
+        &[1u8; 60],
+        "/shared.bin",
+    )
+    .expect("upload by the controller failed");
+
+    let copy = |to: &str| copy_file::Args {
+        from: "/shared.bin".to_string(),
+        to: to.to_string(),
+        overwrite: false,
+        bucket: None,
+    };
+    copy_file(pic, uploader, storage_canister_id, &copy("/mine.bin"))
+        .expect("copy within quota failed");
+    let result = copy_file(pic, uploader, storage_canister_id, &copy("/mine-too.bin"));
+    assert!(
+        matches!(result, Err(copy_file::CopyFileError::QuotaExceeded)),
+        "expected QuotaExceeded for a second copy, got {result:?}"
+    );
+
+    let usage = list_principal_usage(pic, controller, storage_canister_id, &());
+    let entry = usage
+        .principals
+        .iter()
+        .find(|entry| entry.principal == uploader)
+        .expect("uploader usage missing");
+    assert_eq!(entry.used_bytes, 60);
+    assert_eq!(entry.file_count, 1);
+}
+
+#[test]
+fn trashed_files_count_until_purged() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        storage_canister_id,
+        controller,
+        ..
+    } = test_env;
+
+    set_principal_quota(
+        pic,
+        controller,
+        storage_canister_id,
+        &set_principal_quota::Args {
+            principal: controller,
+            quota: PrincipalQuota {
+                max_bytes: None,
+                max_files: Some(1),
+            },
+        },
+    );
+
+    upload_bytes(pic, controller, storage_canister_id, &[1u8; 60], "/a.bin")
+        .expect("upload within quota failed");
+    let remove = |pic: &mut pocket_ic::PocketIc| {
+        remove_file(
+            pic,
+            controller,
+            storage_canister_id,
+            &remove_file::Args {
+                file_path: "/a.bin".to_string(),
+                bucket: None,
+            },
+        )
+        .expect("remove_file failed");
+    };
+    remove(pic);
+
+    let second = vec![2u8; 10];
+    let init_second = init_upload::Args {
+        file_path: "/b.bin".to_string(),
+        file_hash: hex::encode(Sha256::digest(&second)),
+        file_size: second.len() as u64,
+        chunk_size: None,
+        chunk_hashes: None,
+        metadata: None,
+        content_type: None,
+        headers: None,
+        encoding: None,
+        expires_at: None,
+        visibility: None,
+        bucket: None,
+    };
+    let result = init_upload(pic, controller, storage_canister_id, &init_second);
+    assert!(
+        matches!(result, Err(init_upload::InitUploadError::QuotaExceeded)),
+        "expected the trashed file to still count, got {result:?}"
+    );
+    let usage = list_principal_usage(pic, controller, storage_canister_id, &());
+    let entry = usage
+        .principals
+        .iter()
+        .find(|usage| usage.principal == controller)
+        .expect("controller has no usage entry");
+    assert_eq!((entry.used_bytes, entry.file_count), (60, 1));
+
+    // The file is already charged to its owner, so restoring it adds nothing.
+    restore_file(
+        pic,
+        controller,
+        storage_canister_id,
+        &restore_file::Args {
+            file_path: "/a.bin".to_string(),
+        },
+    )
+    .expect("restore_file failed");
+    remove(pic);
+
+    purge_file(
+        pic,
+        controller,
+        storage_canister_id,
+        &purge_file::Args {
+            file_path: "/a.bin".to_string(),
+        },
+    )
+    .expect("purge_file failed");
+    init_upload(pic, controller, storage_canister_id, &init_second)
+        .expect("upload after purging failed");
+}
@@ -0,0 +1,517 @@
+//! Private files are served from the raw domain only to requests carrying an
+//! unexpired token signed by `create_download_token` for their path, and only
+//! callers with a role on their path see their metadata.
+
+use std::time::Duration;
+
+use crate::client::storage::{
+    copy_file, create_download_token, get_file_metadata, get_finalization_progress,
+    get_upload_status, grant_role, http_request, init_reupload, init_upload, list_files, move_file,
+    set_file_visibility,
+};
+use crate::storage_suite::setup::default_test_setup;
+use crate::storage_suite::setup::setup::TestEnv;
+use crate::utils::{random_principal, send_and_finalize, upload_bytes};
+use bity_ic_storage_canister_api::copy_file;
+use bity_ic_storage_canister_api::create_download_token;
+use bity_ic_storage_canister_api::get_file_metadata;
+use bity_ic_storage_canister_api::get_finalization_progress;
+use bity_ic_storage_canister_api::get_upload_status;
+use bity_ic_storage_canister_api::init_reupload;
+use bity_ic_storage_canister_api::init_upload;
+use bity_ic_storage_canister_api::list_files;
+use bity_ic_storage_canister_api::move_file;
+use bity_ic_storage_canister_api::set_file_visibility;
+use bity_ic_storage_canister_api::types::storage::{
+    ContentEncoding, Role, RoleAssignment, Visibility,
+};
+use candid::Principal;
+use ic_http_certification::{HttpRequest, HttpResponse, StatusCode};
+use pocket_ic::PocketIc;
+use sha2::{Digest, Sha256};
+
+fn raw_get(pic: &PocketIc, canister: Principal, url: &str) -> HttpResponse<'static> {
+    let req = HttpRequest::get(url)
+        .with_headers(vec![(
+            "host".to_string(),
+            format!("{}.raw.icp0.io", canister),
+        )])
+        .build();
+    http_request(pic, Principal::anonymous(), canister, &req)
+}
+
+#[test]
+fn private_file_needs_a_valid_token() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        storage_canister_id,
+        controller,
+        ..
+    } = test_env;
+
+    let content = b"quarterly numbers".to_vec();
+    upload_bytes(
+        pic,
+        controller,
+        storage_canister_id,
+        &content,
+        "/private/report.txt",
+    )
+    .expect("upload failed");
+    upload_bytes(
+        pic,
+        controller,
+        storage_canister_id,
+        b"other",
+        "/private/other.txt",
+    )
+    .expect("upload failed");
+    for path in ["/private/report.txt", "/private/other.txt"] {
+        set_file_visibility(
+            pic,
+            controller,
+            storage_canister_id,
+            &set_file_visibility::Args {
+                file_path: path.to_string(),
+                visibility: Visibility::Private,
+            },
+        )
+        .expect("set_file_visibility failed");
+    }
+
+    let metadata = get_file_metadata(
+        pic,
+        controller,
+        storage_canister_id,
+        &get_file_metadata::Args {
+            file_path: "/private/report.txt".to_string(),
+        },
+    )
+    .expect("get_file_metadata failed");
+    assert_eq!(metadata.visibility, Visibility::Private);
+    assert_eq!(
+        raw_get(pic, storage_canister_id, "/private/report.txt").status_code(),
+        StatusCode::FORBIDDEN
+    );
+
+    let now = pic.get_time().as_nanos_since_unix_epoch();
+    let result = create_download_token(
+        pic,
+        controller,
+        storage_canister_id,
+        &create_download_token::Args {
+            file_path: "/private/report.txt".to_string(),
+            expires_at: now,
+        },
+    );
+    assert!(
+        matches!(
+            result,
+            Err(create_download_token::CreateDownloadTokenError::InvalidExpiresAt)
+        ),
+        "expected InvalidExpiresAt, got {result:?}"
+    );
+
+    let query = create_download_token(
+        pic,
+        controller,
+        storage_canister_id,
+        &create_download_token::Args {
+            file_path: "/private/report.txt".to_string(),
+            expires_at: now + Duration::from_secs(60 * 60).as_nanos() as u64,
+        },
+    )
+    .expect("create_download_token failed")
+    .query;
+
+    let response = raw_get(
+        pic,
+        storage_canister_id,
+        &format!("/private/report.txt?{}", query),
+    );
+    assert_eq!(response.status_code(), StatusCode::OK);
+    assert_eq!(response.body(), content.as_slice());
+
+    // The token is bound to its path and can't be altered.
+    assert_eq!(
+        raw_get(
+            pic,
+            storage_canister_id,
+            &format!("/private/other.txt?{}", query)
+        )
+        .status_code(),
+        StatusCode::FORBIDDEN
+    );
+    let tampered = query.replacen("token=", "token=0", 1);
+    assert_eq!(
+        raw_get(
+            pic,
+            storage_canister_id,
+            &format!("/private/report.txt?{}", tampered)
+        )
+        .status_code(),
+        StatusCode::FORBIDDEN
+    );
+
+    pic.advance_time(Duration::from_secs(2 * 60 * 60));
+    pic.tick();
+    assert_eq!(
+        raw_get(
+            pic,
+            storage_canister_id,
+            &format!("/private/report.txt?{}", query)
+        )
+        .status_code(),
+        StatusCode::FORBIDDEN
+    );
+
+    set_file_visibility(
+        pic,
+        controller,
+        storage_canister_id,
+        &set_file_visibility::Args {
+            file_path: "/private/report.txt".to_string(),
+            visibility: Visibility::Public,
+        },
+    )
+    .expect("set_file_visibility failed");
+    assert_eq!(
+        raw_get(pic, storage_canister_id, "/private/report.txt").status_code(),
+        StatusCode::OK
+    );
+}
+
+#[test]
+fn private_files_are_hidden_from_other_callers() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        storage_canister_id,
+        controller,
+        ..
+    } = test_env;
+    let outsider = random_principal();
+
+    let content = b"salaries".to_vec();
+    upload_bytes(
+        pic,
+        controller,
+        storage_canister_id,
+        &content,
+        "/private/salaries.txt",
+    )
+    .expect("upload failed");
+    set_file_visibility(
+        pic,
+        controller,
+        storage_canister_id,
+        &set_file_visibility::Args {
+            file_path: "/private/salaries.txt".to_string(),
+            visibility: Visibility::Private,
+        },
+    )
+    .expect("set_file_visibility failed");
+
+    let metadata_args = get_file_metadata::Args {
+        file_path: "/private/salaries.txt".to_string(),
+    };
+    let result = get_file_metadata(pic, outsider, storage_canister_id, &metadata_args);
+    assert!(
+        matches!(
+            result,
+            Err(get_file_metadata::GetFileMetadataError::FileNotFound)
+        ),
+        "expected FileNotFound for an outsider, got {result:?}"
+    );
+    get_file_metadata(pic, controller, storage_canister_id, &metadata_args)
+        .expect("get_file_metadata by the controller failed");
+
+    let list_args = list_files::Args {
+        prefix: Some("/private".to_string()),
+        cursor: None,
+        limit: None,
+    };
+    assert!(list_files(pic, outsider, storage_canister_id, &list_args)
+        .files
+        .is_empty());
+    assert_eq!(
+        list_files(pic, controller, storage_canister_id, &list_args)
+            .files
+            .len(),
+        1
+    );
+
+    // Knowing the hash of private content doesn't let an uploader claim it.
+    grant_role(
+        pic,
+        controller,
+        storage_canister_id,
+        &RoleAssignment {
+            principal: outsider,
+            role: Role::Uploader,
+            path_prefix: Some("/public/".to_string()),
+        },
+    )
+    .expect("grant_role failed");
+    let resp = init_upload(
+        pic,
+        outsider,
+        storage_canister_id,
+        &init_upload::Args {
+            file_path: "/public/salaries.txt".to_string(),
+            file_hash: hex::encode(Sha256::digest(&content)),
+            file_size: content.len() as u64,
+            chunk_size: None,
+            chunk_hashes: None,
+            metadata: None,
+            content_type: None,
+            headers: None,
+            encoding: None,
+            expires_at: None,
+            visibility: None,
+            bucket: None,
+        },
+    )
+    .expect("init_upload failed");
+    assert_eq!(resp.deduplicated, Some(false));
+}
+
+#[test]
+fn private_version_needs_a_token_after_the_file_goes_public() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        storage_canister_id,
+        controller,
+        ..
+    } = test_env;
+
+    let path = "/private/plan.txt";
+    let secret = b"v1: the secret plan".to_vec();
+    upload_bytes(pic, controller, storage_canister_id, &secret, path).expect("upload failed");
+    let set_visibility = |pic: &mut PocketIc, visibility| {
+        set_file_visibility(
+            pic,
+            controller,
+            storage_canister_id,
+            &set_file_visibility::Args {
+                file_path: path.to_string(),
+                visibility,
+            },
+        )
+        .expect("set_file_visibility failed");
+    };
+    set_visibility(pic, Visibility::Private);
+
+    let published = b"v2: the public plan".to_vec();
+    init_reupload(
+        pic,
+        controller,
+        storage_canister_id,
+        &init_reupload::Args {
+            file_path: path.to_string(),
+            file_hash: hex::encode(Sha256::digest(&published)),
+            file_size: published.len() as u64,
+            chunk_size: None,
+            changed_chunks: None,
+            content_type: None,
+            headers: None,
+            bucket: None,
+        },
+    )
+    .expect("init_reupload failed");
+    send_and_finalize(pic, controller, storage_canister_id, path, &published);
+    set_visibility(pic, Visibility::Public);
+
+    let response = raw_get(pic, storage_canister_id, path);
+    assert_eq!(response.status_code(), StatusCode::OK);
+    assert_eq!(response.body(), published.as_slice());
+    assert_eq!(
+        raw_get(pic, storage_canister_id, &format!("{path}?version=1")).status_code(),
+        StatusCode::FORBIDDEN
+    );
+
+    let now = pic.get_time().as_nanos_since_unix_epoch();
+    let query = create_download_token(
+        pic,
+        controller,
+        storage_canister_id,
+        &create_download_token::Args {
+            file_path: path.to_string(),
+            expires_at: now + Duration::from_secs(60 * 60).as_nanos() as u64,
+        },
+    )
+    .expect("create_download_token failed")
+    .query;
+    let response = raw_get(
+        pic,
+        storage_canister_id,
+        &format!("{path}?version=1&{query}"),
+    );
+    assert_eq!(response.status_code(), StatusCode::OK);
+    assert_eq!(response.body(), secret.as_slice());
+}
+
+#[test]
+fn moved_or_copied_variant_stays_private() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        storage_canister_id,
+        controller,
+        ..
+    } = test_env;
+
+    upload_bytes(
+        pic,
+        controller,
+        storage_canister_id,
+        b"the secret plan",
+        "/private/plan.txt",
+    )
+    .expect("upload failed");
+    set_file_visibility(
+        pic,
+        controller,
+        storage_canister_id,
+        &set_file_visibility::Args {
+            file_path: "/private/plan.txt".to_string(),
+            visibility: Visibility::Private,
+        },
+    )
+    .expect("set_file_visibility failed");
+
+    let compressed = b"\x1f\x8b pretend gzip".to_vec();
+    init_upload(
+        pic,
+        controller,
+        storage_canister_id,
+        &init_upload::Args {
+            file_path: "/private/plan.txt.gz".to_string(),
+            file_hash: hex::encode(Sha256::digest(&compressed)),
+            file_size: compressed.len() as u64,
+            chunk_size: None,
+            chunk_hashes: None,
+            metadata: None,
+            content_type: None,
+            headers: None,
+            encoding: Some(ContentEncoding::Gzip),
+            expires_at: None,
+            visibility: None,
+            bucket: None,
+        },
+    )
+    .expect("init_upload of the variant failed");
+    send_and_finalize(
+        pic,
+        controller,
+        storage_canister_id,
+        "/private/plan.txt.gz",
+        &compressed,
+    );
+
+    copy_file(
+        pic,
+        controller,
+        storage_canister_id,
+        &copy_file::Args {
+            from: "/private/plan.txt.gz".to_string(),
+            to: "/copied.gz".to_string(),
+            overwrite: false,
+            bucket: None,
+        },
+    )
+    .expect("copy_file failed");
+    move_file(
+        pic,
+        controller,
+        storage_canister_id,
+        &move_file::Args {
+            from: "/private/plan.txt.gz".to_string(),
+            to: "/moved.gz".to_string(),
+            overwrite: false,
+            bucket: None,
+        },
+    )
+    .expect("move_file failed");
+
+    for path in ["/copied.gz", "/moved.gz"] {
+        let metadata = get_file_metadata(
+            pic,
+            controller,
+            storage_canister_id,
+            &get_file_metadata::Args {
+                file_path: path.to_string(),
+            },
+        )
+        .expect("get_file_metadata failed");
+        assert_eq!(metadata.visibility, Visibility::Private);
+        assert_eq!(
+            raw_get(pic, storage_canister_id, path).status_code(),
+            StatusCode::FORBIDDEN
+        );
+    }
+}
+
+#[test]
+fn private_upload_progress_is_hidden_from_other_callers() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        storage_canister_id,
+        controller,
+        ..
+    } = test_env;
+    let outsider = random_principal();
+
+    let content = b"next year's salaries".to_vec();
+    init_upload(
+        pic,
+        controller,
+        storage_canister_id,
+        &init_upload::Args {
+            file_path: "/private/salaries.txt".to_string(),
+            file_hash: hex::encode(Sha256::digest(&content)),
+            file_size: content.len() as u64,
+            chunk_size: None,
+            chunk_hashes: None,
+            metadata: None,
+            content_type: None,
+            headers: None,
+            encoding: None,
+            expires_at: None,
+            visibility: Some(Visibility::Private),
+            bucket: None,
+        },
+    )
+    .expect("init_upload failed");
+
+    let status_args = get_upload_status::Args {
+        file_path: "/private/salaries.txt".to_string(),
+    };
+    let progress_args = get_finalization_progress::Args {
+        file_path: "/private/salaries.txt".to_string(),
+    };
+    get_upload_status(pic, controller, storage_canister_id, &status_args)
+        .expect("the uploader should see its upload");
+    get_finalization_progress(pic, controller, storage_canister_id, &progress_args)
+        .expect("the uploader should see its upload");
+
+    let result = get_upload_status(pic, outsider, storage_canister_id, &status_args);
+    assert!(
+        matches!(
+            result,
+            Err(get_upload_status::GetUploadStatusError::UploadNotFound)
+        ),
+        "expected UploadNotFound, got {result:?}"
+    );
+    let result = get_finalization_progress(pic, outsider, storage_canister_id, &progress_args);
+    assert!(
+        matches!(
+            result,
+            Err(get_finalization_progress::GetFinalizationProgressError::FileNotFound)
+        ),
+        "expected FileNotFound, got {result:?}"
+    );
+}
@@ -35,6 +35,14 @@ fn upload_custom_file(
             file_hash,
             file_size,
             chunk_size: None,
+            chunk_hashes: None,
+            metadata: None,
+            content_type: None,
+            headers: None,
+            encoding: None,
+            expires_at: None,
+            visibility: None,
+            bucket: None,
         }),
     )
     .expect("init_upload failed");
@@ -47,6 +55,8 @@ fn upload_custom_file(
             file_path: upload_path.to_string(),
             chunk_id: Nat::from(0u64),
             chunk_data: content.to_vec(),
+            chunk_hash: None,
+            bucket: None,
         }),
     )
     .expect("store_chunk failed");
@@ -57,6 +67,7 @@ fn upload_custom_file(
         storage_canister_id,
         &(finalize_upload::Args {
             file_path: upload_path.to_string(),
+            bucket: None,
         }),
     )
     .expect("finalize_upload failed");
@@ -84,6 +95,10 @@ fn test_init_reupload() {
             file_hash: "00".repeat(32),
             file_size: 100,
             chunk_size: None,
+            changed_chunks: None,
+            content_type: None,
+            headers: None,
+            bucket: None,
         }),
     );
     match reupload_nonexistent_resp {
@@ -112,6 +127,10 @@ fn test_init_reupload() {
             file_hash: "00".repeat(32),
             file_size: incorrect_size,
             chunk_size: None,
+            changed_chunks: None,
+            content_type: None,
+            headers: None,
+            bucket: None,
         }),
     );
     match reupload_incorrect_size_resp {
@@ -136,6 +155,10 @@ fn test_init_reupload() {
             file_hash: new_hash,
             file_size: new_content.len() as u64,
             chunk_size: None,
+            changed_chunks: None,
+            content_type: None,
+            headers: None,
+            bucket: None,
         }),
     );
     assert!(
@@ -152,6 +175,8 @@ fn test_init_reupload() {
             file_path: upload_path.to_string(),
             chunk_id: Nat::from(0u64),
             chunk_data: new_content.clone(),
+            chunk_hash: None,
+            bucket: None,
         }),
     )
     .expect("store_chunk for reupload failed");
@@ -162,6 +187,7 @@ fn test_init_reupload() {
         storage_canister_id,
         &(finalize_upload::Args {
             file_path: upload_path.to_string(),
+            bucket: None,
         }),
     )
     .expect("finalize_upload for reupload failed");
@@ -197,6 +223,7 @@ fn test_remove_file() {
         storage_canister_id,
         &(remove_file::Args {
             file_path: upload_path.to_string(),
+            bucket: None,
         }),
     );
     match remove_nonexistent_resp {
@@ -228,6 +255,7 @@ fn test_remove_file() {
         storage_canister_id,
         &(remove_file::Args {
             file_path: upload_path.to_string(),
+            bucket: None,
         }),
     );
     assert!(remove_resp.is_ok(), "remove_file failed");
@@ -300,6 +328,7 @@ fn test_remove_file_and_upload_another() {
         storage_canister_id,
         &(remove_file::Args {
             file_path: upload_path_1.to_string(),
+            bucket: None,
         }),
     );
     assert!(remove_resp.is_ok(), "remove_file failed");
@@ -403,6 +432,10 @@ fn test_reupload_caching() {
             file_hash: modified_hash.clone(),
             file_size: modified_content.len() as u64,
             chunk_size: None,
+            changed_chunks: None,
+            content_type: None,
+            headers: None,
+            bucket: None,
         }),
     );
     assert!(reupload_resp.is_ok());
@@ -419,6 +452,7 @@ fn test_reupload_caching() {
         storage_canister_id,
         &(cancel_upload::Args {
             file_path: upload_path.to_string(),
+            bucket: None,
         }),
     );
     assert!(cancel_resp.is_ok());
@@ -438,6 +472,10 @@ fn test_reupload_caching() {
             file_hash: modified_hash,
             file_size: modified_content.len() as u64,
             chunk_size: None,
+            changed_chunks: None,
+            content_type: None,
+            headers: None,
+            bucket: None,
         }),
     );
     assert!(reupload_resp2.is_ok());
@@ -451,6 +489,8 @@ fn test_reupload_caching() {
             file_path: upload_path.to_string(),
             chunk_id: Nat::from(0u64),
             chunk_data: modified_content.clone(),
+            chunk_hash: None,
+            bucket: None,
         }),
     )
     .expect("store_chunk for reupload failed");
@@ -467,6 +507,7 @@ fn test_reupload_caching() {
         storage_canister_id,
         &(finalize_upload::Args {
             file_path: upload_path.to_string(),
+            bucket: None,
         }),
     )
     .expect("finalize_upload for reupload failed");
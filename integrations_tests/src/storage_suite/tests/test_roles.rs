@@ -0,0 +1,386 @@
+//! Role assignments limit what a principal may do to the paths under its
+//! scope: Uploaders create files, Editors replace them, Deleters remove them.
+
+use crate::client::storage::{
+    cancel_upload, copy_file, finalize_upload, grant_role, init_reupload, init_upload,
+    list_buckets, list_principal_usage, list_roles, remove_file, revoke_role, store_chunk,
+};
+use crate::storage_suite::setup::default_test_setup;
+use crate::storage_suite::setup::setup::TestEnv;
+use crate::utils::{random_principal, upload_bytes};
+use bity_ic_storage_canister_api::cancel_upload;
+use bity_ic_storage_canister_api::copy_file;
+use bity_ic_storage_canister_api::finalize_upload;
+use bity_ic_storage_canister_api::init_reupload;
+use bity_ic_storage_canister_api::init_upload;
+use bity_ic_storage_canister_api::remove_file;
+use bity_ic_storage_canister_api::revoke_role;
+use bity_ic_storage_canister_api::store_chunk;
+use bity_ic_storage_canister_api::types::storage::{BucketAccessError, Role, RoleAssignment};
+use candid::Nat;
+use sha2::{Digest, Sha256};
+use std::panic::AssertUnwindSafe;
+
+#[test]
+fn roles_are_enforced_per_path() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        storage_canister_id,
+        controller,
+        ..
+    } = test_env;
+    let uploader = random_principal();
+    let deleter = random_principal();
+
+    let uploader_role = RoleAssignment {
+        principal: uploader,
+        role: Role::Uploader,
+        path_prefix: Some("/images/".to_string()),
+    };
+    grant_role(pic, controller, storage_canister_id, &uploader_role).expect("grant_role failed");
+    grant_role(
+        pic,
+        controller,
+        storage_canister_id,
+        &RoleAssignment {
+            principal: deleter,
+            role: Role::Deleter,
+            path_prefix: None,
+        },
+    )
+    .expect("grant_role failed");
+
+    let roles = list_roles(pic, controller, storage_canister_id, &());
+    assert_eq!(roles.assignments.len(), 2);
+    assert_eq!(
+        roles.assignments[0].path_prefix.as_deref(),
+        Some("images"),
+        "path prefix should be stored without slashes"
+    );
+
+    let content = b"not a cat".to_vec();
+    upload_bytes(
+        pic,
+        uploader,
+        storage_canister_id,
+        &content,
+        "/images/cat.txt",
+    )
+    .expect("upload under the uploader's prefix failed");
+
+    let outside = init_upload(
+        pic,
+        uploader,
+        storage_canister_id,
+        &init_upload::Args {
+            file_path: "/images-old/cat.txt".to_string(),
+            file_hash: hex::encode(Sha256::digest(&content)),
+            file_size: content.len() as u64,
+            chunk_size: None,
+            chunk_hashes: None,
+            metadata: None,
+            content_type: None,
+            headers: None,
+            encoding: None,
+            expires_at: None,
+            visibility: None,
+            bucket: None,
+        },
+    );
+    assert!(
+        matches!(
+            outside,
+            Err(init_upload::InitUploadError::BucketAccess(
+                BucketAccessError::NotAuthorized
+            ))
+        ),
+        "expected NotAuthorized outside the prefix, got {outside:?}"
+    );
+
+    // Uploaders only create files; replacing one takes an Editor.
+    let reupload = init_reupload(
+        pic,
+        uploader,
+        storage_canister_id,
+        &init_reupload::Args {
+            file_path: "/images/cat.txt".to_string(),
+            file_hash: hex::encode(Sha256::digest(b"a cat")),
+            file_size: 5,
+            chunk_size: None,
+            changed_chunks: None,
+            content_type: None,
+            headers: None,
+            bucket: None,
+        },
+    );
+    assert!(
+        matches!(
+            reupload,
+            Err(init_reupload::InitReuploadError::BucketAccess(
+                BucketAccessError::NotAuthorized
+            ))
+        ),
+        "expected NotAuthorized for an uploader's reupload, got {reupload:?}"
+    );
+
+    let remove = remove_file::Args {
+        file_path: "/images/cat.txt".to_string(),
+        bucket: None,
+    };
+    let result = remove_file(pic, uploader, storage_canister_id, &remove);
+    assert!(
+        matches!(
+            result,
+            Err(remove_file::RemoveFileError::BucketAccess(
+                BucketAccessError::NotAuthorized
+            ))
+        ),
+        "expected NotAuthorized for an uploader's removal, got {result:?}"
+    );
+    remove_file(pic, deleter, storage_canister_id, &remove)
+        .expect("remove_file by the deleter failed");
+
+    revoke_role(pic, controller, storage_canister_id, &uploader_role).expect("revoke_role failed");
+    let result = revoke_role(pic, controller, storage_canister_id, &uploader_role);
+    assert!(
+        matches!(result, Err(revoke_role::RevokeRoleError::RoleNotFound)),
+        "expected RoleNotFound, got {result:?}"
+    );
+    assert_eq!(
+        list_roles(pic, controller, storage_canister_id, &())
+            .assignments
+            .len(),
+        1
+    );
+}
+
+#[test]
+fn copy_needs_a_role_on_the_source() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        storage_canister_id,
+        controller,
+        ..
+    } = test_env;
+    let uploader = random_principal();
+
+    grant_role(
+        pic,
+        controller,
+        storage_canister_id,
+        &RoleAssignment {
+            principal: uploader,
+            role: Role::Uploader,
+            path_prefix: Some("/images/".to_string()),
+        },
+    )
+    .expect("grant_role failed");
+
+    upload_bytes(
+        pic,
+        controller,
+        storage_canister_id,
+        b"top secret",
+        "/secret/key.txt",
+    )
+    .expect("upload outside the uploader's prefix failed");
+    upload_bytes(
+        pic,
+        uploader,
+        storage_canister_id,
+        b"not a cat",
+        "/images/cat.txt",
+    )
+    .expect("upload under the uploader's prefix failed");
+
+    let result = copy_file(
+        pic,
+        uploader,
+        storage_canister_id,
+        &copy_file::Args {
+            from: "/secret/key.txt".to_string(),
+            to: "/images/key.txt".to_string(),
+            overwrite: false,
+            bucket: None,
+        },
+    );
+    assert!(
+        matches!(
+            result,
+            Err(copy_file::CopyFileError::BucketAccess(
+                BucketAccessError::NotAuthorized
+            ))
+        ),
+        "expected NotAuthorized copying from outside the prefix, got {result:?}"
+    );
+
+    copy_file(
+        pic,
+        uploader,
+        storage_canister_id,
+        &copy_file::Args {
+            from: "/images/cat.txt".to_string(),
+            to: "/images/cat-copy.txt".to_string(),
+            overwrite: false,
+            bucket: None,
+        },
+    )
+    .expect("copy within the prefix failed");
+}
+
+#[test]
+fn pending_reupload_needs_an_editor() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        storage_canister_id,
+        controller,
+        ..
+    } = test_env;
+    let uploader = random_principal();
+    let editor = random_principal();
+    for (principal, role) in [(uploader, Role::Uploader), (editor, Role::Editor)] {
+        grant_role(
+            pic,
+            controller,
+            storage_canister_id,
+            &RoleAssignment {
+                principal,
+                role,
+                path_prefix: Some("/images/".to_string()),
+            },
+        )
+        .expect("grant_role failed");
+    }
+
+    let path = "/images/cat.png";
+    upload_bytes(pic, controller, storage_canister_id, b"old cat", path).expect("upload failed");
+    let replacement = b"new cat".to_vec();
+    init_reupload(
+        pic,
+        editor,
+        storage_canister_id,
+        &init_reupload::Args {
+            file_path: path.to_string(),
+            file_hash: hex::encode(Sha256::digest(&replacement)),
+            file_size: replacement.len() as u64,
+            chunk_size: None,
+            changed_chunks: None,
+            content_type: None,
+            headers: None,
+            bucket: None,
+        },
+    )
+    .expect("init_reupload failed");
+
+    let result = store_chunk(
+        pic,
+        uploader,
+        storage_canister_id,
+        &store_chunk::Args {
+            file_path: path.to_string(),
+            chunk_id: Nat::from(0u64),
+            chunk_data: b"bad cat".to_vec(),
+            chunk_hash: None,
+            bucket: None,
+        },
+    );
+    assert!(
+        matches!(
+            result,
+            Err(store_chunk::StoreChunkError::BucketAccess(
+                BucketAccessError::NotAuthorized
+            ))
+        ),
+        "expected NotAuthorized storing into a reupload, got {result:?}"
+    );
+    let result = finalize_upload(
+        pic,
+        uploader,
+        storage_canister_id,
+        &finalize_upload::Args {
+            file_path: path.to_string(),
+            bucket: None,
+        },
+    );
+    assert!(
+        matches!(
+            result,
+            Err(finalize_upload::FinalizeUploadError::BucketAccess(
+                BucketAccessError::NotAuthorized
+            ))
+        ),
+        "expected NotAuthorized finalizing a reupload, got {result:?}"
+    );
+    let cancel = cancel_upload::Args {
+        file_path: path.to_string(),
+        bucket: None,
+    };
+    let result = cancel_upload(pic, uploader, storage_canister_id, &cancel);
+    assert!(
+        matches!(
+            result,
+            Err(cancel_upload::CancelUploadError::BucketAccess(
+                BucketAccessError::NotAuthorized
+            ))
+        ),
+        "expected NotAuthorized cancelling a reupload, got {result:?}"
+    );
+
+    cancel_upload(pic, editor, storage_canister_id, &cancel).expect("cancel_upload failed");
+}
+
+#[test]
+fn role_and_usage_listings_need_an_admin() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        storage_canister_id,
+        controller,
+        ..
+    } = test_env;
+    let uploader = random_principal();
+    grant_role(
+        pic,
+        controller,
+        storage_canister_id,
+        &RoleAssignment {
+            principal: uploader,
+            role: Role::Uploader,
+            path_prefix: None,
+        },
+    )
+    .expect("grant_role failed");
+
+    // The query client panics when the guard rejects the call.
+    let results = [
+        (
+            "list_roles",
+            std::panic::catch_unwind(AssertUnwindSafe(|| {
+                list_roles(pic, uploader, storage_canister_id, &());
+            })),
+        ),
+        (
+            "list_principal_usage",
+            std::panic::catch_unwind(AssertUnwindSafe(|| {
+                list_principal_usage(pic, uploader, storage_canister_id, &());
+            })),
+        ),
+        (
+            "list_buckets",
+            std::panic::catch_unwind(AssertUnwindSafe(|| {
+                list_buckets(pic, uploader, storage_canister_id, &());
+            })),
+        ),
+    ];
+    for (method, result) in results {
+        assert!(
+            result.is_err(),
+            "{method} should be rejected for a non-admin"
+        );
+    }
+
+    list_roles(pic, controller, storage_canister_id, &());
+}
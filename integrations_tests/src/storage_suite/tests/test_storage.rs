@@ -10,7 +10,7 @@ use icrc_ledger_types::icrc::generic_value::ICRC3Value as Icrc3Value;
 use sha2::{Digest, Sha256};
 
 use crate::storage_suite::setup::setup::TestEnv;
-use crate::utils::{setup_http_client, upload_file};
+use crate::utils::{setup_http_client, upload_bytes, upload_file};
 use crate::{storage_suite::setup::default_test_setup, utils::tick_n_blocks};
 use bytes::Bytes;
 use http::Request;
@@ -155,6 +155,14 @@ fn test_duplicate_upload() {
             file_hash: "dummy_hash".to_string(),
             file_size: 1024,
             chunk_size: None,
+            chunk_hashes: None,
+            metadata: None,
+            content_type: None,
+            headers: None,
+            encoding: None,
+            expires_at: None,
+            visibility: None,
+            bucket: None,
         }),
     );
 
@@ -201,6 +209,14 @@ fn test_duplicate_chunk_upload() {
             file_hash: format!("{:x}", file_hash),
             file_size,
             chunk_size: None,
+            chunk_hashes: None,
+            metadata: None,
+            content_type: None,
+            headers: None,
+            encoding: None,
+            expires_at: None,
+            visibility: None,
+            bucket: None,
         }),
     )
     .expect("Failed to initialize upload");
@@ -221,6 +237,8 @@ fn test_duplicate_chunk_upload() {
                 file_path: "/test.png".to_string(),
                 chunk_id: Nat::from(chunk_index as u64),
                 chunk_data: chunk.to_vec(),
+                chunk_hash: None,
+                bucket: None,
             }),
         )
         .expect("Failed to store chunk");
@@ -234,6 +252,8 @@ fn test_duplicate_chunk_upload() {
                 file_path: "/test.png".to_string(),
                 chunk_id: Nat::from(chunk_index as u64),
                 chunk_data: chunk.to_vec(),
+                chunk_hash: None,
+                bucket: None,
             }),
         );
 
@@ -252,6 +272,7 @@ fn test_duplicate_chunk_upload() {
         storage_canister_id,
         &(finalize_upload::Args {
             file_path: "/test.png".to_string(),
+            bucket: None,
         }),
     )
     .expect("Failed to finalize upload");
@@ -294,6 +315,14 @@ fn test_finalize_upload_missing_chunk() {
             file_hash: format!("{:x}", file_hash),
             file_size,
             chunk_size: None,
+            chunk_hashes: None,
+            metadata: None,
+            content_type: None,
+            headers: None,
+            encoding: None,
+            expires_at: None,
+            visibility: None,
+            bucket: None,
         }),
     );
 
@@ -312,6 +341,8 @@ fn test_finalize_upload_missing_chunk() {
                 file_path: "/test.png".to_string(),
                 chunk_id: Nat::from(chunk_index as u64),
                 chunk_data: chunk.to_vec(),
+                chunk_hash: None,
+                bucket: None,
             }),
         );
 
@@ -326,6 +357,7 @@ fn test_finalize_upload_missing_chunk() {
         storage_canister_id,
         &(finalize_upload::Args {
             file_path: "/test.png".to_string(),
+            bucket: None,
         }),
     );
 
@@ -381,6 +413,14 @@ fn test_upload_with_incorrect_chunk() {
             file_hash: format!("{:x}", file_hash),
             file_size,
             chunk_size: None,
+            chunk_hashes: None,
+            metadata: None,
+            content_type: None,
+            headers: None,
+            encoding: None,
+            expires_at: None,
+            visibility: None,
+            bucket: None,
         }),
     );
 
@@ -403,6 +443,8 @@ fn test_upload_with_incorrect_chunk() {
                 file_path: "/test.png".to_string(),
                 chunk_id: Nat::from(chunk_index as u64),
                 chunk_data: chunk,
+                chunk_hash: None,
+                bucket: None,
             }),
         );
 
@@ -425,6 +467,7 @@ fn test_upload_with_incorrect_chunk() {
         storage_canister_id,
         &(finalize_upload::Args {
             file_path: "/test.png".to_string(),
+            bucket: None,
         }),
     );
 
@@ -480,6 +523,14 @@ fn test_cancel_upload() {
             file_hash: format!("{:x}", file_hash),
             file_size,
             chunk_size: None,
+            chunk_hashes: None,
+            metadata: None,
+            content_type: None,
+            headers: None,
+            encoding: None,
+            expires_at: None,
+            visibility: None,
+            bucket: None,
         }),
     );
 
@@ -498,6 +549,7 @@ fn test_cancel_upload() {
         storage_canister_id,
         &(cancel_upload::Args {
             file_path: "/test_cancel.png".to_string(),
+            bucket: None,
         }),
     );
 
@@ -518,6 +570,7 @@ fn test_cancel_upload() {
         storage_canister_id,
         &(finalize_upload::Args {
             file_path: "/test.png".to_string(),
+            bucket: None,
         }),
     );
 
@@ -568,6 +621,7 @@ fn test_non_governance_principal_rejection() {
                     storage_canister_id,
                     &(cancel_upload::Args {
                         file_path: file_path.clone(),
+                        bucket: None,
                     }),
                 )
                 .map(|_| ())
@@ -587,6 +641,14 @@ fn test_non_governance_principal_rejection() {
                         file_hash: file_hash.clone(),
                         file_size,
                         chunk_size: None,
+                        chunk_hashes: None,
+                        metadata: None,
+                        content_type: None,
+                        headers: None,
+                        encoding: None,
+                        expires_at: None,
+                        visibility: None,
+                        bucket: None,
                     }),
                 )
                 .map(|_| ())
@@ -605,6 +667,8 @@ fn test_non_governance_principal_rejection() {
                         file_path: file_path.clone(),
                         chunk_id: Nat::from(0 as u64),
                         chunk_data: chunk_data.clone(),
+                        chunk_hash: None,
+                        bucket: None,
                     }),
                 )
                 .map(|_| ())
@@ -621,6 +685,7 @@ fn test_non_governance_principal_rejection() {
                     storage_canister_id,
                     &(finalize_upload::Args {
                         file_path: file_path.clone(),
+                        bucket: None,
                     }),
                 )
                 .map(|_| ())
@@ -664,21 +729,24 @@ fn test_storage_scalability() {
     } = test_env;
 
     let file_path = "./src/storage_suite/assets/test.png";
+    let base_buffer = std::fs::read(file_path).expect("Failed to read file");
     let mut uploaded_buffers = Vec::new();
 
+    // Identical files would be deduplicated and take no space, so make each one unique.
+    let unique_buffer = |i: u8| {
+        let mut buffer = base_buffer.clone();
+        buffer.push(i);
+        buffer
+    };
+
     // Upload first two files - should succeed
     for i in 0..7 {
         let upload_path = format!("/test_scalability_{}.png", i);
         println!("Attempting to upload file {} at path: {}", i, upload_path);
 
-        let buffer = upload_file(
-            pic,
-            controller,
-            storage_canister_id,
-            file_path,
-            &upload_path,
-        )
-        .expect(&format!("Upload {} failed", i));
+        let buffer = unique_buffer(i);
+        upload_bytes(pic, controller, storage_canister_id, &buffer, &upload_path)
+            .expect(&format!("Upload {} failed", i));
 
         uploaded_buffers.push((upload_path.clone(), buffer));
     }
@@ -694,11 +762,11 @@ fn test_storage_scalability() {
     let upload_path = "/test_scalability_7.png";
     println!("Attempting to upload third file at path: {}", upload_path);
 
-    let result = upload_file(
+    let result = upload_bytes(
         pic,
         controller,
         storage_canister_id,
-        file_path,
+        &unique_buffer(7),
         &upload_path,
     );
 
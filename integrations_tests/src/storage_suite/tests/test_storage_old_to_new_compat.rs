@@ -84,6 +84,14 @@ fn assert_upgrade_preserves_files(historical_wasm: CanisterWasm, label: &str) {
             file_hash: file_hash.clone(),
             file_size,
             chunk_size: None,
+            chunk_hashes: None,
+            metadata: None,
+            content_type: None,
+            headers: None,
+            encoding: None,
+            expires_at: None,
+            visibility: None,
+            bucket: None,
         }),
     )
     .expect("init_upload on historical wasm failed");
@@ -101,6 +109,8 @@ fn assert_upgrade_preserves_files(historical_wasm: CanisterWasm, label: &str) {
                 file_path: target_path.clone(),
                 chunk_id: Nat::from(chunk_index as u64),
                 chunk_data: chunk.to_vec(),
+                chunk_hash: None,
+                bucket: None,
             }),
         )
         .expect("store_chunk on historical wasm failed");
@@ -114,6 +124,7 @@ fn assert_upgrade_preserves_files(historical_wasm: CanisterWasm, label: &str) {
         storage_canister_id,
         &(finalize_upload::Args {
             file_path: target_path.clone(),
+            bucket: None,
         }),
     )
     .expect("finalize_upload on historical wasm failed");
@@ -122,6 +133,8 @@ fn assert_upgrade_preserves_files(historical_wasm: CanisterWasm, label: &str) {
     let upgrade_args = Args::Upgrade(UpgradeArgs {
         version: BuildVersion::min(),
         commit_hash: format!("upgrade-from-{label}"),
+        version_retention: None,
+        trash_retention_secs: None,
     });
     upgrade_storage_canister(pic, storage_canister_id, upgrade_args, controller);
 
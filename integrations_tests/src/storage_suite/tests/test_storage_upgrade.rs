@@ -58,6 +58,14 @@ fn test_storage_after_update_simple() {
             file_hash: format!("{:x}", file_hash),
             file_size,
             chunk_size: None,
+            chunk_hashes: None,
+            metadata: None,
+            content_type: None,
+            headers: None,
+            encoding: None,
+            expires_at: None,
+            visibility: None,
+            bucket: None,
         }),
     );
 
@@ -84,6 +92,8 @@ fn test_storage_after_update_simple() {
                 file_path: "/test.png".to_string(),
                 chunk_id: Nat::from(chunk_index as u64),
                 chunk_data: chunk.to_vec(),
+                chunk_hash: None,
+                bucket: None,
             }),
         );
 
@@ -106,6 +116,7 @@ fn test_storage_after_update_simple() {
         storage_canister_id,
         &(finalize_upload::Args {
             file_path: "/test.png".to_string(),
+            bucket: None,
         }),
     );
 
@@ -121,6 +132,8 @@ fn test_storage_after_update_simple() {
     let storage_upgrade_args = Args::Upgrade(UpgradeArgs {
         version: BuildVersion::min(),
         commit_hash: "commit_hash 2".to_string(),
+        version_retention: None,
+        trash_retention_secs: None,
     });
 
     upgrade_storage_canister(pic, storage_canister_id, storage_upgrade_args, controller);
@@ -0,0 +1,285 @@
+//! `remove_file` moves a file to the trash, from where it can be restored or
+//! purged until the hourly sweep deletes it after the retention period.
+
+use std::time::Duration;
+
+use crate::client::storage::{
+    get_stored_files_size, http_request, init_reupload, init_upload, list_trash, purge_file,
+    remove_file, restore_file,
+};
+use crate::storage_suite::setup::default_test_setup;
+use crate::storage_suite::setup::setup::TestEnv;
+use crate::utils::{send_and_finalize, tick_n_blocks, upload_bytes};
+use bity_ic_storage_canister_api::init_reupload;
+use bity_ic_storage_canister_api::init_upload;
+use bity_ic_storage_canister_api::list_trash;
+use bity_ic_storage_canister_api::purge_file;
+use bity_ic_storage_canister_api::remove_file;
+use bity_ic_storage_canister_api::restore_file;
+use bity_ic_storage_canister_api::types::storage::ContentEncoding;
+use candid::Principal;
+use ic_http_certification::{HttpRequest, StatusCode};
+use pocket_ic::PocketIc;
+use sha2::{Digest, Sha256};
+
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+fn raw_status(
+    pic: &PocketIc,
+    controller: Principal,
+    canister: Principal,
+    path: &str,
+) -> StatusCode {
+    let req = HttpRequest::get(path)
+        .with_headers(vec![(
+            "host".to_string(),
+            format!("{}.raw.icp0.io", canister),
+        )])
+        .build();
+    http_request(pic, controller, canister, &req).status_code()
+}
+
+fn trashed_paths(pic: &PocketIc, controller: Principal, canister: Principal) -> Vec<String> {
+    list_trash(
+        pic,
+        controller,
+        canister,
+        &list_trash::Args { prefix: None },
+    )
+    .files
+    .into_iter()
+    .map(|file| file.file_path)
+    .collect()
+}
+
+fn remove(pic: &mut PocketIc, controller: Principal, canister: Principal, path: &str) {
+    remove_file(
+        pic,
+        controller,
+        canister,
+        &(remove_file::Args {
+            file_path: path.to_string(),
+            bucket: None,
+        }),
+    )
+    .expect("remove_file failed");
+}
+
+#[test]
+fn removed_file_can_be_restored() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        storage_canister_id,
+        controller,
+        ..
+    } = test_env;
+
+    let content = b"precious".to_vec();
+    upload_bytes(pic, controller, storage_canister_id, &content, "/keep.txt")
+        .expect("upload failed");
+    remove(pic, controller, storage_canister_id, "/keep.txt");
+
+    assert_eq!(
+        raw_status(pic, controller, storage_canister_id, "/keep.txt"),
+        StatusCode::NOT_FOUND
+    );
+    assert_eq!(
+        trashed_paths(pic, controller, storage_canister_id),
+        vec!["/keep.txt".to_string()]
+    );
+
+    restore_file(
+        pic,
+        controller,
+        storage_canister_id,
+        &restore_file::Args {
+            file_path: "/keep.txt".to_string(),
+        },
+    )
+    .expect("restore_file failed");
+
+    let req = HttpRequest::get("/keep.txt")
+        .with_headers(vec![(
+            "host".to_string(),
+            format!("{}.raw.icp0.io", storage_canister_id),
+        )])
+        .build();
+    let resp = http_request(pic, controller, storage_canister_id, &req);
+    assert_eq!(resp.status_code(), StatusCode::OK);
+    assert_eq!(resp.body(), &content);
+    assert!(trashed_paths(pic, controller, storage_canister_id).is_empty());
+}
+
+#[test]
+fn restore_refuses_an_occupied_path_and_purge_empties_the_trash() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        storage_canister_id,
+        controller,
+        ..
+    } = test_env;
+
+    upload_bytes(pic, controller, storage_canister_id, b"old", "/page.html")
+        .expect("upload failed");
+    remove(pic, controller, storage_canister_id, "/page.html");
+    upload_bytes(pic, controller, storage_canister_id, b"new", "/page.html")
+        .expect("upload failed");
+
+    let result = restore_file(
+        pic,
+        controller,
+        storage_canister_id,
+        &restore_file::Args {
+            file_path: "/page.html".to_string(),
+        },
+    );
+    assert!(
+        matches!(
+            result,
+            Err(restore_file::RestoreFileError::DestinationExists)
+        ),
+        "expected DestinationExists, got {result:?}"
+    );
+
+    purge_file(
+        pic,
+        controller,
+        storage_canister_id,
+        &purge_file::Args {
+            file_path: "/page.html".to_string(),
+        },
+    )
+    .expect("purge_file failed");
+    assert!(trashed_paths(pic, controller, storage_canister_id).is_empty());
+
+    let result = purge_file(
+        pic,
+        controller,
+        storage_canister_id,
+        &purge_file::Args {
+            file_path: "/page.html".to_string(),
+        },
+    );
+    assert!(
+        matches!(result, Err(purge_file::PurgeFileError::NotInTrash)),
+        "expected NotInTrash, got {result:?}"
+    );
+    assert_eq!(
+        raw_status(pic, controller, storage_canister_id, "/page.html"),
+        StatusCode::OK
+    );
+}
+
+#[test]
+fn sweep_purges_trash_after_retention() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        storage_canister_id,
+        controller,
+        ..
+    } = test_env;
+
+    upload_bytes(pic, controller, storage_canister_id, b"bye", "/tmp.txt").expect("upload failed");
+    remove(pic, controller, storage_canister_id, "/tmp.txt");
+
+    pic.advance_time(6 * DAY);
+    tick_n_blocks(pic, 10);
+    assert_eq!(
+        trashed_paths(pic, controller, storage_canister_id),
+        vec!["/tmp.txt".to_string()]
+    );
+
+    pic.advance_time(2 * DAY);
+    tick_n_blocks(pic, 10);
+    assert!(trashed_paths(pic, controller, storage_canister_id).is_empty());
+}
+
+#[test]
+fn purge_frees_the_bytes_of_variants_and_versions() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        storage_canister_id,
+        controller,
+        ..
+    } = test_env;
+
+    let initial = get_stored_files_size(pic, controller, storage_canister_id, &());
+
+    upload_bytes(
+        pic,
+        controller,
+        storage_canister_id,
+        b"first draft",
+        "/doc.txt",
+    )
+    .expect("upload failed");
+
+    let second = b"second draft".to_vec();
+    init_reupload(
+        pic,
+        controller,
+        storage_canister_id,
+        &init_reupload::Args {
+            file_path: "/doc.txt".to_string(),
+            file_hash: hex::encode(Sha256::digest(&second)),
+            file_size: second.len() as u64,
+            chunk_size: None,
+            changed_chunks: None,
+            content_type: None,
+            headers: None,
+            bucket: None,
+        },
+    )
+    .expect("init_reupload failed");
+    send_and_finalize(pic, controller, storage_canister_id, "/doc.txt", &second);
+
+    let compressed = b"\x1f\x8b pretend gzip".to_vec();
+    init_upload(
+        pic,
+        controller,
+        storage_canister_id,
+        &init_upload::Args {
+            file_path: "/doc.txt.gz".to_string(),
+            file_hash: hex::encode(Sha256::digest(&compressed)),
+            file_size: compressed.len() as u64,
+            chunk_size: None,
+            chunk_hashes: None,
+            metadata: None,
+            content_type: None,
+            headers: None,
+            encoding: Some(ContentEncoding::Gzip),
+            expires_at: None,
+            visibility: None,
+            bucket: None,
+        },
+    )
+    .expect("init_upload of the variant failed");
+    send_and_finalize(
+        pic,
+        controller,
+        storage_canister_id,
+        "/doc.txt.gz",
+        &compressed,
+    );
+
+    let stored = get_stored_files_size(pic, controller, storage_canister_id, &());
+    assert!(stored.physical_size_bytes > initial.physical_size_bytes);
+
+    remove(pic, controller, storage_canister_id, "/doc.txt");
+    purge_file(
+        pic,
+        controller,
+        storage_canister_id,
+        &purge_file::Args {
+            file_path: "/doc.txt".to_string(),
+        },
+    )
+    .expect("purge_file failed");
+
+    let purged = get_stored_files_size(pic, controller, storage_canister_id, &());
+    assert_eq!(purged, initial);
+}
@@ -0,0 +1,137 @@
+//! In-flight chunks live in the stable-memory staging region, not on the heap,
+//! so they must survive an upgrade and be promoted intact at finalize time
+//! regardless of the order in which they arrived.
+
+use crate::client::storage::{finalize_upload, http_request, init_upload, store_chunk};
+use crate::storage_suite::setup::default_test_setup;
+use crate::storage_suite::setup::setup::TestEnv;
+use crate::storage_suite::setup::setup_storage::upgrade_storage_canister;
+use crate::utils::tick_n_blocks;
+use bity_ic_storage_canister_api::finalize_upload;
+use bity_ic_storage_canister_api::init_upload;
+use bity_ic_storage_canister_api::lifecycle::Args;
+use bity_ic_storage_canister_api::post_upgrade::UpgradeArgs;
+use bity_ic_storage_canister_api::store_chunk;
+use bity_ic_types::BuildVersion;
+use candid::Nat;
+use ic_http_certification::{HttpRequest, StatusCode};
+use sha2::{Digest, Sha256};
+
+#[test]
+fn staged_chunks_survive_upgrade_and_finalize_out_of_order() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        storage_canister_id,
+        controller,
+        ..
+    } = test_env;
+
+    let upload_path = "/staged.bin";
+    let chunk_size: u64 = 1024;
+    let content: Vec<u8> = (0..3 * chunk_size).map(|i| (i % 251) as u8).collect();
+
+    let mut hasher = Sha256::new();
+    hasher.update(&content);
+    let file_hash = format!("{:x}", hasher.finalize());
+
+    init_upload(
+        pic,
+        controller,
+        storage_canister_id,
+        &(init_upload::Args {
+            file_path: upload_path.to_string(),
+            file_hash,
+            file_size: content.len() as u64,
+            chunk_size: Some(chunk_size),
+            chunk_hashes: None,
+            metadata: None,
+            content_type: None,
+            headers: None,
+            encoding: None,
+            expires_at: None,
+            visibility: None,
+            bucket: None,
+        }),
+    )
+    .expect("init_upload failed");
+
+    let store = |pic: &mut pocket_ic::PocketIc, index: u64| {
+        let start = (index * chunk_size) as usize;
+        let end = start + chunk_size as usize;
+        store_chunk(
+            pic,
+            controller,
+            storage_canister_id,
+            &(store_chunk::Args {
+                file_path: upload_path.to_string(),
+                chunk_id: Nat::from(index),
+                chunk_data: content[start..end].to_vec(),
+                chunk_hash: None,
+                bucket: None,
+            }),
+        )
+        .expect("store_chunk failed");
+    };
+
+    // Last chunk first, then upgrade with the upload still in flight.
+    store(pic, 2);
+    store(pic, 0);
+
+    upgrade_storage_canister(
+        pic,
+        storage_canister_id,
+        Args::Upgrade(UpgradeArgs {
+            version: BuildVersion::min(),
+            commit_hash: "staging-upgrade-test".to_string(),
+            version_retention: None,
+            trash_retention_secs: None,
+        }),
+        controller,
+    );
+    tick_n_blocks(pic, 2);
+
+    // A chunk that was staged before the upgrade is still recorded as received.
+    let duplicate = store_chunk(
+        pic,
+        controller,
+        storage_canister_id,
+        &(store_chunk::Args {
+            file_path: upload_path.to_string(),
+            chunk_id: Nat::from(0u64),
+            chunk_data: content[..chunk_size as usize].to_vec(),
+            chunk_hash: None,
+            bucket: None,
+        }),
+    );
+    assert!(
+        matches!(
+            duplicate,
+            Err(store_chunk::StoreChunkError::InvalidChunkData)
+        ),
+        "re-sending a staged chunk after upgrade should be rejected, got {duplicate:?}"
+    );
+
+    store(pic, 1);
+
+    finalize_upload(
+        pic,
+        controller,
+        storage_canister_id,
+        &(finalize_upload::Args {
+            file_path: upload_path.to_string(),
+            bucket: None,
+        }),
+    )
+    .expect("finalize_upload failed");
+
+    let req = HttpRequest::get(upload_path)
+        .with_headers(vec![(
+            "host".to_string(),
+            format!("{}.raw.icp0.io", storage_canister_id),
+        )])
+        .build();
+    let resp = http_request(pic, controller, storage_canister_id, &req);
+    assert_eq!(resp.status_code(), StatusCode::OK);
+    assert_eq!(resp.body(), &content);
+}
@@ -0,0 +1,116 @@
+//! `get_upload_status` reports which chunks are still missing so that an
+//! interrupted uploader can resume instead of starting over.
+
+use crate::client::storage::{finalize_upload, get_upload_status, init_upload, store_chunk};
+use crate::storage_suite::setup::default_test_setup;
+use crate::storage_suite::setup::setup::TestEnv;
+use bity_ic_storage_canister_api::finalize_upload;
+use bity_ic_storage_canister_api::get_upload_status;
+use bity_ic_storage_canister_api::init_upload;
+use bity_ic_storage_canister_api::store_chunk;
+use bity_ic_storage_canister_api::types::storage::UploadState;
+use candid::Nat;
+use sha2::{Digest, Sha256};
+
+const GC_TTL_NANOS: u64 = 24 * 60 * 60 * 1_000_000_000;
+
+#[test]
+fn upload_status_lists_missing_chunks() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        storage_canister_id,
+        controller,
+        ..
+    } = test_env;
+
+    let upload_path = "/resumable.bin";
+    let chunk_size: u64 = 1024;
+    let content: Vec<u8> = (0..3 * chunk_size).map(|i| (i % 251) as u8).collect();
+    let chunks: Vec<&[u8]> = content.chunks(chunk_size as usize).collect();
+
+    let status_args = get_upload_status::Args {
+        file_path: upload_path.to_string(),
+    };
+    let status = get_upload_status(pic, controller, storage_canister_id, &status_args);
+    assert!(
+        matches!(
+            status,
+            Err(get_upload_status::GetUploadStatusError::UploadNotFound)
+        ),
+        "unknown path should not have a status, got {status:?}"
+    );
+
+    init_upload(
+        pic,
+        controller,
+        storage_canister_id,
+        &(init_upload::Args {
+            file_path: upload_path.to_string(),
+            file_hash: hex::encode(Sha256::digest(&content)),
+            file_size: content.len() as u64,
+            chunk_size: Some(chunk_size),
+            chunk_hashes: None,
+            metadata: None,
+            content_type: None,
+            headers: None,
+            encoding: None,
+            expires_at: None,
+            visibility: None,
+            bucket: None,
+        }),
+    )
+    .expect("init_upload failed");
+
+    let store = |pic: &mut pocket_ic::PocketIc, index: usize| {
+        store_chunk(
+            pic,
+            controller,
+            storage_canister_id,
+            &(store_chunk::Args {
+                file_path: upload_path.to_string(),
+                chunk_id: Nat::from(index as u64),
+                chunk_data: chunks[index].to_vec(),
+                chunk_hash: None,
+                bucket: None,
+            }),
+        )
+        .expect("store_chunk failed");
+    };
+
+    store(pic, 1);
+
+    let status = get_upload_status(pic, controller, storage_canister_id, &status_args)
+        .expect("get_upload_status failed");
+    assert_eq!(status.state, UploadState::InProgress);
+    assert_eq!(status.file_size, content.len() as u64);
+    assert_eq!(status.received_size, chunk_size);
+    assert_eq!(status.chunk_size, chunk_size);
+    assert_eq!(status.num_chunks, 3);
+    assert_eq!(status.missing_chunks, vec![0, 2]);
+    assert_eq!(
+        status.gc_deadline,
+        Some(status.init_timestamp + GC_TTL_NANOS)
+    );
+
+    for index in status.missing_chunks {
+        store(pic, index as usize);
+    }
+
+    finalize_upload(
+        pic,
+        controller,
+        storage_canister_id,
+        &(finalize_upload::Args {
+            file_path: upload_path.to_string(),
+            bucket: None,
+        }),
+    )
+    .expect("finalize_upload failed");
+
+    let status = get_upload_status(pic, controller, storage_canister_id, &status_args)
+        .expect("get_upload_status failed");
+    assert_eq!(status.state, UploadState::Finalized);
+    assert!(status.missing_chunks.is_empty());
+    assert_eq!(status.gc_deadline, None);
+}
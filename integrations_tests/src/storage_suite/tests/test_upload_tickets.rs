@@ -0,0 +1,173 @@
+//! An upload ticket lets a principal without a role upload directly, under
+//! the ticket's path, within its size and until it expires.
+
+use std::time::Duration;
+
+use crate::client::storage::{
+    cancel_upload, create_upload_ticket, init_reupload, init_upload, revoke_upload_ticket,
+};
+use crate::storage_suite::setup::default_test_setup;
+use crate::storage_suite::setup::setup::TestEnv;
+use crate::utils::{random_principal, upload_bytes};
+use bity_ic_storage_canister_api::cancel_upload;
+use bity_ic_storage_canister_api::create_upload_ticket;
+use bity_ic_storage_canister_api::init_reupload;
+use bity_ic_storage_canister_api::init_upload;
+use bity_ic_storage_canister_api::revoke_upload_ticket;
+use bity_ic_storage_canister_api::types::storage::BucketAccessError;
+use sha2::{Digest, Sha256};
+
+fn init_args(path: &str, content: &[u8]) -> init_upload::Args {
+    init_upload::Args {
+        file_path: path.to_string(),
+        file_hash: hex::encode(Sha256::digest(content)),
+        file_size: content.len() as u64,
+        chunk_size: None,
+        chunk_hashes: None,
+        metadata: None,
+        content_type: None,
+        headers: None,
+        encoding: None,
+        expires_at: None,
+        visibility: None,
+        bucket: None,
+    }
+}
+
+#[test]
+fn ticket_holder_uploads_within_the_ticket() {
+    let mut test_env: TestEnv = default_test_setup();
+    let TestEnv {
+        ref mut pic,
+        storage_canister_id,
+        controller,
+        ..
+    } = test_env;
+    let user = random_principal();
+
+    let now = pic.get_time().as_nanos_since_unix_epoch();
+    let ticket_args = |max_bytes: u64| create_upload_ticket::Args {
+        holder: user,
+        file_path: "/avatars/user-1/".to_string(),
+        is_prefix: true,
+        max_bytes,
+        expires_at: now + Duration::from_secs(60 * 60).as_nanos() as u64,
+    };
+    let ticket_id = create_upload_ticket(pic, controller, storage_canister_id, &ticket_args(20))
+        .expect("create_upload_ticket failed")
+        .ticket_id;
+
+    upload_bytes(
+        pic,
+        user,
+        storage_canister_id,
+        &[7u8; 12],
+        "/avatars/user-1/a.png",
+    )
+    .expect("upload with the ticket failed");
+
+    let result = init_upload(
+        pic,
+        user,
+        storage_canister_id,
+        &init_args("/avatars/user-1/b.png", &[8u8; 9]),
+    );
+    assert!(
+        matches!(result, Err(init_upload::InitUploadError::QuotaExceeded)),
+        "expected QuotaExceeded past the ticket's bytes, got {result:?}"
+    );
+
+    // Use up the ticket; a second one still has room.
+    upload_bytes(
+        pic,
+        user,
+        storage_canister_id,
+        &[9u8; 8],
+        "/avatars/user-1/c.png",
+    )
+    .expect("upload of the ticket's last bytes failed");
+    create_upload_ticket(pic, controller, storage_canister_id, &ticket_args(10))
+        .expect("create_upload_ticket failed");
+    upload_bytes(
+        pic,
+        user,
+        storage_canister_id,
+        &[8u8; 9],
+        "/avatars/user-1/b.png",
+    )
+    .expect("upload with the second ticket failed");
+
+    // A reupload someone else started is not the holder's to touch.
+    let replacement = [6u8; 12];
+    init_reupload(
+        pic,
+        controller,
+        storage_canister_id,
+        &init_reupload::Args {
+            file_path: "/avatars/user-1/a.png".to_string(),
+            file_hash: hex::encode(Sha256::digest(replacement)),
+            file_size: replacement.len() as u64,
+            chunk_size: None,
+            changed_chunks: None,
+            content_type: None,
+            headers: None,
+            bucket: None,
+        },
+    )
+    .expect("init_reupload failed");
+    let result = cancel_upload(
+        pic,
+        user,
+        storage_canister_id,
+        &cancel_upload::Args {
+            file_path: "/avatars/user-1/a.png".to_string(),
+            bucket: None,
+        },
+    );
+    assert!(
+        matches!(
+            result,
+            Err(cancel_upload::CancelUploadError::BucketAccess(
+                BucketAccessError::NotAuthorized
+            ))
+        ),
+        "expected NotAuthorized on another's reupload, got {result:?}"
+    );
+
+    let result = init_upload(
+        pic,
+        user,
+        storage_canister_id,
+        &init_args("/avatars/user-2/a.png", &[8u8; 4]),
+    );
+    assert!(
+        matches!(
+            result,
+            Err(init_upload::InitUploadError::BucketAccess(
+                BucketAccessError::NotAuthorized
+            ))
+        ),
+        "expected NotAuthorized outside the ticket's path, got {result:?}"
+    );
+
+    revoke_upload_ticket(
+        pic,
+        controller,
+        storage_canister_id,
+        &revoke_upload_ticket::Args { ticket_id },
+    )
+    .expect("revoke_upload_ticket failed");
+    let result = revoke_upload_ticket(
+        pic,
+        controller,
+        storage_canister_id,
+        &revoke_upload_ticket::Args { ticket_id },
+    );
+    assert!(
+        matches!(
+            result,
+            Err(revoke_upload_ticket::RevokeUploadTicketError::TicketNotFound)
+        ),
+        "expected TicketNotFound, got {result:?}"
+    );
+}
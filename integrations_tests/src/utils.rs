@@ -30,6 +30,40 @@ pub fn tick_n_blocks(pic: &PocketIc, times: u32) {
     }
 }
 
+/// Send `content` as the single chunk of an initialized upload at `path`, then
+/// finalize it.
+pub fn send_and_finalize(
+    pic: &mut PocketIc,
+    controller: Principal,
+    canister: Principal,
+    path: &str,
+    content: &[u8],
+) {
+    store_chunk(
+        pic,
+        controller,
+        canister,
+        &(store_chunk::Args {
+            file_path: path.to_string(),
+            chunk_id: Nat::from(0u64),
+            chunk_data: content.to_vec(),
+            chunk_hash: None,
+            bucket: None,
+        }),
+    )
+    .expect("store_chunk failed");
+    finalize_upload(
+        pic,
+        controller,
+        canister,
+        &(finalize_upload::Args {
+            file_path: path.to_string(),
+            bucket: None,
+        }),
+    )
+    .expect("finalize_upload failed");
+}
+
 pub fn upload_file(
     pic: &mut PocketIc,
     controller: Principal,
@@ -43,11 +77,23 @@ pub fn upload_file(
     file.read_to_end(&mut buffer)
         .map_err(|e| format!("Failed to read file: {:?}", e))?;
 
+    upload_bytes(pic, controller, storage_canister_id, &buffer, upload_path)?;
+
+    Ok(buffer)
+}
+
+pub fn upload_bytes(
+    pic: &mut PocketIc,
+    controller: Principal,
+    storage_canister_id: Principal,
+    buffer: &[u8],
+    upload_path: &str,
+) -> Result<(), String> {
     let file_size = buffer.len() as u64;
 
     // Calculate SHA-256 hash
     let mut hasher = Sha256::new();
-    hasher.update(&buffer);
+    hasher.update(buffer);
     let file_hash = hasher.finalize();
 
     let init_upload_resp = init_upload(
@@ -59,12 +105,25 @@ pub fn upload_file(
             file_hash: format!("{:x}", file_hash),
             file_size,
             chunk_size: None,
+            chunk_hashes: None,
+            metadata: None,
+            content_type: None,
+            headers: None,
+            encoding: None,
+            expires_at: None,
+            visibility: None,
+            bucket: None,
         }),
     )
     .map_err(|e| format!("init_upload error: {:?}", e))?;
 
     println!("init_upload_resp: {:?}", init_upload_resp);
 
+    // Identical content already stored: the file is finalized without any chunks.
+    if init_upload_resp.deduplicated == Some(true) {
+        return Ok(());
+    }
+
     let mut offset = 0;
     let chunk_size = 1024 * 1024;
     let mut chunk_index = 0;
@@ -79,6 +138,8 @@ pub fn upload_file(
                 file_path: upload_path.to_string(),
                 chunk_id: Nat::from(chunk_index as u64),
                 chunk_data: chunk.to_vec(),
+                chunk_hash: None,
+                bucket: None,
             }),
         )
         .map_err(|e| format!("store_chunk error: {:?}", e))?;
@@ -95,13 +156,14 @@ pub fn upload_file(
         storage_canister_id,
         &(finalize_upload::Args {
             file_path: upload_path.to_string(),
+            bucket: None,
         }),
     )
     .map_err(|e| format!("finalize_upload error: {:?}", e))?;
 
     println!("finalize_upload_resp: {:?}", finalize_upload_resp);
 
-    Ok(buffer)
+    Ok(())
 }
 
 pub const T: Cycles = 1_000_000_000_000;